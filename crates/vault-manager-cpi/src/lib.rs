@@ -0,0 +1,111 @@
+//! Helpers for calling the Collateral Vault Manager from other Anchor programs.
+//!
+//! An integration must be registered with `add_authorized_program`. It signs
+//! every lock, unlock and transfer with its caller authority PDA, derived from
+//! `[b"vault_caller"]` under its own program id; [`caller_authority`] gives the
+//! address and bump. Reads go through `get_vault_state`, which works for any
+//! program and does not depend on the `CollateralVault` account layout.
+//!
+//! ```ignore
+//! let (_, bump) = vault_manager_cpi::caller_authority(&crate::ID);
+//! vault_manager_cpi::lock(
+//!     ctx.accounts.vault_manager_program.to_account_info(),
+//!     vault_manager_cpi::LockCollateral { /* ... */ },
+//!     bump,
+//!     margin,
+//! )?;
+//! let state = vault_manager_cpi::get_vault_state(
+//!     ctx.accounts.vault_manager_program.to_account_info(),
+//!     vault_manager_cpi::GetVaultState { /* ... */ },
+//!     crate::ID,
+//! )?;
+//! ```
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::get_return_data;
+
+pub use vault_manager;
+pub use vault_manager::cpi::accounts::{
+    GetVaultState, LockCollateral, TransferCollateral, UnlockCollateral,
+};
+pub use vault_manager::{VaultState, VaultStatus};
+
+/// Derive the PDA a program signs vault CPIs with, and its bump
+pub fn caller_authority(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[vault_manager::VaultAuthority::CALLER_SEED], program_id)
+}
+
+/// Lock `amount` of a vault's available collateral for the calling program
+pub fn lock<'info>(
+    vault_manager_program: AccountInfo<'info>,
+    accounts: LockCollateral<'info>,
+    caller_bump: u8,
+    amount: u64,
+) -> Result<()> {
+    let bump = [caller_bump];
+    let seeds: &[&[u8]] = &[vault_manager::VaultAuthority::CALLER_SEED, &bump];
+    vault_manager::cpi::lock_collateral(
+        CpiContext::new_with_signer(vault_manager_program, accounts, &[seeds]),
+        amount,
+    )
+}
+
+/// Release `amount` of the collateral the calling program locked in a vault
+pub fn unlock<'info>(
+    vault_manager_program: AccountInfo<'info>,
+    accounts: UnlockCollateral<'info>,
+    caller_bump: u8,
+    amount: u64,
+) -> Result<()> {
+    let bump = [caller_bump];
+    let seeds: &[&[u8]] = &[vault_manager::VaultAuthority::CALLER_SEED, &bump];
+    vault_manager::cpi::unlock_collateral(
+        CpiContext::new_with_signer(vault_manager_program, accounts, &[seeds]),
+        amount,
+    )
+}
+
+/// Move `amount` of available collateral between two vaults of the same mint
+pub fn transfer<'info>(
+    vault_manager_program: AccountInfo<'info>,
+    accounts: TransferCollateral<'info>,
+    caller_bump: u8,
+    amount: u64,
+) -> Result<()> {
+    let bump = [caller_bump];
+    let seeds: &[&[u8]] = &[vault_manager::VaultAuthority::CALLER_SEED, &bump];
+    vault_manager::cpi::transfer_collateral(
+        CpiContext::new_with_signer(vault_manager_program, accounts, &[seeds]),
+        amount,
+    )
+}
+
+/// Read a vault, with the collateral `program` has locked in it
+pub fn get_vault_state<'info>(
+    vault_manager_program: AccountInfo<'info>,
+    accounts: GetVaultState<'info>,
+    program: Pubkey,
+) -> Result<VaultState> {
+    vault_manager::cpi::get_vault_state(CpiContext::new(vault_manager_program, accounts), program)?;
+    read_vault_state()
+}
+
+/// Decode the `VaultState` the last `get_vault_state` CPI left in return data.
+/// Fields appended by newer versions of the vault manager are skipped.
+pub fn read_vault_state() -> Result<VaultState> {
+    let (program_id, data) = get_return_data().ok_or(VaultCpiError::MissingVaultState)?;
+    require_keys_eq!(program_id, vault_manager::ID, VaultCpiError::MissingVaultState);
+
+    // `deserialize` rather than `try_from_slice`, which rejects trailing bytes
+    VaultState::deserialize(&mut data.as_slice())
+        .map_err(|_| error!(VaultCpiError::InvalidVaultState))
+}
+
+#[error_code(offset = 7000)]
+pub enum VaultCpiError {
+    #[msg("The vault manager returned no vault state")]
+    MissingVaultState,
+
+    #[msg("The vault state returned by the vault manager could not be decoded")]
+    InvalidVaultState,
+}
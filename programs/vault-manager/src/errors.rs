@@ -17,12 +17,9 @@ pub enum VaultError {
     #[msg("Unauthorized program attempting to access vault")]
     UnauthorizedProgram,
     
-    #[msg("Vault has open positions, cannot withdraw")]
+    #[msg("Vault has open positions, cannot close")]
     HasOpenPositions,
     
-    #[msg("Overflow in balance calculation")]
-    OverflowError,
-    
     #[msg("Underflow in balance calculation")]
     UnderflowError,
     
@@ -35,9 +32,6 @@ pub enum VaultError {
     #[msg("Only admin can modify authority")]
     UnauthorizedAdmin,
     
-    #[msg("Maximum authorized programs limit reached")]
-    MaxAuthorizedProgramsReached,
-    
     #[msg("Program not found in authorized list")]
     ProgramNotAuthorized,
     
@@ -46,4 +40,109 @@ pub enum VaultError {
     
     #[msg("Numerical overflow occurred")]
     NumericalOverflow,
+    
+    #[msg("Settlement amount exceeds collateral locked by this program")]
+    InsufficientLockedBalance,
+    
+    #[msg("Vault must be empty with no locked collateral to close")]
+    VaultNotEmpty,
+    
+    #[msg("Token mint does not match the vault's collateral mint")]
+    MintMismatch,
+    
+    #[msg("Withdrawal timelock has not expired yet")]
+    WithdrawalTimelockActive,
+    
+    #[msg("Instant withdrawals are disabled, request a timelocked withdrawal")]
+    WithdrawalTimelockRequired,
+    
+    #[msg("Withdrawal delay is out of range")]
+    InvalidWithdrawalDelay,
+    
+    #[msg("Protocol is paused")]
+    ProtocolPaused,
+    
+    #[msg("Vault is frozen")]
+    VaultFrozen,
+    
+    #[msg("Signer is not the pending admin")]
+    NotPendingAdmin,
+    
+    #[msg("Invalid multisig signer set or threshold")]
+    InvalidMultisigConfig,
+    
+    #[msg("Signer is not a member of the admin multisig")]
+    NotMultisigSigner,
+    
+    #[msg("Signer already approved this proposal")]
+    ProposalAlreadyApproved,
+    
+    #[msg("Proposal does not have enough approvals")]
+    ProposalThresholdNotMet,
+    
+    #[msg("Proposal was created for a previous signer set")]
+    StaleProposal,
+    
+    #[msg("Program label is too long")]
+    ProgramLabelTooLong,
+    
+    #[msg("Lock would exceed the calling program's maximum lock")]
+    ProgramLockLimitExceeded,
+    
+    #[msg("Deposit would exceed the per-vault deposit cap")]
+    VaultDepositCapExceeded,
+    
+    #[msg("Deposit would exceed the protocol TVL cap")]
+    TvlCapExceeded,
+    
+    #[msg("Withdrawal window is out of range")]
+    InvalidWithdrawalWindow,
+    
+    #[msg("Vault withdrawal rate limit reached, request a queued withdrawal or retry later")]
+    VaultWithdrawalRateLimited,
+    
+    #[msg("Protocol withdrawal rate limit reached, request a queued withdrawal or retry later")]
+    GlobalWithdrawalRateLimited,
+    
+    #[msg("Protocol fee is out of range")]
+    InvalidFee,
+    
+    #[msg("Token account is not a treasury account for this mint")]
+    InvalidTreasuryAccount,
+    
+    #[msg("Treasury account holds no fees")]
+    NoFeesToCollect,
+    
+    #[msg("No vault holds yield shares for this mint")]
+    NoYieldShares,
+    
+    #[msg("Mint has a Token-2022 extension vaults do not support")]
+    UnsupportedMintExtension,
+    
+    #[msg("Vault already uses the current account layout")]
+    VaultAlreadyMigrated,
+    
+    #[msg("Batch accounts do not match the amounts or repeat a vault")]
+    InvalidBatch,
+    
+    #[msg("Borrow exceeds the vault's available collateral")]
+    InsufficientBorrowBalance,
+    
+    #[msg("Vault already has an outstanding borrow")]
+    BorrowOutstanding,
+    
+    #[msg("Vault has no outstanding borrow to repay")]
+    NoOutstandingBorrow,
+    
+    #[msg("No repay to the vault follows the borrow in this transaction")]
+    MissingRepay,
+    
+    #[msg("Only the locks of a disabled program can be force-unlocked")]
+    ProgramStillEnabled,
+    
+    #[msg("Vault earns from a yield index that was not passed")]
+    YieldIndexRequired,
+    
+    #[msg("Vault predates mints and sub-accounts and cannot be migrated")]
+    UnsupportedLegacyLayout,
 }
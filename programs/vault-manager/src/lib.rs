@@ -1,6 +1,15 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer, Mint};
-use anchor_spl::associated_token::AssociatedToken;
+use anchor_lang::Discriminator;
+use anchor_lang::solana_program::program::set_return_data;
+use anchor_lang::solana_program::sysvar::instructions as instructions_sysvar;
+use anchor_lang::system_program::{create_account, transfer, CreateAccount, Transfer};
+use anchor_spl::associated_token::{self, AssociatedToken};
+use anchor_spl::token_interface::{
+    self, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+use spl_token_2022::extension::{
+    transfer_fee::TransferFeeConfig, BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+};
 
 pub mod state;
 pub mod errors;
@@ -8,29 +17,160 @@ pub mod errors;
 pub use state::*;
 pub use errors::*;
 
-declare_id!("VAULTmngr11111111111111111111111111111111");
+declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 
 #[program]
 pub mod vault_manager {
     use super::*;
 
-    /// Initialize a new collateral vault for a user
-    pub fn initialize_vault(ctx: Context<InitializeVault>) -> Result<()> {
+    /// Initialize a new collateral vault for a user, mint and sub-account index
+    pub fn initialize_vault(ctx: Context<InitializeVault>, sub_account: u16) -> Result<()> {
+        check_mint_extensions(&ctx.accounts.mint)?;
+        
         let vault = &mut ctx.accounts.vault;
         let clock = Clock::get()?;
         
         vault.owner = ctx.accounts.user.key();
+        vault.mint = ctx.accounts.mint.key();
+        vault.sub_account = sub_account;
         vault.token_account = ctx.accounts.vault_token_account.key();
         vault.total_balance = 0;
         vault.locked_balance = 0;
         vault.available_balance = 0;
+        vault.pending_balance = 0;
         vault.total_deposited = 0;
         vault.total_withdrawn = 0;
         vault.created_at = clock.unix_timestamp;
         vault.last_updated = clock.unix_timestamp;
+        vault.status = VaultStatus::Active;
+        vault.withdrawal_window = WithdrawalWindow::default();
+        vault.yield_shares = 0;
+        vault.yield_checkpoint = 0;
+        vault.unsettled_yield = 0;
         vault.bump = ctx.bumps.vault;
+        vault.version = CollateralVault::VERSION;
+        vault.sequence = 0;
+        vault.borrowed = 0;
+        vault.loss_checkpoint = 0;
+        vault.unpaid_loss = 0;
+        vault.unsettled_loss = 0;
+        vault.previous_window_withdrawn = 0;
+        vault.loss_weight = 0;
+        vault.pending_cover = 0;
+        
+        msg!(
+            "Vault initialized for user: {} (sub-account {})",
+            ctx.accounts.user.key(),
+            sub_account
+        );
+        Ok(())
+    }
+
+    /// Close an empty vault, returning the rent of the vault and its token account to the owner
+    pub fn close_vault(ctx: Context<CloseVault>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
         
-        msg!("Vault initialized for user: {}", ctx.accounts.user.key());
+        // Only an empty vault with nothing locked can be closed
+        require!(vault.locked_balance == 0, VaultError::HasOpenPositions);
+        require!(
+            vault.total_balance == 0 && ctx.accounts.vault_token_account.amount == 0,
+            VaultError::VaultNotEmpty
+        );
+
+        // Close the vault token account using CPI with PDA signer
+        let user_key = ctx.accounts.user.key();
+        let mint_key = vault.mint;
+        let sub_account = vault.sub_account.to_le_bytes();
+        let seeds = &[
+            b"vault",
+            user_key.as_ref(),
+            mint_key.as_ref(),
+            sub_account.as_ref(),
+            &[vault.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        token_interface::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.vault_token_account.to_account_info(),
+                destination: ctx.accounts.user.to_account_info(),
+                authority: vault.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+
+        // Closing is the vault's last mutation
+        let clock = Clock::get()?;
+        let sequence = vault.next_sequence().ok_or(VaultError::NumericalOverflow)?;
+        emit!(VaultClosedEvent {
+            user: user_key,
+            vault: vault.key(),
+            sequence,
+            slot: clock.slot,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!("Vault closed for user: {}", user_key);
+        Ok(())
+    }
+
+    /// Upgrade a vault created with an older account layout to the current
+    /// one. Permissionless: the payer covers the extra rent.
+    pub fn migrate_vault(ctx: Context<MigrateVault>) -> Result<()> {
+        let vault = ctx.accounts.vault.to_account_info();
+        {
+            let data = vault.try_borrow_data()?;
+            require!(
+                data.len() >= 8 && data[..8] == CollateralVault::DISCRIMINATOR,
+                VaultError::InvalidVaultState
+            );
+            // Layout 0 is the only older layout that can be migrated; later
+            // versions will also branch on the stored `version` here
+            match data.len() {
+                CollateralVault::LEGACY_LEN => {}
+                CollateralVault::LEN => return err!(VaultError::VaultAlreadyMigrated),
+                CollateralVault::BASELINE_LEN => return err!(VaultError::UnsupportedLegacyLayout),
+                _ => return err!(VaultError::InvalidVaultState),
+            }
+        }
+
+        // Top up rent before growing the account
+        let rent = Rent::get()?.minimum_balance(CollateralVault::LEN);
+        let shortfall = rent.saturating_sub(vault.lamports());
+        if shortfall > 0 {
+            transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: vault.clone(),
+                    },
+                ),
+                shortfall,
+            )?;
+        }
+        vault.realloc(CollateralVault::LEN, true)?;
+
+        // The new fields follow `bump`; all but `version` stay zeroed by the
+        // realloc
+        vault.try_borrow_mut_data()?[CollateralVault::LEGACY_LEN] = CollateralVault::VERSION;
+
+        // The migration is the first mutation the vault's sequence counts
+        let mut migrated = CollateralVault::try_deserialize(&mut &vault.try_borrow_data()?[..])?;
+        let sequence = migrated.next_sequence().ok_or(VaultError::NumericalOverflow)?;
+        migrated.try_serialize(&mut &mut vault.try_borrow_mut_data()?[..])?;
+
+        let clock = Clock::get()?;
+        emit!(VaultMigratedEvent {
+            vault: vault.key(),
+            version: CollateralVault::VERSION,
+            sequence,
+            slot: clock.slot,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!("Vault {} migrated to layout {}", vault.key(), CollateralVault::VERSION);
         Ok(())
     }
 
@@ -40,144 +180,923 @@ pub mod vault_manager {
         
         authority.authorized_programs = Vec::new();
         authority.admin = ctx.accounts.admin.key();
+        authority.pending_admin = None;
+        authority.withdrawal_delay = 0;
+        authority.paused = false;
+        authority.pause_reason = 0;
+        authority.max_vault_balance = 0;
+        authority.max_tvl = 0;
+        authority.withdrawal_window = 0;
+        authority.withdrawal_window_start = 0;
+        authority.max_vault_withdrawal = 0;
+        authority.max_global_withdrawal = 0;
+        authority.fee_bps = 0;
+        authority.borrow_fee_bps = 0;
         authority.bump = ctx.bumps.authority;
+
+        let counters = &mut ctx.accounts.counters;
+        counters.total_value_locked = 0;
+        counters.withdrawal_window = WithdrawalWindow::default();
+        counters.previous_window_withdrawn = 0;
+        counters.last_updated = Clock::get()?.unix_timestamp;
+        counters.bump = ctx.bumps.counters;
         
         msg!("Vault authority initialized");
         Ok(())
     }
 
-    /// Add an authorized program that can lock/unlock collateral
+    /// Authorize a program to lock/unlock collateral, or update its label and
+    /// maximum lock if it already has an `AuthorizedProgram` PDA
     pub fn add_authorized_program(
-        ctx: Context<ManageAuthority>,
+        ctx: Context<AddAuthorizedProgram>,
+        program_id: Pubkey,
+        label: String,
+        max_lock: u64,
+    ) -> Result<()> {
+        add_program(
+            &ctx.accounts.authority,
+            &mut ctx.accounts.authorized_program,
+            program_id,
+            label,
+            max_lock,
+        )
+    }
+
+    /// Disable an authorized program; its PDA is kept so it can be re-enabled
+    pub fn remove_authorized_program(
+        ctx: Context<RemoveAuthorizedProgram>,
+        program_id: Pubkey,
+    ) -> Result<()> {
+        remove_program(&ctx.accounts.authority, &mut ctx.accounts.authorized_program, program_id)
+    }
+
+    /// Move one entry of the legacy `authorized_programs` vector into its own
+    /// `AuthorizedProgram` PDA (permissionless; the payer funds the rent)
+    pub fn migrate_authorized_program(
+        ctx: Context<MigrateAuthorizedProgram>,
         program_id: Pubkey,
     ) -> Result<()> {
         let authority = &mut ctx.accounts.authority;
-        
-        require!(
-            authority.authorized_programs.len() < VaultAuthority::MAX_AUTHORIZED_PROGRAMS,
-            VaultError::MaxAuthorizedProgramsReached
-        );
-        
-        if !authority.authorized_programs.contains(&program_id) {
-            authority.authorized_programs.push(program_id);
-        }
-        
+        authority.authorized_programs.retain(|&x| x != program_id);
+
+        let authorized_program = &mut ctx.accounts.authorized_program;
+        authorized_program.program_id = program_id;
+        authorized_program.label = String::new();
+        authorized_program.max_lock = 0;
+        authorized_program.enabled = true;
+        authorized_program.created_at = Clock::get()?.unix_timestamp;
+        authorized_program.bump = ctx.bumps.authorized_program;
+
         emit!(AuthorityUpdatedEvent {
             authority: authority.key(),
             program: program_id,
             authorized: true,
-            timestamp: Clock::get()?.unix_timestamp,
+            label: String::new(),
+            max_lock: 0,
+            slot: Clock::get()?.slot,
+            timestamp: authorized_program.created_at,
+        });
+
+        msg!("Migrated authorized program: {}", program_id);
+        Ok(())
+    }
+
+    /// Propose a new admin; the handover completes when they call `accept_admin`
+    pub fn propose_admin(ctx: Context<ManageAuthority>, new_admin: Pubkey) -> Result<()> {
+        set_pending_admin(&mut ctx.accounts.authority, new_admin)
+    }
+
+    /// Accept a pending admin proposal, becoming the admin
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        let authority = &mut ctx.accounts.authority;
+        let previous_admin = authority.admin;
+        authority.admin = ctx.accounts.pending_admin.key();
+        authority.pending_admin = None;
+        
+        let clock = Clock::get()?;
+        emit!(AdminChangedEvent {
+            authority: authority.key(),
+            previous_admin,
+            new_admin: authority.admin,
+            slot: clock.slot,
+            timestamp: clock.unix_timestamp,
         });
         
-        msg!("Added authorized program: {}", program_id);
+        msg!("Admin changed from {} to {}", previous_admin, authority.admin);
         Ok(())
     }
 
-    /// Remove an authorized program
-    pub fn remove_authorized_program(
+    /// Set the timelock applied to withdrawals (0 re-enables instant withdrawals)
+    pub fn set_withdrawal_delay(
         ctx: Context<ManageAuthority>,
-        program_id: Pubkey,
+        withdrawal_delay: i64,
     ) -> Result<()> {
-        let authority = &mut ctx.accounts.authority;
+        update_withdrawal_delay(&mut ctx.accounts.authority, withdrawal_delay)
+    }
+
+    /// Set the per-vault and protocol-wide deposit caps (0 disables a cap)
+    pub fn set_deposit_caps(
+        ctx: Context<ManageAuthority>,
+        max_vault_balance: u64,
+        max_tvl: u64,
+    ) -> Result<()> {
+        update_deposit_caps(&mut ctx.accounts.authority, max_vault_balance, max_tvl)
+    }
+
+    /// Set the withdrawal rate limits: at most `max_vault_withdrawal` per vault and
+    /// `max_global_withdrawal` overall per `window` seconds (0 disables a limit)
+    pub fn set_withdrawal_limits(
+        ctx: Context<ManageAuthority>,
+        window: i64,
+        max_vault_withdrawal: u64,
+        max_global_withdrawal: u64,
+    ) -> Result<()> {
+        update_withdrawal_limits(
+            &mut ctx.accounts.authority,
+            window,
+            max_vault_withdrawal,
+            max_global_withdrawal,
+        )
+    }
+
+    /// Set the protocol fee charged on withdrawals and transfers, in basis points
+    pub fn set_fee(ctx: Context<ManageAuthority>, fee_bps: u16) -> Result<()> {
+        update_fee(&mut ctx.accounts.authority, fee_bps)
+    }
+
+    /// Set the fee `repay` charges on borrowed collateral, in basis points
+    pub fn set_borrow_fee(ctx: Context<ManageAuthority>, borrow_fee_bps: u16) -> Result<()> {
+        update_borrow_fee(&mut ctx.accounts.authority, borrow_fee_bps)
+    }
+
+    /// Sweep the full balance of a treasury token account into `destination`
+    pub fn collect_fees(ctx: Context<CollectFees>) -> Result<()> {
+        sweep_fees(
+            &ctx.accounts.treasury,
+            &ctx.accounts.treasury_token_account,
+            &ctx.accounts.destination,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+        )?;
+        Ok(())
+    }
+
+    /// Create the yield index of a mint and the reserve that holds its yield;
+    /// `include_locked` decides whether locked collateral earns yield too
+    pub fn initialize_yield_index(
+        ctx: Context<InitializeYieldIndex>,
+        include_locked: bool,
+    ) -> Result<()> {
+        check_mint_extensions(&ctx.accounts.mint)?;
         
-        authority.authorized_programs.retain(|&x| x != program_id);
+        let yield_index = new_yield_index(
+            ctx.accounts.mint.key(),
+            ctx.accounts.yield_reserve.key(),
+            include_locked,
+            ctx.bumps.yield_index,
+        )?;
+        ctx.accounts.yield_index.set_inner(yield_index);
+        Ok(())
+    }
+
+    /// Move `amount` tokens of yield into the reserve of a mint and raise its
+    /// index, so every share of that mint is worth proportionally more
+    pub fn distribute_yield(ctx: Context<DistributeYield>, amount: u64) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
         
-        emit!(AuthorityUpdatedEvent {
+        require!(ctx.accounts.yield_index.total_shares > 0, VaultError::NoYieldShares);
+        
+        // Only what reaches the reserve after a transfer fee is distributed
+        let received = transfer_tokens(
+            &ctx.accounts.token_program,
+            ctx.accounts.source_token_account.to_account_info(),
+            ctx.accounts.yield_reserve.to_account_info(),
+            ctx.accounts.admin.to_account_info(),
+            &ctx.accounts.mint,
+            amount,
+            &[],
+        )?;
+        
+        raise_yield_index(&mut ctx.accounts.yield_index, &mut ctx.accounts.counters, received)
+    }
+
+    /// Create the insurance fund of a collateral mint and its token account
+    pub fn initialize_insurance_fund(
+        ctx: Context<InitializeInsuranceFund>,
+        socialize_losses: bool,
+    ) -> Result<()> {
+        check_mint_extensions(&ctx.accounts.mint)?;
+        
+        let insurance_fund = new_insurance_fund(
+            ctx.accounts.mint.key(),
+            ctx.accounts.fund_token_account.key(),
+            socialize_losses,
+            ctx.bumps.insurance_fund,
+        )?;
+        ctx.accounts.insurance_fund.set_inner(insurance_fund);
+        Ok(())
+    }
+
+    /// Choose whether shortfalls an insurance fund cannot cover are socialized
+    /// across the vaults of its mint
+    pub fn set_socialize_losses(
+        ctx: Context<ManageInsuranceFund>,
+        socialize_losses: bool,
+    ) -> Result<()> {
+        let key = ctx.accounts.insurance_fund.key();
+        update_socialize_losses(&mut ctx.accounts.insurance_fund, key, socialize_losses)
+    }
+
+    /// Sweep a treasury token account into the insurance fund of its mint
+    pub fn collect_fees_to_insurance(ctx: Context<CollectFeesToInsurance>) -> Result<()> {
+        let received = sweep_fees(
+            &ctx.accounts.treasury,
+            &ctx.accounts.treasury_token_account,
+            &ctx.accounts.fund_token_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+        )?;
+
+        ctx.accounts.fund_token_account.reload()?;
+        let key = ctx.accounts.insurance_fund.key();
+        credit_fee_sweep(
+            &mut ctx.accounts.insurance_fund,
+            key,
+            received,
+            ctx.accounts.fund_token_account.amount,
+        )
+    }
+
+    /// Pause deposits, withdrawals, locks and transfers protocol-wide
+    pub fn pause(ctx: Context<ManageAuthority>, reason: u8) -> Result<()> {
+        set_paused(&mut ctx.accounts.authority, true, reason)
+    }
+
+    /// Lift a protocol-wide pause
+    pub fn unpause(ctx: Context<ManageAuthority>) -> Result<()> {
+        set_paused(&mut ctx.accounts.authority, false, 0)
+    }
+
+    /// Freeze a single vault: withdrawals, new locks and outgoing transfers
+    /// are rejected until it is unfrozen, while deposits and unlocks continue
+    pub fn freeze_vault(ctx: Context<FreezeVault>) -> Result<()> {
+        set_vault_status(
+            ctx.accounts.admin.key(),
+            &mut ctx.accounts.vault,
+            VaultStatus::Frozen,
+        )
+    }
+
+    /// Unfreeze a single vault
+    pub fn unfreeze_vault(ctx: Context<FreezeVault>) -> Result<()> {
+        set_vault_status(
+            ctx.accounts.admin.key(),
+            &mut ctx.accounts.vault,
+            VaultStatus::Active,
+        )
+    }
+
+    /// Release collateral a disabled program still holds locked in a vault,
+    /// for integrations that were wound down without unlocking
+    pub fn force_unlock(ctx: Context<ForceUnlock>, amount: u64) -> Result<()> {
+        force_release(
+            ctx.accounts.admin.key(),
+            &ctx.accounts.authorized_program,
+            &mut ctx.accounts.vault,
+            &mut ctx.accounts.program_lock,
+            ctx.accounts.yield_index.as_deref_mut(),
+            amount,
+        )
+    }
+
+    /// Hand the admin role to an M-of-N signer set; admin actions then go
+    /// through `create_proposal` / `approve_proposal` / `execute_proposal`
+    pub fn enable_multisig(
+        ctx: Context<EnableMultisig>,
+        signers: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        require!(
+            AdminMultisig::is_valid_config(&signers, threshold),
+            VaultError::InvalidMultisigConfig
+        );
+
+        let clock = Clock::get()?;
+        let multisig = &mut ctx.accounts.multisig;
+        multisig.signers = signers;
+        multisig.threshold = threshold;
+        // Re-enabling after a handback must not revive proposals of the old set
+        multisig.signer_set_seqno = multisig.signer_set_seqno
+            .checked_add(1)
+            .ok_or(VaultError::NumericalOverflow)?;
+        multisig.bump = ctx.bumps.multisig;
+
+        let authority = &mut ctx.accounts.authority;
+        let previous_admin = authority.admin;
+        authority.admin = multisig.key();
+        authority.pending_admin = None;
+
+        emit!(MultisigUpdatedEvent {
+            multisig: multisig.key(),
+            signers: multisig.signers.clone(),
+            threshold,
+            signer_set_seqno: multisig.signer_set_seqno,
+            slot: clock.slot,
+            timestamp: clock.unix_timestamp,
+        });
+        emit!(AdminChangedEvent {
             authority: authority.key(),
-            program: program_id,
-            authorized: false,
-            timestamp: Clock::get()?.unix_timestamp,
+            previous_admin,
+            new_admin: authority.admin,
+            slot: clock.slot,
+            timestamp: clock.unix_timestamp,
         });
-        
-        msg!("Removed authorized program: {}", program_id);
+
+        msg!("Admin handed to {}-of-{} multisig", threshold, multisig.signers.len());
+        Ok(())
+    }
+
+    /// Propose an admin action; the proposer's approval is counted immediately
+    pub fn create_proposal(ctx: Context<CreateProposal>, action: AdminAction) -> Result<()> {
+        match &action {
+            AdminAction::UpdateSigners { signers, threshold } => require!(
+                AdminMultisig::is_valid_config(signers, *threshold),
+                VaultError::InvalidMultisigConfig
+            ),
+            // Would otherwise only fail at execution, after the approvals
+            AdminAction::AddAuthorizedProgram { label, .. } => require!(
+                label.len() <= AuthorizedProgram::MAX_LABEL_LEN,
+                VaultError::ProgramLabelTooLong
+            ),
+            _ => {}
+        }
+
+        let clock = Clock::get()?;
+        let multisig = &mut ctx.accounts.multisig;
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.index = multisig.proposal_count;
+        proposal.proposer = ctx.accounts.proposer.key();
+        proposal.action = action;
+        proposal.approvals = vec![ctx.accounts.proposer.key()];
+        proposal.signer_set_seqno = multisig.signer_set_seqno;
+        proposal.created_at = clock.unix_timestamp;
+        proposal.bump = ctx.bumps.proposal;
+
+        multisig.proposal_count = multisig.proposal_count
+            .checked_add(1)
+            .ok_or(VaultError::NumericalOverflow)?;
+
+        emit!(ProposalCreatedEvent {
+            proposal: proposal.key(),
+            index: proposal.index,
+            proposer: proposal.proposer,
+            action: proposal.action.clone(),
+            slot: clock.slot,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!("Created admin proposal {}", proposal.index);
+        Ok(())
+    }
+
+    /// Approve an open proposal as a multisig signer
+    pub fn approve_proposal(ctx: Context<ApproveProposal>) -> Result<()> {
+        let signer = ctx.accounts.signer.key();
+        let proposal = &mut ctx.accounts.proposal;
+
+        require!(
+            !proposal.approvals.contains(&signer),
+            VaultError::ProposalAlreadyApproved
+        );
+        proposal.approvals.push(signer);
+
+        let clock = Clock::get()?;
+        emit!(ProposalApprovedEvent {
+            proposal: proposal.key(),
+            signer,
+            approvals: proposal.approvals.len() as u8,
+            slot: clock.slot,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!("Proposal {} approved by {}", proposal.index, signer);
+        Ok(())
+    }
+
+    /// Execute a proposal that reached the threshold, closing it to the proposer
+    pub fn execute_proposal(ctx: Context<ExecuteProposal>) -> Result<()> {
+        let proposal = &ctx.accounts.proposal;
+        require!(
+            proposal.approvals.len() >= ctx.accounts.multisig.threshold as usize,
+            VaultError::ProposalThresholdNotMet
+        );
+
+        let action = proposal.action.clone();
+        let authority = &mut ctx.accounts.authority;
+        match action.clone() {
+            AdminAction::AddAuthorizedProgram { program_id, label, max_lock } => {
+                let info = ctx
+                    .accounts
+                    .authorized_program
+                    .as_ref()
+                    .ok_or(VaultError::InvalidVaultState)?
+                    .to_account_info();
+                let mut record = load_or_create_authorized_program(
+                    &info,
+                    &ctx.accounts.executor,
+                    &ctx.accounts.system_program,
+                    &program_id,
+                )?;
+                add_program(authority, &mut record, program_id, label, max_lock)?;
+                record.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+            }
+            AdminAction::RemoveAuthorizedProgram { program_id } => {
+                let info = ctx
+                    .accounts
+                    .authorized_program
+                    .as_ref()
+                    .ok_or(VaultError::InvalidVaultState)?
+                    .to_account_info();
+                require_keys_eq!(*info.owner, crate::ID, VaultError::ProgramNotAuthorized);
+                let mut record = AuthorizedProgram::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+                remove_program(authority, &mut record, program_id)?;
+                record.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+            }
+            AdminAction::SetWithdrawalDelay { withdrawal_delay } => {
+                update_withdrawal_delay(authority, withdrawal_delay)?
+            }
+            AdminAction::SetDepositCaps { max_vault_balance, max_tvl } => {
+                update_deposit_caps(authority, max_vault_balance, max_tvl)?
+            }
+            AdminAction::SetWithdrawalLimits {
+                window,
+                max_vault_withdrawal,
+                max_global_withdrawal,
+            } => update_withdrawal_limits(
+                authority,
+                window,
+                max_vault_withdrawal,
+                max_global_withdrawal,
+            )?,
+            AdminAction::SetFee { fee_bps } => update_fee(authority, fee_bps)?,
+            AdminAction::SetBorrowFee { borrow_fee_bps } => {
+                update_borrow_fee(authority, borrow_fee_bps)?
+            }
+            AdminAction::CollectFees { treasury_token_account, destination } => {
+                let (Some(treasury), Some(source), Some(target), Some(mint), Some(token_program)) = (
+                    ctx.accounts.treasury.as_ref(),
+                    ctx.accounts.treasury_token_account.as_ref(),
+                    ctx.accounts.fee_destination.as_ref(),
+                    ctx.accounts.mint.as_ref(),
+                    ctx.accounts.token_program.as_ref(),
+                ) else {
+                    return err!(VaultError::InvalidVaultState);
+                };
+                require_keys_eq!(source.key(), treasury_token_account, VaultError::InvalidVaultState);
+                require_keys_eq!(target.key(), destination, VaultError::InvalidVaultState);
+                sweep_fees(treasury, source, target, mint, token_program)?;
+            }
+            AdminAction::InitializeYieldIndex { mint, include_locked } => {
+                let (
+                    Some(info),
+                    Some(reserve),
+                    Some(mint_account),
+                    Some(token_program),
+                    Some(associated_token_program),
+                ) = (
+                    ctx.accounts.yield_index.as_ref(),
+                    ctx.accounts.reserve_token_account.as_ref(),
+                    ctx.accounts.mint.as_ref(),
+                    ctx.accounts.token_program.as_ref(),
+                    ctx.accounts.associated_token_program.as_ref(),
+                ) else {
+                    return err!(VaultError::InvalidVaultState);
+                };
+                require_keys_eq!(mint_account.key(), mint, VaultError::MintMismatch);
+                check_mint_extensions(mint_account)?;
+
+                let (address, bump) = YieldIndex::find_address(&mint);
+                require_keys_eq!(info.key(), address, VaultError::InvalidVaultState);
+                let info = info.to_account_info();
+                create_pda_account(
+                    &info,
+                    &ctx.accounts.executor,
+                    &ctx.accounts.system_program,
+                    &[YieldIndex::SEED, mint.as_ref(), &[bump]],
+                    YieldIndex::LEN,
+                )?;
+                create_associated_account(
+                    reserve,
+                    &info,
+                    &ctx.accounts.executor,
+                    mint_account,
+                    token_program,
+                    associated_token_program,
+                    &ctx.accounts.system_program,
+                )?;
+
+                let record = new_yield_index(mint, reserve.key(), include_locked, bump)?;
+                record.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+            }
+            AdminAction::DistributeYield { mint, amount } => {
+                require!(amount > 0, VaultError::InvalidAmount);
+                let (
+                    Some(treasury),
+                    Some(source),
+                    Some(reserve),
+                    Some(info),
+                    Some(counters),
+                    Some(mint_account),
+                    Some(token_program),
+                ) = (
+                    ctx.accounts.treasury.as_ref(),
+                    ctx.accounts.treasury_token_account.as_ref(),
+                    ctx.accounts.fee_destination.as_ref(),
+                    ctx.accounts.yield_index.as_ref(),
+                    ctx.accounts.counters.as_mut(),
+                    ctx.accounts.mint.as_ref(),
+                    ctx.accounts.token_program.as_ref(),
+                ) else {
+                    return err!(VaultError::InvalidVaultState);
+                };
+                let address = YieldIndex::find_address(&mint).0;
+                require_keys_eq!(info.key(), address, VaultError::InvalidVaultState);
+                require_keys_eq!(*info.owner, crate::ID, VaultError::InvalidVaultState);
+                let mut record = YieldIndex::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+                require_keys_eq!(reserve.key(), record.reserve, VaultError::InvalidVaultState);
+                require!(record.total_shares > 0, VaultError::NoYieldShares);
+
+                let received = transfer_from_treasury(
+                    treasury,
+                    source,
+                    reserve,
+                    mint_account,
+                    token_program,
+                    amount,
+                )?;
+                raise_yield_index(&mut record, counters, received)?;
+                record.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+            }
+            AdminAction::InitializeInsuranceFund { mint, socialize_losses } => {
+                let (
+                    Some(info),
+                    Some(fund_token_account),
+                    Some(mint_account),
+                    Some(token_program),
+                    Some(associated_token_program),
+                ) = (
+                    ctx.accounts.insurance_fund.as_ref(),
+                    ctx.accounts.reserve_token_account.as_ref(),
+                    ctx.accounts.mint.as_ref(),
+                    ctx.accounts.token_program.as_ref(),
+                    ctx.accounts.associated_token_program.as_ref(),
+                ) else {
+                    return err!(VaultError::InvalidVaultState);
+                };
+                require_keys_eq!(mint_account.key(), mint, VaultError::MintMismatch);
+                check_mint_extensions(mint_account)?;
+
+                let (address, bump) = InsuranceFund::find_address(&mint);
+                require_keys_eq!(info.key(), address, VaultError::InvalidVaultState);
+                let info = info.to_account_info();
+                create_pda_account(
+                    &info,
+                    &ctx.accounts.executor,
+                    &ctx.accounts.system_program,
+                    &[InsuranceFund::SEED, mint.as_ref(), &[bump]],
+                    InsuranceFund::LEN,
+                )?;
+                create_associated_account(
+                    fund_token_account,
+                    &info,
+                    &ctx.accounts.executor,
+                    mint_account,
+                    token_program,
+                    associated_token_program,
+                    &ctx.accounts.system_program,
+                )?;
+
+                let record =
+                    new_insurance_fund(mint, fund_token_account.key(), socialize_losses, bump)?;
+                record.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+            }
+            AdminAction::SetSocializeLosses { mint, socialize_losses } => {
+                let info = ctx
+                    .accounts
+                    .insurance_fund
+                    .as_ref()
+                    .ok_or(VaultError::InvalidVaultState)?;
+                let address = InsuranceFund::find_address(&mint).0;
+                require_keys_eq!(info.key(), address, VaultError::InvalidVaultState);
+                require_keys_eq!(*info.owner, crate::ID, VaultError::InvalidVaultState);
+                let mut record = InsuranceFund::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+                update_socialize_losses(&mut record, info.key(), socialize_losses)?;
+                record.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+            }
+            AdminAction::CollectFeesToInsurance { treasury_token_account } => {
+                let (Some(treasury), Some(source), Some(info), Some(mint), Some(token_program)) = (
+                    ctx.accounts.treasury.as_ref(),
+                    ctx.accounts.treasury_token_account.as_ref(),
+                    ctx.accounts.insurance_fund.as_ref(),
+                    ctx.accounts.mint.as_ref(),
+                    ctx.accounts.token_program.as_ref(),
+                ) else {
+                    return err!(VaultError::InvalidVaultState);
+                };
+                let Some(target) = ctx.accounts.fee_destination.as_mut() else {
+                    return err!(VaultError::InvalidVaultState);
+                };
+                require_keys_eq!(source.key(), treasury_token_account, VaultError::InvalidVaultState);
+                let address = InsuranceFund::find_address(&source.mint).0;
+                require_keys_eq!(info.key(), address, VaultError::InvalidVaultState);
+                require_keys_eq!(*info.owner, crate::ID, VaultError::InvalidVaultState);
+                let mut record = InsuranceFund::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+                require_keys_eq!(target.key(), record.token_account, VaultError::InvalidVaultState);
+
+                let received = sweep_fees(treasury, source, target, mint, token_program)?;
+                target.reload()?;
+                credit_fee_sweep(&mut record, info.key(), received, target.amount)?;
+                record.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+            }
+            AdminAction::Pause { reason } => set_paused(authority, true, reason)?,
+            AdminAction::Unpause => set_paused(authority, false, 0)?,
+            AdminAction::SetVaultFrozen { vault, frozen } => {
+                let target = ctx
+                    .accounts
+                    .vault
+                    .as_mut()
+                    .ok_or(VaultError::InvalidVaultState)?;
+                require_keys_eq!(target.key(), vault, VaultError::InvalidVaultState);
+                let status = if frozen {
+                    VaultStatus::Frozen
+                } else {
+                    VaultStatus::Active
+                };
+                set_vault_status(authority.admin, target, status)?
+            }
+            AdminAction::ForceUnlock { vault, program, amount } => {
+                let (Some(target), Some(program_lock), Some(record)) = (
+                    ctx.accounts.vault.as_mut(),
+                    ctx.accounts.program_lock.as_mut(),
+                    ctx.accounts.authorized_program.as_ref(),
+                ) else {
+                    return err!(VaultError::InvalidVaultState);
+                };
+                require_keys_eq!(target.key(), vault, VaultError::InvalidVaultState);
+                require_keys_eq!(
+                    program_lock.key(),
+                    ProgramLock::find_address(&vault, &program).0,
+                    VaultError::InvalidVaultState
+                );
+                require_keys_eq!(
+                    record.key(),
+                    AuthorizedProgram::find_address(&program).0,
+                    VaultError::InvalidVaultState
+                );
+                let record = AuthorizedProgram::load(record, &program)
+                    .ok_or(VaultError::ProgramNotAuthorized)?;
+                // The vault's mint may have no yield index
+                let info = ctx.accounts.yield_index.as_ref();
+                let mut yield_index = match info {
+                    Some(info) => {
+                        require_keys_eq!(
+                            info.key(),
+                            YieldIndex::find_address(&target.mint).0,
+                            VaultError::InvalidVaultState
+                        );
+                        require_keys_eq!(*info.owner, crate::ID, VaultError::InvalidVaultState);
+                        Some(YieldIndex::try_deserialize(&mut &info.try_borrow_data()?[..])?)
+                    }
+                    None => None,
+                };
+
+                force_release(
+                    authority.admin,
+                    &record,
+                    target,
+                    program_lock,
+                    yield_index.as_mut(),
+                    amount,
+                )?;
+                if let (Some(info), Some(yield_index)) = (info, yield_index) {
+                    yield_index.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+                }
+            }
+            AdminAction::ProposeAdmin { new_admin } => set_pending_admin(authority, new_admin)?,
+            AdminAction::UpdateSigners { signers, threshold } => {
+                let multisig = &mut ctx.accounts.multisig;
+                multisig.signers = signers;
+                multisig.threshold = threshold;
+                // Open proposals were approved by the previous set
+                multisig.signer_set_seqno = multisig.signer_set_seqno
+                    .checked_add(1)
+                    .ok_or(VaultError::NumericalOverflow)?;
+
+                let clock = Clock::get()?;
+                emit!(MultisigUpdatedEvent {
+                    multisig: multisig.key(),
+                    signers: multisig.signers.clone(),
+                    threshold,
+                    signer_set_seqno: multisig.signer_set_seqno,
+                    slot: clock.slot,
+                    timestamp: clock.unix_timestamp,
+                });
+            }
+        }
+
+        let clock = Clock::get()?;
+        emit!(ProposalExecutedEvent {
+            proposal: ctx.accounts.proposal.key(),
+            index: ctx.accounts.proposal.index,
+            action,
+            slot: clock.slot,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!("Executed admin proposal {}", ctx.accounts.proposal.index);
         Ok(())
     }
 
-    /// Deposit USDT collateral into the vault
+    /// Deposit collateral of the vault's mint into the vault
     pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
         require!(amount > 0, VaultError::InvalidAmount);
         
         let clock = Clock::get()?;
         
-        // Transfer USDT from user to vault using CPI
-        token::transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.user_token_account.to_account_info(),
-                    to: ctx.accounts.vault_token_account.to_account_info(),
-                    authority: ctx.accounts.user.to_account_info(),
-                },
-            ),
+        // A Token-2022 transfer fee is withheld on the way in; only the rest is credited
+        let credited = amount
+            .checked_sub(transfer_fee(&ctx.accounts.mint, amount)?)
+            .ok_or(VaultError::UnderflowError)?;
+        
+        // Transfer tokens from user to vault using CPI
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            ctx.accounts.user_token_account.to_account_info(),
+            ctx.accounts.vault_token_account.to_account_info(),
+            ctx.accounts.user.to_account_info(),
+            &ctx.accounts.mint,
             amount,
+            &[],
         )?;
 
-        // Update vault state
         let vault = &mut ctx.accounts.vault;
-        vault.total_balance = vault.total_balance
-            .checked_add(amount)
-            .ok_or(VaultError::NumericalOverflow)?;
-        vault.available_balance = vault.available_balance
-            .checked_add(amount)
-            .ok_or(VaultError::NumericalOverflow)?;
-        vault.total_deposited = vault.total_deposited
-            .checked_add(amount)
-            .ok_or(VaultError::NumericalOverflow)?;
-        vault.last_updated = clock.unix_timestamp;
+        credit_deposit(
+            vault,
+            &ctx.accounts.authority,
+            &mut ctx.accounts.counters,
+            ctx.accounts.yield_index.as_deref_mut(),
+            credited,
+            clock.unix_timestamp,
+        )?;
 
         emit!(DepositEvent {
             user: ctx.accounts.user.key(),
+            payer: ctx.accounts.user.key(),
             vault: vault.key(),
+            amount: credited,
+            new_balance: vault.total_balance,
+            sequence: vault.sequence,
+            slot: clock.slot,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!("Deposited {} tokens to vault", credited);
+        Ok(())
+    }
+
+    /// Deposit from the payer's token account into another owner's vault
+    /// (subsidies, treasury top-ups, custodial on-ramps). Only the vault owner
+    /// can withdraw the deposit.
+    pub fn deposit_for(ctx: Context<DepositFor>, amount: u64) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        
+        let clock = Clock::get()?;
+        
+        let credited = amount
+            .checked_sub(transfer_fee(&ctx.accounts.mint, amount)?)
+            .ok_or(VaultError::UnderflowError)?;
+        
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            ctx.accounts.payer_token_account.to_account_info(),
+            ctx.accounts.vault_token_account.to_account_info(),
+            ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.mint,
             amount,
+            &[],
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+        credit_deposit(
+            vault,
+            &ctx.accounts.authority,
+            &mut ctx.accounts.counters,
+            ctx.accounts.yield_index.as_deref_mut(),
+            credited,
+            clock.unix_timestamp,
+        )?;
+
+        emit!(DepositEvent {
+            user: ctx.accounts.beneficiary.key(),
+            payer: ctx.accounts.payer.key(),
+            vault: vault.key(),
+            amount: credited,
             new_balance: vault.total_balance,
+            sequence: vault.sequence,
+            slot: clock.slot,
             timestamp: clock.unix_timestamp,
         });
 
-        msg!("Deposited {} tokens to vault", amount);
+        msg!(
+            "Deposited {} tokens to vault of {}",
+            credited,
+            ctx.accounts.beneficiary.key()
+        );
         Ok(())
     }
 
-    /// Withdraw USDT collateral from the vault
+    /// Withdraw collateral from the vault (only while no withdrawal delay is configured)
     pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
         require!(amount > 0, VaultError::InvalidAmount);
+        require!(
+            ctx.accounts.authority.withdrawal_delay == 0,
+            VaultError::WithdrawalTimelockRequired
+        );
         
         let vault = &mut ctx.accounts.vault;
         let clock = Clock::get()?;
         
+        // Credit accrued yield so it can be withdrawn with the rest, and move
+        // it out of the reserve before paying out
+        accrue_yield(vault, ctx.accounts.yield_index.as_deref())?;
+        let withheld = settle_yield(
+            vault,
+            ctx.accounts.yield_index.as_mut(),
+            ctx.accounts.yield_reserve.as_ref(),
+            &ctx.accounts.vault_token_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+        )?;
+        
         // Verify sufficient available balance
         require!(
             vault.available_balance >= amount,
             VaultError::InsufficientBalance
         );
         
-        // Verify no locked collateral preventing withdrawal
-        require!(
-            vault.locked_balance == 0 || vault.available_balance >= amount,
-            VaultError::HasOpenPositions
-        );
+        // Count the withdrawal against the per-vault and global rate limits
+        enforce_withdrawal_limits(
+            &ctx.accounts.authority,
+            vault,
+            &mut ctx.accounts.counters,
+            amount,
+            clock.unix_timestamp,
+        )?;
+
+        // The fee is taken out of the withdrawn amount
+        let fee = ctx.accounts.authority
+            .fee_for(amount)
+            .ok_or(VaultError::NumericalOverflow)?;
+        let received = amount
+            .checked_sub(fee)
+            .ok_or(VaultError::UnderflowError)?;
 
-        // Transfer USDT from vault to user using CPI with PDA signer
+        // Transfer tokens from vault to user using CPI with PDA signer
         let user_key = ctx.accounts.user.key();
+        let mint_key = vault.mint;
+        let sub_account = vault.sub_account.to_le_bytes();
         let seeds = &[
             b"vault",
             user_key.as_ref(),
+            mint_key.as_ref(),
+            sub_account.as_ref(),
             &[vault.bump],
         ];
         let signer_seeds = &[&seeds[..]];
 
-        token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.vault_token_account.to_account_info(),
-                    to: ctx.accounts.user_token_account.to_account_info(),
-                    authority: vault.to_account_info(),
-                },
-                signer_seeds,
-            ),
-            amount,
+        // Any Token-2022 transfer fee on the payout is borne by the recipient
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            ctx.accounts.vault_token_account.to_account_info(),
+            ctx.accounts.user_token_account.to_account_info(),
+            vault.to_account_info(),
+            &ctx.accounts.mint,
+            received,
+            signer_seeds,
         )?;
 
+        if fee > 0 {
+            transfer_tokens(
+                &ctx.accounts.token_program,
+                ctx.accounts.vault_token_account.to_account_info(),
+                ctx.accounts.treasury_token_account.to_account_info(),
+                vault.to_account_info(),
+                &ctx.accounts.mint,
+                fee,
+                signer_seeds,
+            )?;
+        }
+
         // Update vault state
         vault.total_balance = vault.total_balance
             .checked_sub(amount)
@@ -189,90 +1108,229 @@ pub mod vault_manager {
             .checked_add(amount)
             .ok_or(VaultError::NumericalOverflow)?;
         vault.last_updated = clock.unix_timestamp;
+        vault.next_sequence().ok_or(VaultError::NumericalOverflow)?;
+        reshare(vault, ctx.accounts.yield_index.as_deref_mut())?;
+
+        let counters = &mut ctx.accounts.counters;
+        counters.total_value_locked = counters.total_value_locked
+            .checked_sub(amount)
+            .and_then(|tvl| tvl.checked_sub(withheld))
+            .ok_or(VaultError::UnderflowError)?;
+        counters.last_updated = clock.unix_timestamp;
 
         emit!(WithdrawalEvent {
             user: ctx.accounts.user.key(),
             vault: vault.key(),
             amount,
+            fee,
             new_balance: vault.total_balance,
+            sequence: vault.sequence,
+            slot: clock.slot,
             timestamp: clock.unix_timestamp,
         });
 
-        msg!("Withdrawn {} tokens from vault", amount);
+        msg!("Withdrawn {} tokens from vault (fee {})", amount, fee);
         Ok(())
     }
 
-    /// Lock collateral for margin requirements (called by authorized programs via CPI)
-    pub fn lock_collateral(ctx: Context<LockCollateral>, amount: u64) -> Result<()> {
+    /// Request a timelocked withdrawal, moving the amount into the pending bucket
+    pub fn request_withdrawal(ctx: Context<RequestWithdrawal>, amount: u64) -> Result<()> {
         require!(amount > 0, VaultError::InvalidAmount);
         
         let vault = &mut ctx.accounts.vault;
         let clock = Clock::get()?;
         
+        // Pending collateral stops earning yield, so credit what it earned first
+        accrue_yield(vault, ctx.accounts.yield_index.as_deref())?;
+        
         // Verify sufficient available balance
         require!(
             vault.available_balance >= amount,
-            VaultError::InsufficientBalanceToLock
+            VaultError::InsufficientBalance
         );
-
-        // Update balances
-        vault.locked_balance = vault.locked_balance
-            .checked_add(amount)
+        
+        let executable_at = clock.unix_timestamp
+            .checked_add(ctx.accounts.authority.withdrawal_delay)
             .ok_or(VaultError::NumericalOverflow)?;
+
+        // Reserve the amount so it can be neither locked nor withdrawn twice
         vault.available_balance = vault.available_balance
             .checked_sub(amount)
             .ok_or(VaultError::UnderflowError)?;
+        vault.pending_balance = vault.pending_balance
+            .checked_add(amount)
+            .ok_or(VaultError::NumericalOverflow)?;
         vault.last_updated = clock.unix_timestamp;
+        vault.next_sequence().ok_or(VaultError::NumericalOverflow)?;
+        reshare(vault, ctx.accounts.yield_index.as_deref_mut())?;
+
+        let pending_withdrawal = &mut ctx.accounts.pending_withdrawal;
+        pending_withdrawal.vault = vault.key();
+        pending_withdrawal.amount = amount;
+        pending_withdrawal.requested_at = clock.unix_timestamp;
+        pending_withdrawal.executable_at = executable_at;
+        pending_withdrawal.bump = ctx.bumps.pending_withdrawal;
 
-        emit!(LockEvent {
+        emit!(WithdrawalRequestedEvent {
+            user: ctx.accounts.user.key(),
             vault: vault.key(),
             amount,
-            locked_balance: vault.locked_balance,
-            available_balance: vault.available_balance,
+            executable_at,
+            sequence: vault.sequence,
+            slot: clock.slot,
             timestamp: clock.unix_timestamp,
         });
 
-        msg!("Locked {} tokens in vault", amount);
+        msg!("Requested withdrawal of {} tokens, executable at {}", amount, executable_at);
         Ok(())
     }
 
-    /// Unlock collateral when position is closed (called by authorized programs via CPI)
-    pub fn unlock_collateral(ctx: Context<UnlockCollateral>, amount: u64) -> Result<()> {
-        require!(amount > 0, VaultError::InvalidAmount);
-        
+    /// Execute a pending withdrawal once its timelock has expired
+    pub fn execute_withdrawal(ctx: Context<ExecuteWithdrawal>) -> Result<()> {
         let vault = &mut ctx.accounts.vault;
+        let pending_withdrawal = &ctx.accounts.pending_withdrawal;
+        let amount = pending_withdrawal.amount;
         let clock = Clock::get()?;
         
-        // Verify sufficient locked balance
         require!(
-            vault.locked_balance >= amount,
-            VaultError::InvalidUnlockAmount
+            clock.unix_timestamp >= pending_withdrawal.executable_at,
+            VaultError::WithdrawalTimelockActive
         );
 
-        // Update balances
-        vault.locked_balance = vault.locked_balance
+        accrue_yield(vault, ctx.accounts.yield_index.as_deref())?;
+        let withheld = settle_yield(
+            vault,
+            ctx.accounts.yield_index.as_mut(),
+            ctx.accounts.yield_reserve.as_ref(),
+            &ctx.accounts.vault_token_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+        )?;
+
+        // Queued withdrawals count against the rate limits when they execute
+        enforce_withdrawal_limits(
+            &ctx.accounts.authority,
+            vault,
+            &mut ctx.accounts.counters,
+            amount,
+            clock.unix_timestamp,
+        )?;
+
+        // The fee in force at execution is taken out of the withdrawn amount
+        let fee = ctx.accounts.authority
+            .fee_for(amount)
+            .ok_or(VaultError::NumericalOverflow)?;
+        let received = amount
+            .checked_sub(fee)
+            .ok_or(VaultError::UnderflowError)?;
+
+        // Transfer tokens from vault to user using CPI with PDA signer
+        let user_key = ctx.accounts.user.key();
+        let mint_key = vault.mint;
+        let sub_account = vault.sub_account.to_le_bytes();
+        let seeds = &[
+            b"vault",
+            user_key.as_ref(),
+            mint_key.as_ref(),
+            sub_account.as_ref(),
+            &[vault.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        // Any Token-2022 transfer fee on the payout is borne by the recipient
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            ctx.accounts.vault_token_account.to_account_info(),
+            ctx.accounts.user_token_account.to_account_info(),
+            vault.to_account_info(),
+            &ctx.accounts.mint,
+            received,
+            signer_seeds,
+        )?;
+
+        if fee > 0 {
+            transfer_tokens(
+                &ctx.accounts.token_program,
+                ctx.accounts.vault_token_account.to_account_info(),
+                ctx.accounts.treasury_token_account.to_account_info(),
+                vault.to_account_info(),
+                &ctx.accounts.mint,
+                fee,
+                signer_seeds,
+            )?;
+        }
+
+        // Update vault state
+        vault.total_balance = vault.total_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::UnderflowError)?;
+        vault.pending_balance = vault.pending_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::UnderflowError)?;
+        vault.total_withdrawn = vault.total_withdrawn
+            .checked_add(amount)
+            .ok_or(VaultError::NumericalOverflow)?;
+        vault.last_updated = clock.unix_timestamp;
+        vault.next_sequence().ok_or(VaultError::NumericalOverflow)?;
+        reshare(vault, ctx.accounts.yield_index.as_deref_mut())?;
+
+        let counters = &mut ctx.accounts.counters;
+        counters.total_value_locked = counters.total_value_locked
+            .checked_sub(amount)
+            .and_then(|tvl| tvl.checked_sub(withheld))
+            .ok_or(VaultError::UnderflowError)?;
+        counters.last_updated = clock.unix_timestamp;
+
+        emit!(WithdrawalEvent {
+            user: user_key,
+            vault: vault.key(),
+            amount,
+            fee,
+            new_balance: vault.total_balance,
+            sequence: vault.sequence,
+            slot: clock.slot,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!("Executed withdrawal of {} tokens from vault (fee {})", amount, fee);
+        Ok(())
+    }
+
+    /// Cancel a pending withdrawal, returning the amount to the available balance
+    pub fn cancel_withdrawal(ctx: Context<CancelWithdrawal>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let amount = ctx.accounts.pending_withdrawal.amount;
+        let clock = Clock::get()?;
+
+        accrue_yield(vault, ctx.accounts.yield_index.as_deref())?;
+
+        vault.pending_balance = vault.pending_balance
             .checked_sub(amount)
             .ok_or(VaultError::UnderflowError)?;
         vault.available_balance = vault.available_balance
             .checked_add(amount)
             .ok_or(VaultError::NumericalOverflow)?;
         vault.last_updated = clock.unix_timestamp;
+        vault.next_sequence().ok_or(VaultError::NumericalOverflow)?;
+        reshare(vault, ctx.accounts.yield_index.as_deref_mut())?;
 
-        emit!(UnlockEvent {
+        emit!(WithdrawalCancelledEvent {
+            user: ctx.accounts.user.key(),
             vault: vault.key(),
             amount,
-            locked_balance: vault.locked_balance,
-            available_balance: vault.available_balance,
+            sequence: vault.sequence,
+            slot: clock.slot,
             timestamp: clock.unix_timestamp,
         });
 
-        msg!("Unlocked {} tokens in vault", amount);
+        msg!("Cancelled withdrawal of {} tokens", amount);
         Ok(())
     }
 
-    /// Transfer collateral between vaults (for settlements/liquidations)
-    pub fn transfer_collateral(
-        ctx: Context<TransferCollateral>,
+    /// Move available collateral between two sub-accounts of the signer with
+    /// the same mint
+    pub fn transfer_between_sub_accounts(
+        ctx: Context<TransferBetweenSubAccounts>,
         amount: u64,
     ) -> Result<()> {
         require!(amount > 0, VaultError::InvalidAmount);
@@ -281,227 +1339,3910 @@ pub mod vault_manager {
         let to_vault = &mut ctx.accounts.to_vault;
         let clock = Clock::get()?;
         
-        // Verify sufficient balance in source vault
+        accrue_yield(from_vault, ctx.accounts.yield_index.as_deref())?;
+        accrue_yield(to_vault, ctx.accounts.yield_index.as_deref())?;
+        let withheld = settle_yield(
+            from_vault,
+            ctx.accounts.yield_index.as_mut(),
+            ctx.accounts.yield_reserve.as_ref(),
+            &ctx.accounts.from_token_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+        )?;
+        
         require!(
-            from_vault.total_balance >= amount,
-            VaultError::InsufficientTransferBalance
+            from_vault.available_balance >= amount,
+            VaultError::InsufficientBalance
         );
 
-        // Update source vault
+        // The collateral stays with the owner, so no protocol fee or rate limit
+        // applies; only a Token-2022 transfer fee is lost on the way
+        let credited = amount
+            .checked_sub(transfer_fee(&ctx.accounts.mint, amount)?)
+            .ok_or(VaultError::UnderflowError)?;
+
         from_vault.total_balance = from_vault.total_balance
             .checked_sub(amount)
             .ok_or(VaultError::UnderflowError)?;
+        from_vault.available_balance = from_vault.available_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::UnderflowError)?;
         from_vault.last_updated = clock.unix_timestamp;
+        from_vault.next_sequence().ok_or(VaultError::NumericalOverflow)?;
 
-        // Update destination vault
         to_vault.total_balance = to_vault.total_balance
-            .checked_add(amount)
+            .checked_add(credited)
             .ok_or(VaultError::NumericalOverflow)?;
         to_vault.available_balance = to_vault.available_balance
-            .checked_add(amount)
+            .checked_add(credited)
             .ok_or(VaultError::NumericalOverflow)?;
         to_vault.last_updated = clock.unix_timestamp;
+        to_vault.next_sequence().ok_or(VaultError::NumericalOverflow)?;
 
-        // Transfer tokens between vault token accounts
-        let from_owner_key = from_vault.owner.key();
+        reshare(from_vault, ctx.accounts.yield_index.as_deref_mut())?;
+        reshare(to_vault, ctx.accounts.yield_index.as_deref_mut())?;
+
+        let counters = &mut ctx.accounts.counters;
+        counters.total_value_locked = counters.total_value_locked
+            .checked_sub(amount - credited)
+            .and_then(|tvl| tvl.checked_sub(withheld))
+            .ok_or(VaultError::UnderflowError)?;
+        counters.last_updated = clock.unix_timestamp;
+
+        let user_key = ctx.accounts.user.key();
+        let mint_key = from_vault.mint;
+        let sub_account = from_vault.sub_account.to_le_bytes();
         let seeds = &[
             b"vault",
-            from_owner_key.as_ref(),
+            user_key.as_ref(),
+            mint_key.as_ref(),
+            sub_account.as_ref(),
             &[from_vault.bump],
         ];
         let signer_seeds = &[&seeds[..]];
 
-        token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.from_token_account.to_account_info(),
-                    to: ctx.accounts.to_token_account.to_account_info(),
-                    authority: from_vault.to_account_info(),
-                },
-                signer_seeds,
-            ),
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            ctx.accounts.from_token_account.to_account_info(),
+            ctx.accounts.to_token_account.to_account_info(),
+            from_vault.to_account_info(),
+            &ctx.accounts.mint,
             amount,
+            signer_seeds,
         )?;
 
-        emit!(TransferEvent {
+        emit!(SubAccountTransferEvent {
+            owner: user_key,
             from_vault: from_vault.key(),
             to_vault: to_vault.key(),
             amount,
+            from_sequence: from_vault.sequence,
+            to_sequence: to_vault.sequence,
+            slot: clock.slot,
             timestamp: clock.unix_timestamp,
         });
 
-        msg!("Transferred {} tokens between vaults", amount);
+        msg!(
+            "Moved {} tokens from sub-account {} to sub-account {}",
+            amount,
+            from_vault.sub_account,
+            to_vault.sub_account
+        );
         Ok(())
     }
-}
 
-// ============ Account Validation Contexts ============
+    /// Lock collateral for margin requirements (called by authorized programs via CPI)
+    pub fn lock_collateral(ctx: Context<LockCollateral>, amount: u64) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let program_lock = &mut ctx.accounts.program_lock;
+        if program_lock.vault == Pubkey::default() {
+            program_lock.vault = vault_key;
+            program_lock.program = ctx.accounts.caller_program.key();
+            program_lock.locked_amount = 0;
+            program_lock.bump = ctx.bumps.program_lock;
+        }
 
-#[derive(Accounts)]
-pub struct InitializeVault<'info> {
-    #[account(mut)]
-    pub user: Signer<'info>,
+        let authorized_program = AuthorizedProgram::load_enabled(
+            &ctx.accounts.authorized_program,
+            &ctx.accounts.caller_program.key(),
+        )
+        .ok_or(VaultError::UnauthorizedProgram)?;
+        apply_lock(
+            &mut ctx.accounts.vault,
+            program_lock,
+            &authorized_program,
+            ctx.accounts.yield_index.as_deref_mut(),
+            amount,
+            &Clock::get()?,
+        )?;
 
-    #[account(
-        init,
-        payer = user,
-        space = CollateralVault::LEN,
-        seeds = [b"vault", user.key().as_ref()],
-        bump
-    )]
-    pub vault: Account<'info, CollateralVault>,
+        msg!("Locked {} tokens in vault", amount);
+        Ok(())
+    }
 
-    #[account(
-        init,
-        payer = user,
-        associated_token::mint = mint,
-        associated_token::authority = vault,
-    )]
-    pub vault_token_account: Account<'info, TokenAccount>,
+    /// Unlock collateral when position is closed (called by authorized programs via CPI)
+    pub fn unlock_collateral(ctx: Context<UnlockCollateral>, amount: u64) -> Result<()> {
+        apply_unlock(
+            &mut ctx.accounts.vault,
+            &mut ctx.accounts.program_lock,
+            ctx.accounts.yield_index.as_deref_mut(),
+            amount,
+            &Clock::get()?,
+        )?;
 
-    pub mint: Account<'info, Mint>,
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub system_program: Program<'info, System>,
-}
+        msg!("Unlocked {} tokens in vault", amount);
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct InitializeAuthority<'info> {
-    #[account(mut)]
-    pub admin: Signer<'info>,
+    /// Lock collateral in many vaults of one mint at once (called by authorized
+    /// programs via CPI). `remaining_accounts` holds a (vault, program lock)
+    /// pair per entry of `amounts`; any failing entry fails the whole batch.
+    pub fn batch_lock<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, BatchLockCollateral<'info>>,
+        amounts: Vec<u64>,
+    ) -> Result<()> {
+        check_batch(ctx.remaining_accounts, amounts.len())?;
 
-    #[account(
-        init,
-        payer = admin,
-        space = VaultAuthority::LEN,
-        seeds = [b"authority"],
-        bump
-    )]
-    pub authority: Account<'info, VaultAuthority>,
+        let caller = ctx.accounts.caller_program.key();
+        let authorized_program = AuthorizedProgram::load_enabled(
+            &ctx.accounts.authorized_program,
+            &caller,
+        )
+        .ok_or(VaultError::UnauthorizedProgram)?;
+        let clock = Clock::get()?;
 
-    pub system_program: Program<'info, System>,
-}
+        for (accounts, &amount) in ctx.remaining_accounts.chunks(2).zip(&amounts) {
+            let mut vault = load_batch_vault(&accounts[0], &ctx.accounts.yield_index)?;
+            require!(!vault.is_frozen(), VaultError::VaultFrozen);
+            let mut program_lock = load_or_create_program_lock(
+                &accounts[1],
+                &ctx.accounts.payer,
+                &ctx.accounts.system_program,
+                &vault.key(),
+                &caller,
+            )?;
 
-#[derive(Accounts)]
-pub struct ManageAuthority<'info> {
-    #[account(mut)]
-    pub admin: Signer<'info>,
+            apply_lock(
+                &mut vault,
+                &mut program_lock,
+                &authorized_program,
+                Some(&mut *ctx.accounts.yield_index),
+                amount,
+                &clock,
+            )?;
+            vault.exit(&crate::ID)?;
+            program_lock.exit(&crate::ID)?;
+        }
 
-    #[account(
-        mut,
-        seeds = [b"authority"],
-        bump = authority.bump,
-        has_one = admin @ VaultError::UnauthorizedAdmin
-    )]
-    pub authority: Account<'info, VaultAuthority>,
-}
+        msg!("Locked collateral in {} vaults", amounts.len());
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct Deposit<'info> {
-    #[account(mut)]
-    pub user: Signer<'info>,
+    /// Unlock collateral in many vaults of one mint at once (called by
+    /// authorized programs via CPI). Takes the same accounts as `batch_lock`.
+    pub fn batch_unlock<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, BatchUnlockCollateral<'info>>,
+        amounts: Vec<u64>,
+    ) -> Result<()> {
+        check_batch(ctx.remaining_accounts, amounts.len())?;
 
-    #[account(
-        mut,
-        seeds = [b"vault", user.key().as_ref()],
-        bump = vault.bump,
-        has_one = owner @ VaultError::UnauthorizedOwner
-    )]
-    pub vault: Account<'info, CollateralVault>,
+        let caller = ctx.accounts.caller_program.key();
+        let clock = Clock::get()?;
 
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
+        for (accounts, &amount) in ctx.remaining_accounts.chunks(2).zip(&amounts) {
+            let mut vault = load_batch_vault(&accounts[0], &ctx.accounts.yield_index)?;
+            let mut program_lock: Account<ProgramLock> = Account::try_from(&accounts[1])?;
+            require!(
+                program_lock.vault == vault.key() && program_lock.program == caller,
+                VaultError::InvalidBatch
+            );
 
-    #[account(mut)]
-    pub vault_token_account: Account<'info, TokenAccount>,
+            apply_unlock(
+                &mut vault,
+                &mut program_lock,
+                Some(&mut *ctx.accounts.yield_index),
+                amount,
+                &clock,
+            )?;
+            vault.exit(&crate::ID)?;
+            program_lock.exit(&crate::ID)?;
+        }
 
-    pub owner: SystemAccount<'info>,
-    pub token_program: Program<'info, Token>,
-}
+        msg!("Unlocked collateral in {} vaults", amounts.len());
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct Withdraw<'info> {
-    #[account(mut)]
-    pub user: Signer<'info>,
+    /// Transfer collateral between vaults (for settlements/liquidations)
+    pub fn transfer_collateral(
+        ctx: Context<TransferCollateral>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        
+        let from_vault = &mut ctx.accounts.from_vault;
+        let to_vault = &mut ctx.accounts.to_vault;
+        let clock = Clock::get()?;
+        
+        accrue_yield(from_vault, ctx.accounts.yield_index.as_deref())?;
+        accrue_yield(to_vault, ctx.accounts.yield_index.as_deref())?;
+        let withheld = settle_yield(
+            from_vault,
+            ctx.accounts.yield_index.as_mut(),
+            ctx.accounts.yield_reserve.as_ref(),
+            &ctx.accounts.from_token_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+        )?;
+        
+        // Only available collateral can be transferred; locked margin moves via settle_locked
+        require!(
+            from_vault.available_balance >= amount,
+            VaultError::InsufficientTransferBalance
+        );
 
-    #[account(
-        mut,
-        seeds = [b"vault", user.key().as_ref()],
-        bump = vault.bump,
-        has_one = owner @ VaultError::UnauthorizedOwner
-    )]
-    pub vault: Account<'info, CollateralVault>,
+        // The fee is taken out of the transferred amount
+        let fee = ctx.accounts.authority
+            .fee_for(amount)
+            .ok_or(VaultError::NumericalOverflow)?;
+        let received = amount
+            .checked_sub(fee)
+            .ok_or(VaultError::UnderflowError)?;
+        // The destination is credited what arrives after a Token-2022 transfer fee
+        let credited = received
+            .checked_sub(transfer_fee(&ctx.accounts.mint, received)?)
+            .ok_or(VaultError::UnderflowError)?;
 
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
+        // Update source vault
+        from_vault.total_balance = from_vault.total_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::UnderflowError)?;
+        from_vault.available_balance = from_vault.available_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::UnderflowError)?;
+        from_vault.last_updated = clock.unix_timestamp;
+        from_vault.next_sequence().ok_or(VaultError::NumericalOverflow)?;
 
-    #[account(mut)]
-    pub vault_token_account: Account<'info, TokenAccount>,
+        // Update destination vault
+        to_vault.total_balance = to_vault.total_balance
+            .checked_add(credited)
+            .ok_or(VaultError::NumericalOverflow)?;
+        to_vault.available_balance = to_vault.available_balance
+            .checked_add(credited)
+            .ok_or(VaultError::NumericalOverflow)?;
+        to_vault.last_updated = clock.unix_timestamp;
+        to_vault.next_sequence().ok_or(VaultError::NumericalOverflow)?;
 
-    pub owner: SystemAccount<'info>,
-    pub token_program: Program<'info, Token>,
-}
+        reshare(from_vault, ctx.accounts.yield_index.as_deref_mut())?;
+        reshare(to_vault, ctx.accounts.yield_index.as_deref_mut())?;
 
-#[derive(Accounts)]
-pub struct LockCollateral<'info> {
-    #[account(
-        mut,
-        seeds = [b"vault", vault.owner.as_ref()],
-        bump = vault.bump,
-    )]
-    pub vault: Account<'info, CollateralVault>,
+        // The fees leave the vaults, so they no longer count towards the TVL
+        let counters = &mut ctx.accounts.counters;
+        counters.total_value_locked = counters.total_value_locked
+            .checked_sub(amount - credited)
+            .and_then(|tvl| tvl.checked_sub(withheld))
+            .ok_or(VaultError::UnderflowError)?;
+        counters.last_updated = clock.unix_timestamp;
 
-    #[account(
-        seeds = [b"authority"],
-        bump = authority.bump,
-    )]
-    pub authority: Account<'info, VaultAuthority>,
-}
+        // Transfer tokens between vault token accounts
+        let from_owner_key = from_vault.owner.key();
+        let mint_key = from_vault.mint;
+        let sub_account = from_vault.sub_account.to_le_bytes();
+        let seeds = &[
+            b"vault",
+            from_owner_key.as_ref(),
+            mint_key.as_ref(),
+            sub_account.as_ref(),
+            &[from_vault.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
 
-#[derive(Accounts)]
-pub struct UnlockCollateral<'info> {
-    #[account(
-        mut,
-        seeds = [b"vault", vault.owner.as_ref()],
-        bump = vault.bump,
-    )]
-    pub vault: Account<'info, CollateralVault>,
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            ctx.accounts.from_token_account.to_account_info(),
+            ctx.accounts.to_token_account.to_account_info(),
+            from_vault.to_account_info(),
+            &ctx.accounts.mint,
+            received,
+            signer_seeds,
+        )?;
+
+        if fee > 0 {
+            transfer_tokens(
+                &ctx.accounts.token_program,
+                ctx.accounts.from_token_account.to_account_info(),
+                ctx.accounts.treasury_token_account.to_account_info(),
+                from_vault.to_account_info(),
+                &ctx.accounts.mint,
+                fee,
+                signer_seeds,
+            )?;
+        }
+
+        emit!(TransferEvent {
+            from_vault: from_vault.key(),
+            to_vault: to_vault.key(),
+            amount,
+            fee,
+            from_sequence: from_vault.sequence,
+            to_sequence: to_vault.sequence,
+            slot: clock.slot,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!("Transferred {} tokens between vaults (fee {})", amount, fee);
+        Ok(())
+    }
+
+    /// Seize collateral locked by the calling program and move it to another vault
+    /// (liquidations, losing-side settlements, insurance contributions)
+    pub fn settle_locked(
+        ctx: Context<SettleLocked>,
+        amount: u64,
+        reason: SettlementReason,
+    ) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        
+        let from_vault = &mut ctx.accounts.from_vault;
+        let to_vault = &mut ctx.accounts.to_vault;
+        let program_lock = &mut ctx.accounts.program_lock;
+        let clock = Clock::get()?;
+        
+        accrue_yield(from_vault, ctx.accounts.yield_index.as_deref())?;
+        accrue_yield(to_vault, ctx.accounts.yield_index.as_deref())?;
+        let withheld = settle_yield(
+            from_vault,
+            ctx.accounts.yield_index.as_mut(),
+            ctx.accounts.yield_reserve.as_ref(),
+            &ctx.accounts.from_token_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+        )?;
+        
+        // A program may only seize collateral it locked itself
+        require!(
+            program_lock.locked_amount >= amount && from_vault.locked_balance >= amount,
+            VaultError::InsufficientLockedBalance
+        );
+
+        // The destination is credited what arrives after a Token-2022 transfer fee
+        let credited = amount
+            .checked_sub(transfer_fee(&ctx.accounts.mint, amount)?)
+            .ok_or(VaultError::UnderflowError)?;
+
+        // Update program lock ledger
+        program_lock.locked_amount = program_lock.locked_amount
+            .checked_sub(amount)
+            .ok_or(VaultError::UnderflowError)?;
+        program_lock.last_updated = clock.unix_timestamp;
+
+        // Update source vault: the debit comes entirely out of the locked portion
+        from_vault.total_balance = from_vault.total_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::UnderflowError)?;
+        from_vault.locked_balance = from_vault.locked_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::UnderflowError)?;
+        from_vault.last_updated = clock.unix_timestamp;
+        from_vault.next_sequence().ok_or(VaultError::NumericalOverflow)?;
+
+        // Update destination vault
+        to_vault.total_balance = to_vault.total_balance
+            .checked_add(credited)
+            .ok_or(VaultError::NumericalOverflow)?;
+        to_vault.available_balance = to_vault.available_balance
+            .checked_add(credited)
+            .ok_or(VaultError::NumericalOverflow)?;
+        to_vault.last_updated = clock.unix_timestamp;
+        to_vault.next_sequence().ok_or(VaultError::NumericalOverflow)?;
+
+        reshare(from_vault, ctx.accounts.yield_index.as_deref_mut())?;
+        reshare(to_vault, ctx.accounts.yield_index.as_deref_mut())?;
+
+        let counters = &mut ctx.accounts.counters;
+        counters.total_value_locked = counters.total_value_locked
+            .checked_sub(amount - credited)
+            .and_then(|tvl| tvl.checked_sub(withheld))
+            .ok_or(VaultError::UnderflowError)?;
+        counters.last_updated = clock.unix_timestamp;
+
+        // Transfer tokens between vault token accounts
+        let from_owner_key = from_vault.owner.key();
+        let mint_key = from_vault.mint;
+        let sub_account = from_vault.sub_account.to_le_bytes();
+        let seeds = &[
+            b"vault",
+            from_owner_key.as_ref(),
+            mint_key.as_ref(),
+            sub_account.as_ref(),
+            &[from_vault.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            ctx.accounts.from_token_account.to_account_info(),
+            ctx.accounts.to_token_account.to_account_info(),
+            from_vault.to_account_info(),
+            &ctx.accounts.mint,
+            amount,
+            signer_seeds,
+        )?;
+
+        emit!(SettlementEvent {
+            from_vault: from_vault.key(),
+            to_vault: to_vault.key(),
+            program: program_lock.program,
+            amount,
+            reason,
+            locked_balance: from_vault.locked_balance,
+            program_locked: program_lock.locked_amount,
+            from_sequence: from_vault.sequence,
+            to_sequence: to_vault.sequence,
+            slot: clock.slot,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!("Settled {} locked tokens between vaults", amount);
+        Ok(())
+    }
+
+    /// Lend a vault's available collateral to an authorized program for the
+    /// rest of the transaction (called via CPI). A top-level `repay` of the
+    /// same vault must follow later in the transaction; locked and pending
+    /// collateral is never lent.
+    pub fn borrow(ctx: Context<Borrow>, amount: u64) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        require_repay(&ctx.accounts.instructions, &ctx.accounts.vault.key())?;
+
+        let vault = &mut ctx.accounts.vault;
+        let clock = Clock::get()?;
+        require!(vault.borrowed == 0, VaultError::BorrowOutstanding);
+
+        // Credited yield is still in the reserve; settle it so every
+        // available token is actually held by the vault token account
+        accrue_yield(vault, ctx.accounts.yield_index.as_deref())?;
+        let withheld = settle_yield(
+            vault,
+            ctx.accounts.yield_index.as_mut(),
+            ctx.accounts.yield_reserve.as_ref(),
+            &ctx.accounts.vault_token_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+        )?;
+
+        require!(
+            vault.available_balance >= amount,
+            VaultError::InsufficientBorrowBalance
+        );
+
+        // Paid by `repay` on top of the borrowed amount
+        let fee = ctx.accounts.authority
+            .borrow_fee_for(amount)
+            .ok_or(VaultError::NumericalOverflow)?;
+
+        // Lent collateral is no longer available, so nothing else in the
+        // transaction can withdraw, lock or transfer it
+        vault.available_balance = vault.available_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::UnderflowError)?;
+        vault.borrowed = amount;
+        vault.last_updated = clock.unix_timestamp;
+        vault.next_sequence().ok_or(VaultError::NumericalOverflow)?;
+        reshare(vault, ctx.accounts.yield_index.as_deref_mut())?;
+
+        let counters = &mut ctx.accounts.counters;
+        counters.total_value_locked = counters.total_value_locked
+            .checked_sub(withheld)
+            .ok_or(VaultError::UnderflowError)?;
+        counters.last_updated = clock.unix_timestamp;
+
+        let owner_key = vault.owner;
+        let mint_key = vault.mint;
+        let sub_account = vault.sub_account.to_le_bytes();
+        let seeds = &[
+            b"vault",
+            owner_key.as_ref(),
+            mint_key.as_ref(),
+            sub_account.as_ref(),
+            &[vault.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            ctx.accounts.vault_token_account.to_account_info(),
+            ctx.accounts.destination_token_account.to_account_info(),
+            vault.to_account_info(),
+            &ctx.accounts.mint,
+            amount,
+            signer_seeds,
+        )?;
+
+        emit!(BorrowEvent {
+            vault: vault.key(),
+            program: ctx.accounts.caller_program.key(),
+            amount,
+            fee,
+            sequence: vault.sequence,
+            slot: clock.slot,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!("Lent {} tokens from vault (fee {})", amount, fee);
+        Ok(())
+    }
+
+    /// Return a vault's outstanding borrow from the payer's token account,
+    /// plus the borrow fee, which goes to the treasury
+    pub fn repay(ctx: Context<Repay>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let clock = Clock::get()?;
+        let amount = vault.borrowed;
+        require!(amount > 0, VaultError::NoOutstandingBorrow);
+
+        let fee = ctx.accounts.authority
+            .borrow_fee_for(amount)
+            .ok_or(VaultError::NumericalOverflow)?;
+        // Gross up for a Token-2022 transfer fee so the vault gets back
+        // exactly what it lent
+        let returned = amount
+            .checked_add(inverse_transfer_fee(&ctx.accounts.mint, amount)?)
+            .ok_or(VaultError::NumericalOverflow)?;
+
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            ctx.accounts.payer_token_account.to_account_info(),
+            ctx.accounts.vault_token_account.to_account_info(),
+            ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.mint,
+            returned,
+            &[],
+        )?;
+
+        if fee > 0 {
+            transfer_tokens(
+                &ctx.accounts.token_program,
+                ctx.accounts.payer_token_account.to_account_info(),
+                ctx.accounts.treasury_token_account.to_account_info(),
+                ctx.accounts.payer.to_account_info(),
+                &ctx.accounts.mint,
+                fee,
+                &[],
+            )?;
+        }
+
+        accrue_yield(vault, ctx.accounts.yield_index.as_deref())?;
+        vault.available_balance = vault.available_balance
+            .checked_add(amount)
+            .ok_or(VaultError::NumericalOverflow)?;
+        vault.borrowed = 0;
+        vault.last_updated = clock.unix_timestamp;
+        vault.next_sequence().ok_or(VaultError::NumericalOverflow)?;
+        reshare(vault, ctx.accounts.yield_index.as_deref_mut())?;
+
+        emit!(RepayEvent {
+            vault: vault.key(),
+            payer: ctx.accounts.payer.key(),
+            amount,
+            fee,
+            sequence: vault.sequence,
+            slot: clock.slot,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!("Repaid {} tokens to vault (fee {})", amount, fee);
+        Ok(())
+    }
+
+    /// Seize collateral locked by the calling program into the insurance fund
+    /// of its mint (liquidation penalties)
+    pub fn settle_to_insurance(ctx: Context<SettleToInsurance>, amount: u64) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+
+        let vault = &mut ctx.accounts.vault;
+        let program_lock = &mut ctx.accounts.program_lock;
+        let clock = Clock::get()?;
+
+        accrue_yield(vault, ctx.accounts.yield_index.as_deref())?;
+        let withheld = settle_yield(
+            vault,
+            ctx.accounts.yield_index.as_mut(),
+            ctx.accounts.yield_reserve.as_ref(),
+            &ctx.accounts.vault_token_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+        )?;
+
+        // A program may only seize collateral it locked itself
+        require!(
+            program_lock.locked_amount >= amount && vault.locked_balance >= amount,
+            VaultError::InsufficientLockedBalance
+        );
+
+        program_lock.locked_amount = program_lock.locked_amount
+            .checked_sub(amount)
+            .ok_or(VaultError::UnderflowError)?;
+        program_lock.last_updated = clock.unix_timestamp;
+
+        vault.total_balance = vault.total_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::UnderflowError)?;
+        vault.locked_balance = vault.locked_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::UnderflowError)?;
+        vault.last_updated = clock.unix_timestamp;
+        vault.next_sequence().ok_or(VaultError::NumericalOverflow)?;
+        reshare(vault, ctx.accounts.yield_index.as_deref_mut())?;
+
+        // The fund is not a vault, so the whole amount leaves the TVL
+        let counters = &mut ctx.accounts.counters;
+        counters.total_value_locked = counters.total_value_locked
+            .checked_sub(amount)
+            .and_then(|tvl| tvl.checked_sub(withheld))
+            .ok_or(VaultError::UnderflowError)?;
+        counters.last_updated = clock.unix_timestamp;
+
+        let owner_key = vault.owner;
+        let mint_key = vault.mint;
+        let sub_account = vault.sub_account.to_le_bytes();
+        let seeds = &[
+            b"vault",
+            owner_key.as_ref(),
+            mint_key.as_ref(),
+            sub_account.as_ref(),
+            &[vault.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let received = transfer_tokens(
+            &ctx.accounts.token_program,
+            ctx.accounts.vault_token_account.to_account_info(),
+            ctx.accounts.fund_token_account.to_account_info(),
+            vault.to_account_info(),
+            &ctx.accounts.mint,
+            amount,
+            signer_seeds,
+        )?;
+
+        let insurance_fund = &mut ctx.accounts.insurance_fund;
+        insurance_fund.total_contributed = insurance_fund.total_contributed
+            .checked_add(received)
+            .ok_or(VaultError::NumericalOverflow)?;
+        insurance_fund.last_updated = clock.unix_timestamp;
+
+        ctx.accounts.fund_token_account.reload()?;
+        emit!(InsuranceContributionEvent {
+            insurance_fund: insurance_fund.key(),
+            vault: Some(vault.key()),
+            amount: received,
+            fund_balance: ctx.accounts.fund_token_account.amount,
+            sequence: Some(vault.sequence),
+            slot: clock.slot,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!("Settled {} locked tokens into the insurance fund", amount);
+        Ok(())
+    }
+
+    /// Pay bad debt owed to `to_vault` out of the insurance fund of its mint
+    /// (called by authorized programs via CPI). If the fund runs short and its
+    /// socialized-loss mode is on, the rest is socialized across every other
+    /// vault of the mint pro rata to its available balance: the mint's loss
+    /// index rises, each vault pays its part into the loss reserve the next
+    /// time it is touched, and `to_vault` is credited as it is paid out of the
+    /// loss reserve. Collected haircuts go to whichever covered vault is
+    /// touched first.
+    pub fn cover_shortfall(ctx: Context<CoverShortfall>, amount: u64) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+
+        let insurance_fund = &mut ctx.accounts.insurance_fund;
+        let clock = Clock::get()?;
+
+        // The fund pays what it holds
+        let from_fund = amount.min(ctx.accounts.fund_token_account.amount);
+        let fund_balance = ctx.accounts.fund_token_account.amount - from_fund;
+        let mut received = 0;
+        if from_fund > 0 {
+            let mint_key = insurance_fund.mint;
+            let seeds: &[&[u8]] = &[InsuranceFund::SEED, mint_key.as_ref(), &[insurance_fund.bump]];
+            received = transfer_tokens(
+                &ctx.accounts.token_program,
+                ctx.accounts.fund_token_account.to_account_info(),
+                ctx.accounts.to_token_account.to_account_info(),
+                insurance_fund.to_account_info(),
+                &ctx.accounts.mint,
+                from_fund,
+                &[seeds],
+            )?;
+        }
+
+        let to_vault = &mut ctx.accounts.to_vault;
+        let yield_index = &mut ctx.accounts.yield_index;
+        accrue_yield(to_vault, Some(yield_index))?;
+
+        // `to_vault` does not share in a loss it is owed
+        let shortfall = amount - from_fund;
+        let mut socialized = 0;
+        let other_weight = yield_index.total_loss_weight
+            .checked_sub(to_vault.loss_weight)
+            .ok_or(VaultError::UnderflowError)?;
+        if shortfall > 0 && insurance_fund.socialize_losses && other_weight > 0 {
+            // Never more than the other vaults' available balances
+            socialized = shortfall.min(other_weight);
+
+            let increase = (socialized as u128)
+                .checked_mul(YieldIndex::SCALE as u128)
+                .ok_or(VaultError::NumericalOverflow)?
+                .div_ceil(other_weight as u128);
+            let increase = u64::try_from(increase).map_err(|_| VaultError::NumericalOverflow)?;
+            yield_index.loss_index = yield_index.loss_index
+                .checked_add(increase)
+                .ok_or(VaultError::NumericalOverflow)?;
+            yield_index.last_updated = clock.unix_timestamp;
+            to_vault.loss_checkpoint = yield_index.loss_index;
+
+            // Credited to `to_vault` only as the haircuts are collected
+            to_vault.pending_cover = to_vault.pending_cover
+                .checked_add(socialized)
+                .ok_or(VaultError::NumericalOverflow)?;
+
+            emit!(LossSocializedEvent {
+                mint: yield_index.mint,
+                to_vault: to_vault.key(),
+                amount: socialized,
+                loss_index: yield_index.loss_index,
+                total_loss_weight: other_weight,
+                slot: clock.slot,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+
+        to_vault.total_balance = to_vault.total_balance
+            .checked_add(received)
+            .ok_or(VaultError::NumericalOverflow)?;
+        to_vault.available_balance = to_vault.available_balance
+            .checked_add(received)
+            .ok_or(VaultError::NumericalOverflow)?;
+        let withheld = settle_yield(
+            to_vault,
+            Some(&mut *yield_index),
+            Some(&ctx.accounts.yield_reserve),
+            &ctx.accounts.to_token_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+        )?;
+        to_vault.last_updated = clock.unix_timestamp;
+        to_vault.next_sequence().ok_or(VaultError::NumericalOverflow)?;
+        reshare(to_vault, Some(yield_index))?;
+
+        insurance_fund.total_covered = insurance_fund.total_covered
+            .checked_add(from_fund)
+            .ok_or(VaultError::NumericalOverflow)?;
+        insurance_fund.total_socialized = insurance_fund.total_socialized
+            .checked_add(socialized)
+            .ok_or(VaultError::NumericalOverflow)?;
+        insurance_fund.last_updated = clock.unix_timestamp;
+
+        // Fund payouts enter the TVL; socialized amounts only move between
+        // vaults through the loss reserve, less any Token-2022 fees withheld
+        // on the way
+        let counters = &mut ctx.accounts.counters;
+        counters.total_value_locked = counters.total_value_locked
+            .checked_add(received)
+            .and_then(|tvl| tvl.checked_sub(withheld))
+            .ok_or(VaultError::UnderflowError)?;
+        counters.last_updated = clock.unix_timestamp;
+
+        emit!(ShortfallCoveredEvent {
+            insurance_fund: insurance_fund.key(),
+            to_vault: to_vault.key(),
+            program: ctx.accounts.caller_program.key(),
+            amount,
+            from_fund,
+            socialized,
+            uncovered: shortfall - socialized,
+            fund_balance,
+            to_sequence: to_vault.sequence,
+            slot: clock.slot,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!(
+            "Covered {} of a {} token shortfall ({} socialized)",
+            from_fund + socialized,
+            amount,
+            socialized
+        );
+        Ok(())
+    }
+
+    /// Write a `VaultState` of the vault, with the collateral `program` has
+    /// locked in it, to return data. Read-only and permissionless, so other
+    /// programs can CPI into it instead of deserializing `CollateralVault`.
+    pub fn get_vault_state(ctx: Context<GetVaultState>, program: Pubkey) -> Result<()> {
+        // Accrue on a copy; the vault account is not written back
+        let mut vault = ctx.accounts.vault.clone();
+        accrue_yield(&mut vault, ctx.accounts.yield_index.as_deref())?;
+
+        // The lock ledger only exists once the program has locked in this vault
+        let program_lock = &ctx.accounts.program_lock;
+        let program_locked = if program_lock.owner == &crate::ID && !program_lock.data_is_empty() {
+            let data = program_lock.try_borrow_data()?;
+            ProgramLock::try_deserialize(&mut &data[..])?.locked_amount
+        } else {
+            0
+        };
+
+        let state = VaultState {
+            version: VaultState::VERSION,
+            vault: vault.key(),
+            owner: vault.owner,
+            mint: vault.mint,
+            sub_account: vault.sub_account,
+            status: vault.status,
+            total_balance: vault.total_balance,
+            locked_balance: vault.locked_balance,
+            available_balance: vault.available_balance,
+            pending_balance: vault.pending_balance,
+            borrowed: vault.borrowed,
+            program,
+            program_locked,
+            sequence: vault.sequence,
+            slot: Clock::get()?.slot,
+        };
+        set_return_data(&state.try_to_vec()?);
+        Ok(())
+    }
+}
+
+/// Fresh yield index of `mint`, with shares worth one token each
+fn new_yield_index(
+    mint: Pubkey,
+    reserve: Pubkey,
+    include_locked: bool,
+    bump: u8,
+) -> Result<YieldIndex> {
+    msg!("Yield index initialized for mint: {}", mint);
+    Ok(YieldIndex {
+        mint,
+        index: YieldIndex::SCALE,
+        total_shares: 0,
+        include_locked,
+        reserve,
+        total_distributed: 0,
+        loss_index: 0,
+        total_loss_weight: 0,
+        loss_reserve: 0,
+        last_updated: Clock::get()?.unix_timestamp,
+        bump,
+    })
+}
+
+/// Raise a yield index by `received` tokens that just reached its reserve
+fn raise_yield_index(
+    yield_index: &mut YieldIndex,
+    counters: &mut ProtocolCounters,
+    received: u64,
+) -> Result<()> {
+    require!(yield_index.total_shares > 0, VaultError::NoYieldShares);
+    let clock = Clock::get()?;
+
+    // Round the increase down so the yield credited never exceeds `received`
+    let increase = (received as u128)
+        .checked_mul(YieldIndex::SCALE as u128)
+        .ok_or(VaultError::NumericalOverflow)?
+        / yield_index.total_shares as u128;
+    let increase = u64::try_from(increase).map_err(|_| VaultError::NumericalOverflow)?;
+    require!(increase > 0, VaultError::InvalidAmount);
+
+    yield_index.index = yield_index.index
+        .checked_add(increase)
+        .ok_or(VaultError::NumericalOverflow)?;
+    yield_index.total_distributed = yield_index.total_distributed
+        .checked_add(received)
+        .ok_or(VaultError::NumericalOverflow)?;
+    yield_index.last_updated = clock.unix_timestamp;
+
+    // Distributed yield belongs to the vaults as soon as the index moves
+    counters.total_value_locked = counters.total_value_locked
+        .checked_add(received)
+        .ok_or(VaultError::NumericalOverflow)?;
+    counters.last_updated = clock.unix_timestamp;
+
+    emit!(YieldDistributedEvent {
+        mint: yield_index.mint,
+        amount: received,
+        index: yield_index.index,
+        total_shares: yield_index.total_shares,
+        slot: clock.slot,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Distributed {} tokens of yield, index now {}", received, yield_index.index);
+    Ok(())
+}
+
+/// Empty insurance fund of `mint`
+fn new_insurance_fund(
+    mint: Pubkey,
+    token_account: Pubkey,
+    socialize_losses: bool,
+    bump: u8,
+) -> Result<InsuranceFund> {
+    msg!("Insurance fund initialized for mint: {}", mint);
+    Ok(InsuranceFund {
+        mint,
+        token_account,
+        socialize_losses,
+        total_contributed: 0,
+        total_covered: 0,
+        total_socialized: 0,
+        last_updated: Clock::get()?.unix_timestamp,
+        bump,
+    })
+}
+
+fn update_socialize_losses(
+    insurance_fund: &mut InsuranceFund,
+    key: Pubkey,
+    socialize_losses: bool,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    insurance_fund.socialize_losses = socialize_losses;
+    insurance_fund.last_updated = clock.unix_timestamp;
+
+    emit!(SocializeLossesUpdatedEvent {
+        insurance_fund: key,
+        socialize_losses,
+        slot: clock.slot,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!(
+        "Socialized losses {} for mint: {}",
+        if socialize_losses { "enabled" } else { "disabled" },
+        insurance_fund.mint
+    );
+    Ok(())
+}
+
+/// Book `received` tokens of swept fees as a contribution to an insurance fund
+fn credit_fee_sweep(
+    insurance_fund: &mut InsuranceFund,
+    key: Pubkey,
+    received: u64,
+    fund_balance: u64,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    insurance_fund.total_contributed = insurance_fund.total_contributed
+        .checked_add(received)
+        .ok_or(VaultError::NumericalOverflow)?;
+    insurance_fund.last_updated = clock.unix_timestamp;
+
+    emit!(InsuranceContributionEvent {
+        insurance_fund: key,
+        vault: None,
+        amount: received,
+        fund_balance,
+        sequence: None,
+        slot: clock.slot,
+        timestamp: clock.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// Credit the yield a vault earned since its checkpoint to its balances, then
+/// debit the socialized losses its loss weight owes. The tokens stay in the reserve
+/// as `unsettled_yield`, and in the vault token account as `unsettled_loss`,
+/// until `settle_yield`. Without a yield index there is nothing to accrue,
+/// but a vault that already holds a checkpoint must pass it, so leaving the
+/// index out cannot skip a socialized loss.
+fn accrue_yield(vault: &mut Account<CollateralVault>, yield_index: Option<&YieldIndex>) -> Result<()> {
+    let Some(yield_index) = yield_index else {
+        require!(vault.yield_checkpoint == 0, VaultError::YieldIndexRequired);
+        return Ok(());
+    };
+    let increase = yield_index.index
+        .checked_sub(vault.yield_checkpoint)
+        .ok_or(VaultError::UnderflowError)?;
+    if vault.yield_shares > 0 && increase > 0 {
+        // Rounded down per vault, so the total credited never exceeds what was distributed
+        let earned = (vault.yield_shares as u128)
+            .checked_mul(increase as u128)
+            .ok_or(VaultError::NumericalOverflow)?
+            / YieldIndex::SCALE as u128;
+        let earned = u64::try_from(earned).map_err(|_| VaultError::NumericalOverflow)?;
+
+        vault.total_balance = vault.total_balance
+            .checked_add(earned)
+            .ok_or(VaultError::NumericalOverflow)?;
+        vault.available_balance = vault.available_balance
+            .checked_add(earned)
+            .ok_or(VaultError::NumericalOverflow)?;
+        vault.unsettled_yield = vault.unsettled_yield
+            .checked_add(earned)
+            .ok_or(VaultError::NumericalOverflow)?;
+        vault.yield_checkpoint = yield_index.index;
+    }
+
+    accrue_loss(vault, yield_index)
+}
+
+/// Debit the socialized losses a vault's loss weight owes since its checkpoint
+/// from its available balance. Whatever the available balance cannot pay stays
+/// in `unpaid_loss` and is debited once collateral is unlocked or deposited.
+fn accrue_loss(vault: &mut Account<CollateralVault>, yield_index: &YieldIndex) -> Result<()> {
+    let increase = yield_index.loss_index
+        .checked_sub(vault.loss_checkpoint)
+        .ok_or(VaultError::UnderflowError)?;
+    // Rounded up per vault, so the haircuts collected cover what was socialized
+    let owed = (vault.loss_weight as u128)
+        .checked_mul(increase as u128)
+        .ok_or(VaultError::NumericalOverflow)?
+        .div_ceil(YieldIndex::SCALE as u128);
+    let owed = u64::try_from(owed)
+        .ok()
+        .and_then(|owed| owed.checked_add(vault.unpaid_loss))
+        .ok_or(VaultError::NumericalOverflow)?;
+    vault.loss_checkpoint = yield_index.loss_index;
+    if owed == 0 {
+        return Ok(());
+    }
+
+    let haircut = owed.min(vault.available_balance);
+    vault.total_balance = vault.total_balance
+        .checked_sub(haircut)
+        .ok_or(VaultError::UnderflowError)?;
+    vault.available_balance -= haircut;
+    vault.unsettled_loss = vault.unsettled_loss
+        .checked_add(haircut)
+        .ok_or(VaultError::NumericalOverflow)?;
+    vault.unpaid_loss = owed - haircut;
+    Ok(())
+}
+
+/// Re-issue a vault's shares for its current yield-bearing balance at the
+/// current index, and its loss weight for its current available balance, if
+/// its mint has an index
+fn reshare(vault: &mut Account<CollateralVault>, yield_index: Option<&mut YieldIndex>) -> Result<()> {
+    let Some(yield_index) = yield_index else {
+        return Ok(());
+    };
+    let bearing = vault
+        .yield_bearing_balance(yield_index.include_locked)
+        .ok_or(VaultError::NumericalOverflow)?;
+    let shares = yield_index
+        .tokens_to_shares(bearing)
+        .ok_or(VaultError::NumericalOverflow)?;
+
+    yield_index.total_shares = yield_index.total_shares
+        .checked_sub(vault.yield_shares)
+        .ok_or(VaultError::UnderflowError)?
+        .checked_add(shares)
+        .ok_or(VaultError::NumericalOverflow)?;
+    vault.yield_shares = shares;
+    vault.yield_checkpoint = yield_index.index;
+
+    yield_index.total_loss_weight = yield_index.total_loss_weight
+        .checked_sub(vault.loss_weight)
+        .ok_or(VaultError::UnderflowError)?
+        .checked_add(vault.available_balance)
+        .ok_or(VaultError::NumericalOverflow)?;
+    vault.loss_weight = vault.available_balance;
+    Ok(())
+}
+
+/// Settle a vault's accrued yield, socialized losses and pending cover with
+/// the reserve, netting its yield against its losses first. Losses move from
+/// the vault token account into the reserve's `loss_reserve`, signed by the
+/// vault; yield and cover move the other way, signed by the yield index PDA.
+/// Yield is paid out of the reserve outside `loss_reserve`, and cover only out
+/// of `loss_reserve` up to what has been collected, so a vault's balances
+/// never run ahead of its token account. Returns the Token-2022 transfer fees
+/// withheld on the way, which leave the TVL.
+fn settle_yield<'info>(
+    vault: &mut Account<'info, CollateralVault>,
+    yield_index: Option<&mut Account<'info, YieldIndex>>,
+    yield_reserve: Option<&InterfaceAccount<'info, TokenAccount>>,
+    vault_token_account: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+) -> Result<u64> {
+    let (yield_index, yield_reserve) = match (yield_index, yield_reserve) {
+        (Some(yield_index), Some(yield_reserve)) => (yield_index, yield_reserve),
+        // A mint without a yield index has nothing to settle
+        (None, _) => return Ok(0),
+        (Some(_), None) => return err!(VaultError::YieldIndexRequired),
+    };
+
+    // Yield owed to the vault pays its haircut in place, so those reserve
+    // tokens now back the loss reserve
+    let netted = vault.unsettled_yield.min(vault.unsettled_loss);
+    vault.unsettled_yield -= netted;
+    vault.unsettled_loss -= netted;
+    yield_index.loss_reserve = yield_index.loss_reserve
+        .checked_add(netted)
+        .ok_or(VaultError::NumericalOverflow)?;
+
+    let mut withheld = 0;
+    if vault.unsettled_loss > 0 {
+        let amount = vault.unsettled_loss;
+        let owner_key = vault.owner;
+        let mint_key = vault.mint;
+        let sub_account = vault.sub_account.to_le_bytes();
+        let seeds: &[&[u8]] = &[
+            b"vault",
+            owner_key.as_ref(),
+            mint_key.as_ref(),
+            sub_account.as_ref(),
+            &[vault.bump],
+        ];
+        let received = transfer_tokens(
+            token_program,
+            vault_token_account.to_account_info(),
+            yield_reserve.to_account_info(),
+            vault.to_account_info(),
+            mint,
+            amount,
+            &[seeds],
+        )?;
+        yield_index.loss_reserve = yield_index.loss_reserve
+            .checked_add(received)
+            .ok_or(VaultError::NumericalOverflow)?;
+
+        // The vault owes the fee withheld on its haircut as well
+        withheld = amount
+            .checked_sub(received)
+            .ok_or(VaultError::UnderflowError)?;
+        vault.unpaid_loss = vault.unpaid_loss
+            .checked_add(withheld)
+            .ok_or(VaultError::NumericalOverflow)?;
+        vault.unsettled_loss = 0;
+    } else {
+        // Read the balance from the account, which an earlier settlement in
+        // the same instruction may have changed
+        let reserve_balance = anchor_spl::token::accessor::amount(&yield_reserve.to_account_info())?;
+        let amount = vault
+            .unsettled_yield
+            .min(reserve_balance.saturating_sub(yield_index.loss_reserve));
+        if amount > 0 {
+            let credited = pay_from_reserve(
+                yield_index,
+                yield_reserve,
+                vault_token_account,
+                mint,
+                token_program,
+                amount,
+            )?;
+            vault.total_balance = vault.total_balance
+                .checked_sub(amount - credited)
+                .ok_or(VaultError::UnderflowError)?;
+            vault.available_balance = vault.available_balance
+                .checked_sub(amount - credited)
+                .ok_or(VaultError::UnderflowError)?;
+            vault.unsettled_yield -= amount;
+            withheld = amount - credited;
+        }
+    }
+
+    let amount = vault.pending_cover.min(yield_index.loss_reserve);
+    if amount > 0 {
+        let credited = pay_from_reserve(
+            yield_index,
+            yield_reserve,
+            vault_token_account,
+            mint,
+            token_program,
+            amount,
+        )?;
+        yield_index.loss_reserve -= amount;
+        vault.pending_cover -= amount;
+        vault.total_balance = vault.total_balance
+            .checked_add(credited)
+            .ok_or(VaultError::NumericalOverflow)?;
+        vault.available_balance = vault.available_balance
+            .checked_add(credited)
+            .ok_or(VaultError::NumericalOverflow)?;
+        withheld = withheld
+            .checked_add(amount - credited)
+            .ok_or(VaultError::NumericalOverflow)?;
+    }
+    Ok(withheld)
+}
+
+/// Move `amount` from the yield reserve into a vault token account, signed by
+/// the yield index PDA. Returns the amount received after transfer fees.
+fn pay_from_reserve<'info>(
+    yield_index: &Account<'info, YieldIndex>,
+    yield_reserve: &InterfaceAccount<'info, TokenAccount>,
+    vault_token_account: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+) -> Result<u64> {
+    let seeds: &[&[u8]] = &[YieldIndex::SEED, yield_index.mint.as_ref(), &[yield_index.bump]];
+    transfer_tokens(
+        token_program,
+        yield_reserve.to_account_info(),
+        vault_token_account.to_account_info(),
+        yield_index.to_account_info(),
+        mint,
+        amount,
+        &[seeds],
+    )
+}
+
+/// Token-2022 mint extensions collateral may carry. Transfer fees are netted
+/// out of every credit, interest only changes the UI amount and metadata does
+/// not affect transfers.
+const SUPPORTED_MINT_EXTENSIONS: [ExtensionType; 4] = [
+    ExtensionType::TransferFeeConfig,
+    ExtensionType::InterestBearingConfig,
+    ExtensionType::MetadataPointer,
+    ExtensionType::TokenMetadata,
+];
+
+/// Reject Token-2022 mints with an extension outside `SUPPORTED_MINT_EXTENSIONS`
+/// (transfer hooks, permanent delegates, confidential transfers, ...)
+fn check_mint_extensions(mint: &InterfaceAccount<Mint>) -> Result<()> {
+    let info = mint.to_account_info();
+    if *info.owner != spl_token_2022::ID {
+        return Ok(());
+    }
+
+    let data = info.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    for extension in state.get_extension_types()? {
+        require!(
+            SUPPORTED_MINT_EXTENSIONS.contains(&extension),
+            VaultError::UnsupportedMintExtension
+        );
+    }
+    Ok(())
+}
+
+/// Token-2022 transfer fee withheld from a transfer of `amount` in the
+/// current epoch (0 for SPL Token mints and mints without the extension)
+fn transfer_fee(mint: &InterfaceAccount<Mint>, amount: u64) -> Result<u64> {
+    let info = mint.to_account_info();
+    if *info.owner != spl_token_2022::ID {
+        return Ok(0);
+    }
+
+    let data = info.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    let Ok(config) = state.get_extension::<TransferFeeConfig>() else {
+        return Ok(0);
+    };
+    let fee = config
+        .calculate_epoch_fee(Clock::get()?.epoch, amount)
+        .ok_or(VaultError::NumericalOverflow)?;
+    Ok(fee)
+}
+
+/// Token-2022 transfer fee withheld from a transfer that delivers `received`
+/// after the fee in the current epoch (0 for SPL Token mints and mints
+/// without the extension)
+fn inverse_transfer_fee(mint: &InterfaceAccount<Mint>, received: u64) -> Result<u64> {
+    let info = mint.to_account_info();
+    if *info.owner != spl_token_2022::ID {
+        return Ok(0);
+    }
+
+    let data = info.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    let Ok(config) = state.get_extension::<TransferFeeConfig>() else {
+        return Ok(0);
+    };
+    let fee = config
+        .calculate_inverse_epoch_fee(Clock::get()?.epoch, received)
+        .ok_or(VaultError::NumericalOverflow)?;
+    Ok(fee)
+}
+
+/// Move `amount` tokens with `transfer_checked` through either token program,
+/// returning what the destination receives after any transfer fee
+fn transfer_tokens<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    from: AccountInfo<'info>,
+    to: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<u64> {
+    let fee = transfer_fee(mint, amount)?;
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from,
+                mint: mint.to_account_info(),
+                to,
+                authority,
+            },
+            signer_seeds,
+        ),
+        amount,
+        mint.decimals,
+    )?;
+
+    let received = amount
+        .checked_sub(fee)
+        .ok_or(VaultError::UnderflowError)?;
+    Ok(received)
+}
+
+/// Credit `credited` deposited tokens to a vault within the guarded-launch
+/// caps, after crediting its accrued yield so the caps see it
+fn credit_deposit(
+    vault: &mut Account<CollateralVault>,
+    authority: &VaultAuthority,
+    counters: &mut Account<ProtocolCounters>,
+    yield_index: Option<&mut YieldIndex>,
+    credited: u64,
+    now: i64,
+) -> Result<()> {
+    accrue_yield(vault, yield_index.as_deref())?;
+
+    let new_vault_balance = vault.total_balance
+        .checked_add(credited)
+        .ok_or(VaultError::NumericalOverflow)?;
+    require!(
+        authority.max_vault_balance == 0 || new_vault_balance <= authority.max_vault_balance,
+        VaultError::VaultDepositCapExceeded
+    );
+    let new_tvl = counters.total_value_locked
+        .checked_add(credited)
+        .ok_or(VaultError::NumericalOverflow)?;
+    require!(
+        authority.max_tvl == 0 || new_tvl <= authority.max_tvl,
+        VaultError::TvlCapExceeded
+    );
+
+    vault.total_balance = new_vault_balance;
+    vault.available_balance = vault.available_balance
+        .checked_add(credited)
+        .ok_or(VaultError::NumericalOverflow)?;
+    vault.total_deposited = vault.total_deposited
+        .checked_add(credited)
+        .ok_or(VaultError::NumericalOverflow)?;
+    vault.last_updated = now;
+    vault.next_sequence().ok_or(VaultError::NumericalOverflow)?;
+    reshare(vault, yield_index)?;
+
+    counters.total_value_locked = new_tvl;
+    counters.last_updated = now;
+    Ok(())
+}
+
+/// Lock `amount` of a vault's available collateral under `program_lock`,
+/// within the lock cap of the program's authorization
+fn apply_lock(
+    vault: &mut Account<CollateralVault>,
+    program_lock: &mut Account<ProgramLock>,
+    authorized_program: &AuthorizedProgram,
+    yield_index: Option<&mut YieldIndex>,
+    amount: u64,
+    clock: &Clock,
+) -> Result<()> {
+    require!(amount > 0, VaultError::InvalidAmount);
+
+    accrue_yield(vault, yield_index.as_deref())?;
+
+    // Verify sufficient available balance
+    require!(
+        vault.available_balance >= amount,
+        VaultError::InsufficientBalanceToLock
+    );
+
+    // Record the lock against the calling program
+    program_lock.locked_amount = program_lock.locked_amount
+        .checked_add(amount)
+        .ok_or(VaultError::NumericalOverflow)?;
+    program_lock.last_updated = clock.unix_timestamp;
+
+    // Enforce the per-program cap recorded in its authorization
+    require!(
+        authorized_program.allows_lock(program_lock.locked_amount),
+        VaultError::ProgramLockLimitExceeded
+    );
+
+    // Update balances
+    vault.locked_balance = vault.locked_balance
+        .checked_add(amount)
+        .ok_or(VaultError::NumericalOverflow)?;
+    vault.available_balance = vault.available_balance
+        .checked_sub(amount)
+        .ok_or(VaultError::UnderflowError)?;
+    vault.last_updated = clock.unix_timestamp;
+    vault.next_sequence().ok_or(VaultError::NumericalOverflow)?;
+    reshare(vault, yield_index)?;
+
+    emit!(LockEvent {
+        vault: vault.key(),
+        program: program_lock.program,
+        amount,
+        program_locked: program_lock.locked_amount,
+        locked_balance: vault.locked_balance,
+        available_balance: vault.available_balance,
+        sequence: vault.sequence,
+        slot: clock.slot,
+        timestamp: clock.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// Release `amount` of the collateral `program_lock` holds in a vault
+fn apply_unlock(
+    vault: &mut Account<CollateralVault>,
+    program_lock: &mut Account<ProgramLock>,
+    yield_index: Option<&mut YieldIndex>,
+    amount: u64,
+    clock: &Clock,
+) -> Result<()> {
+    require!(amount > 0, VaultError::InvalidAmount);
+
+    accrue_yield(vault, yield_index.as_deref())?;
+
+    // A program may only release collateral it locked itself
+    require!(
+        program_lock.locked_amount >= amount && vault.locked_balance >= amount,
+        VaultError::InvalidUnlockAmount
+    );
+
+    program_lock.locked_amount = program_lock.locked_amount
+        .checked_sub(amount)
+        .ok_or(VaultError::UnderflowError)?;
+    program_lock.last_updated = clock.unix_timestamp;
+
+    // Update balances
+    vault.locked_balance = vault.locked_balance
+        .checked_sub(amount)
+        .ok_or(VaultError::UnderflowError)?;
+    vault.available_balance = vault.available_balance
+        .checked_add(amount)
+        .ok_or(VaultError::NumericalOverflow)?;
+    vault.last_updated = clock.unix_timestamp;
+    vault.next_sequence().ok_or(VaultError::NumericalOverflow)?;
+    reshare(vault, yield_index)?;
+
+    emit!(UnlockEvent {
+        vault: vault.key(),
+        program: program_lock.program,
+        amount,
+        program_locked: program_lock.locked_amount,
+        locked_balance: vault.locked_balance,
+        available_balance: vault.available_balance,
+        sequence: vault.sequence,
+        slot: clock.slot,
+        timestamp: clock.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// Release `amount` of the collateral a disabled program holds in a vault on
+/// behalf of `admin`
+fn force_release(
+    admin: Pubkey,
+    authorized_program: &AuthorizedProgram,
+    vault: &mut Account<CollateralVault>,
+    program_lock: &mut Account<ProgramLock>,
+    yield_index: Option<&mut YieldIndex>,
+    amount: u64,
+) -> Result<()> {
+    require!(!authorized_program.enabled, VaultError::ProgramStillEnabled);
+
+    apply_unlock(vault, program_lock, yield_index, amount, &Clock::get()?)?;
+
+    msg!(
+        "Admin {} force-unlocked {} tokens of program {} in vault {}",
+        admin,
+        amount,
+        authorized_program.program_id,
+        vault.key()
+    );
+    Ok(())
+}
+
+/// Position of the vault among the accounts of a `repay` instruction
+const REPAY_VAULT_ACCOUNT: usize = 0;
+
+/// Require a `repay` of `vault` after the instruction currently executing.
+/// The instructions sysvar only lists top-level instructions, so the repay
+/// cannot be issued through a CPI.
+fn require_repay(instructions: &AccountInfo, vault: &Pubkey) -> Result<()> {
+    let current = instructions_sysvar::load_current_index_checked(instructions)?;
+    let mut index = current as usize + 1;
+    while let Ok(ix) = instructions_sysvar::load_instruction_at_checked(index, instructions) {
+        if ix.program_id == crate::ID
+            && ix.data.get(..8) == Some(&instruction::Repay::DISCRIMINATOR[..])
+            && ix.accounts.get(REPAY_VAULT_ACCOUNT).map(|meta| meta.pubkey) == Some(*vault)
+        {
+            return Ok(());
+        }
+        index += 1;
+    }
+    err!(VaultError::MissingRepay)
+}
+
+/// Check that a batch passes one (vault, program lock) pair
+/// per entry and no vault twice; a repeated vault would be written back twice and lose an entry
+fn check_batch(accounts: &[AccountInfo], entries: usize) -> Result<()> {
+    require!(
+        entries > 0 && accounts.len() == entries * 2,
+        VaultError::InvalidBatch
+    );
+
+    let vaults: Vec<&Pubkey> = accounts.iter().step_by(2).map(|info| info.key).collect();
+    for (i, vault) in vaults.iter().enumerate() {
+        require!(!vaults[..i].contains(vault), VaultError::InvalidBatch);
+    }
+    Ok(())
+}
+
+/// Load a vault passed to a batch instruction, which must hold the batch's mint
+fn load_batch_vault<'info>(
+    info: &'info AccountInfo<'info>,
+    yield_index: &YieldIndex,
+) -> Result<Account<'info, CollateralVault>> {
+    let vault: Account<CollateralVault> = Account::try_from(info)?;
+    require_keys_eq!(vault.mint, yield_index.mint, VaultError::MintMismatch);
+    Ok(vault)
+}
+
+/// Load the `ProgramLock` of `program` in `vault` passed to `batch_lock`,
+/// creating it (funded by `payer`) on the program's first lock in the vault
+fn load_or_create_program_lock<'info>(
+    info: &'info AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    vault: &Pubkey,
+    program: &Pubkey,
+) -> Result<Account<'info, ProgramLock>> {
+    if info.owner == &System::id() {
+        let (address, bump) = ProgramLock::find_address(vault, program);
+        require_keys_eq!(info.key(), address, VaultError::InvalidBatch);
+
+        let signer_seeds: &[&[u8]] = &[ProgramLock::SEED, vault.as_ref(), program.as_ref(), &[bump]];
+        create_account(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                CreateAccount {
+                    from: payer.to_account_info(),
+                    to: info.clone(),
+                },
+                &[signer_seeds],
+            ),
+            Rent::get()?.minimum_balance(ProgramLock::LEN),
+            ProgramLock::LEN as u64,
+            &crate::ID,
+        )?;
+
+        let record = ProgramLock {
+            vault: *vault,
+            program: *program,
+            locked_amount: 0,
+            last_updated: 0,
+            bump,
+        };
+        record.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+    }
+
+    // Existing ledgers only live at their PDA, so matching fields identify it
+    let program_lock: Account<ProgramLock> = Account::try_from(info)?;
+    require!(
+        program_lock.vault == *vault && program_lock.program == *program,
+        VaultError::InvalidBatch
+    );
+    Ok(program_lock)
+}
+
+/// Move a vault to `status` on behalf of `admin`; the event is the audit
+/// record of every freeze and unfreeze
+fn set_vault_status(
+    admin: Pubkey,
+    vault: &mut Account<CollateralVault>,
+    status: VaultStatus,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let previous_status = vault.status;
+    vault.status = status;
+    vault.last_updated = clock.unix_timestamp;
+    vault.next_sequence().ok_or(VaultError::NumericalOverflow)?;
+
+    emit!(VaultFreezeEvent {
+        vault: vault.key(),
+        admin,
+        previous_status,
+        status,
+        sequence: vault.sequence,
+        slot: clock.slot,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Vault {} status: {:?} -> {:?}", vault.key(), previous_status, status);
+    Ok(())
+}
+
+/// Record a withdrawal in the vault's and the protocol's rolling rate-limit
+/// windows, failing if either limit would be exceeded
+fn enforce_withdrawal_limits(
+    authority: &Account<VaultAuthority>,
+    vault: &mut Account<CollateralVault>,
+    counters: &mut Account<ProtocolCounters>,
+    amount: u64,
+    now: i64,
+) -> Result<()> {
+    let window = authority.withdrawal_window;
+    if window == 0 {
+        return Ok(());
+    }
+    let origin = authority.withdrawal_window_start;
+
+    let vault = &mut **vault;
+    let vault_withdrawn = vault
+        .withdrawal_window
+        .record(&mut vault.previous_window_withdrawn, now, window, origin, amount)
+        .ok_or(VaultError::NumericalOverflow)?;
+    require!(
+        authority.max_vault_withdrawal == 0 || vault_withdrawn <= authority.max_vault_withdrawal,
+        VaultError::VaultWithdrawalRateLimited
+    );
+
+    let counters = &mut **counters;
+    let global_withdrawn = counters
+        .withdrawal_window
+        .record(&mut counters.previous_window_withdrawn, now, window, origin, amount)
+        .ok_or(VaultError::NumericalOverflow)?;
+    require!(
+        authority.max_global_withdrawal == 0
+            || global_withdrawn <= authority.max_global_withdrawal,
+        VaultError::GlobalWithdrawalRateLimited
+    );
+    Ok(())
+}
+
+// ============ Admin Actions ============
+// Shared by the single-admin instructions and `execute_proposal`
+
+fn add_program(
+    authority: &Account<VaultAuthority>,
+    authorized_program: &mut AuthorizedProgram,
+    program_id: Pubkey,
+    label: String,
+    max_lock: u64,
+) -> Result<()> {
+    require!(
+        label.len() <= AuthorizedProgram::MAX_LABEL_LEN,
+        VaultError::ProgramLabelTooLong
+    );
+
+    let clock = Clock::get()?;
+    // Freshly created PDAs are zeroed; keep created_at of re-enabled programs
+    if authorized_program.program_id == Pubkey::default() {
+        authorized_program.program_id = program_id;
+        authorized_program.created_at = clock.unix_timestamp;
+        authorized_program.bump = AuthorizedProgram::find_address(&program_id).1;
+    }
+    authorized_program.label = label.clone();
+    authorized_program.max_lock = max_lock;
+    authorized_program.enabled = true;
+
+    emit!(AuthorityUpdatedEvent {
+        authority: authority.key(),
+        program: program_id,
+        authorized: true,
+        label,
+        max_lock,
+        slot: clock.slot,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Added authorized program: {}", program_id);
+    Ok(())
+}
+
+fn remove_program(
+    authority: &Account<VaultAuthority>,
+    authorized_program: &mut AuthorizedProgram,
+    program_id: Pubkey,
+) -> Result<()> {
+    require_keys_eq!(
+        authorized_program.program_id,
+        program_id,
+        VaultError::ProgramNotAuthorized
+    );
+    authorized_program.enabled = false;
+
+    let clock = Clock::get()?;
+    emit!(AuthorityUpdatedEvent {
+        authority: authority.key(),
+        program: program_id,
+        authorized: false,
+        label: authorized_program.label.clone(),
+        max_lock: authorized_program.max_lock,
+        slot: clock.slot,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Removed authorized program: {}", program_id);
+    Ok(())
+}
+
+/// Load the `AuthorizedProgram` PDA of `program_id` passed to `execute_proposal`,
+/// creating it (funded by `payer`) if it does not exist yet. The caller writes
+/// the record back with `try_serialize`.
+fn load_or_create_authorized_program<'info>(
+    info: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    program_id: &Pubkey,
+) -> Result<AuthorizedProgram> {
+    let (address, bump) = AuthorizedProgram::find_address(program_id);
+    require_keys_eq!(info.key(), address, VaultError::InvalidVaultState);
+
+    if info.owner == &System::id() {
+        let signer_seeds: &[&[u8]] = &[AuthorizedProgram::SEED, program_id.as_ref(), &[bump]];
+        create_account(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                CreateAccount {
+                    from: payer.to_account_info(),
+                    to: info.clone(),
+                },
+                &[signer_seeds],
+            ),
+            Rent::get()?.minimum_balance(AuthorizedProgram::LEN),
+            AuthorizedProgram::LEN as u64,
+            &crate::ID,
+        )?;
+
+        // A zeroed record, filled in by `add_program`
+        return Ok(AuthorizedProgram {
+            program_id: Pubkey::default(),
+            label: String::new(),
+            max_lock: 0,
+            enabled: false,
+            created_at: 0,
+            bump,
+        });
+    }
+
+    require_keys_eq!(*info.owner, crate::ID, VaultError::InvalidVaultState);
+    AuthorizedProgram::try_deserialize(&mut &info.try_borrow_data()?[..])
+}
+
+/// Create the account of a PDA owned by this program, for proposals that open
+/// accounts the single-admin instructions create through `init`
+fn create_pda_account<'info>(
+    info: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    signer_seeds: &[&[u8]],
+    space: usize,
+) -> Result<()> {
+    create_account(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            CreateAccount {
+                from: payer.to_account_info(),
+                to: info.clone(),
+            },
+            &[signer_seeds],
+        ),
+        Rent::get()?.minimum_balance(space),
+        space as u64,
+        &crate::ID,
+    )
+}
+
+/// Create the associated token account of `owner` for `mint`
+fn create_associated_account<'info>(
+    token_account: &AccountInfo<'info>,
+    owner: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+    associated_token_program: &Program<'info, AssociatedToken>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    associated_token::create(CpiContext::new(
+        associated_token_program.to_account_info(),
+        associated_token::Create {
+            payer: payer.to_account_info(),
+            associated_token: token_account.clone(),
+            authority: owner.clone(),
+            mint: mint.to_account_info(),
+            system_program: system_program.to_account_info(),
+            token_program: token_program.to_account_info(),
+        },
+    ))
+}
+
+fn set_pending_admin(authority: &mut Account<VaultAuthority>, new_admin: Pubkey) -> Result<()> {
+    authority.pending_admin = Some(new_admin);
+
+    let clock = Clock::get()?;
+    emit!(AdminProposedEvent {
+        authority: authority.key(),
+        admin: authority.admin,
+        pending_admin: new_admin,
+        slot: clock.slot,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Proposed new admin: {}", new_admin);
+    Ok(())
+}
+
+fn update_withdrawal_delay(
+    authority: &mut Account<VaultAuthority>,
+    withdrawal_delay: i64,
+) -> Result<()> {
+    require!(
+        (0..=VaultAuthority::MAX_WITHDRAWAL_DELAY).contains(&withdrawal_delay),
+        VaultError::InvalidWithdrawalDelay
+    );
+
+    authority.withdrawal_delay = withdrawal_delay;
+
+    let clock = Clock::get()?;
+    emit!(WithdrawalDelayUpdatedEvent {
+        authority: authority.key(),
+        withdrawal_delay,
+        slot: clock.slot,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Withdrawal delay set to {} seconds", withdrawal_delay);
+    Ok(())
+}
+
+fn update_deposit_caps(
+    authority: &mut Account<VaultAuthority>,
+    max_vault_balance: u64,
+    max_tvl: u64,
+) -> Result<()> {
+    authority.max_vault_balance = max_vault_balance;
+    authority.max_tvl = max_tvl;
+
+    let clock = Clock::get()?;
+    emit!(DepositCapsUpdatedEvent {
+        authority: authority.key(),
+        max_vault_balance,
+        max_tvl,
+        slot: clock.slot,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Deposit caps set: vault {}, TVL {}", max_vault_balance, max_tvl);
+    Ok(())
+}
+
+fn update_withdrawal_limits(
+    authority: &mut Account<VaultAuthority>,
+    window: i64,
+    max_vault_withdrawal: u64,
+    max_global_withdrawal: u64,
+) -> Result<()> {
+    require!(
+        (0..=VaultAuthority::MAX_WITHDRAWAL_WINDOW).contains(&window),
+        VaultError::InvalidWithdrawalWindow
+    );
+
+    let clock = Clock::get()?;
+    if window != authority.withdrawal_window {
+        // Buckets cut for the old length are dropped as they are next touched
+        authority.withdrawal_window_start = clock.unix_timestamp;
+    }
+    authority.withdrawal_window = window;
+    authority.max_vault_withdrawal = max_vault_withdrawal;
+    authority.max_global_withdrawal = max_global_withdrawal;
+
+    emit!(WithdrawalLimitsUpdatedEvent {
+        authority: authority.key(),
+        window,
+        max_vault_withdrawal,
+        max_global_withdrawal,
+        slot: clock.slot,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!(
+        "Withdrawal limits set: vault {}, global {} per {} seconds",
+        max_vault_withdrawal,
+        max_global_withdrawal,
+        window
+    );
+    Ok(())
+}
+
+fn update_fee(authority: &mut Account<VaultAuthority>, fee_bps: u16) -> Result<()> {
+    require!(fee_bps <= VaultAuthority::MAX_FEE_BPS, VaultError::InvalidFee);
+
+    authority.fee_bps = fee_bps;
+
+    let clock = Clock::get()?;
+    emit!(FeeUpdatedEvent {
+        authority: authority.key(),
+        fee_bps,
+        slot: clock.slot,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Protocol fee set to {} bps", fee_bps);
+    Ok(())
+}
+
+fn update_borrow_fee(authority: &mut Account<VaultAuthority>, borrow_fee_bps: u16) -> Result<()> {
+    require!(borrow_fee_bps <= VaultAuthority::MAX_FEE_BPS, VaultError::InvalidFee);
+
+    authority.borrow_fee_bps = borrow_fee_bps;
+
+    let clock = Clock::get()?;
+    emit!(BorrowFeeUpdatedEvent {
+        authority: authority.key(),
+        borrow_fee_bps,
+        slot: clock.slot,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Borrow fee set to {} bps", borrow_fee_bps);
+    Ok(())
+}
+
+/// Move the full balance of a treasury token account into `destination`,
+/// signing as the treasury PDA. Returns what `destination` received.
+fn sweep_fees<'info>(
+    treasury: &AccountInfo<'info>,
+    treasury_token_account: &InterfaceAccount<'info, TokenAccount>,
+    destination: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+) -> Result<u64> {
+    let amount = treasury_token_account.amount;
+    require!(amount > 0, VaultError::NoFeesToCollect);
+
+    let received = transfer_from_treasury(
+        treasury,
+        treasury_token_account,
+        destination,
+        mint,
+        token_program,
+        amount,
+    )?;
+
+    let clock = Clock::get()?;
+    emit!(FeesCollectedEvent {
+        treasury_token_account: treasury_token_account.key(),
+        destination: destination.key(),
+        mint: treasury_token_account.mint,
+        amount,
+        slot: clock.slot,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Collected {} tokens of fees", amount);
+    Ok(received)
+}
+
+/// Move `amount` tokens out of a treasury token account, signed by the
+/// treasury PDA. Returns what `destination` received.
+fn transfer_from_treasury<'info>(
+    treasury: &AccountInfo<'info>,
+    treasury_token_account: &InterfaceAccount<'info, TokenAccount>,
+    destination: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+) -> Result<u64> {
+    let (treasury_address, bump) = VaultAuthority::find_treasury_address();
+    require_keys_eq!(treasury.key(), treasury_address, VaultError::InvalidTreasuryAccount);
+    require_keys_eq!(
+        treasury_token_account.owner,
+        treasury_address,
+        VaultError::InvalidTreasuryAccount
+    );
+    require_keys_eq!(destination.mint, treasury_token_account.mint, VaultError::MintMismatch);
+    require_keys_eq!(mint.key(), treasury_token_account.mint, VaultError::MintMismatch);
+
+    let seeds: &[&[u8]] = &[VaultAuthority::TREASURY_SEED, &[bump]];
+    transfer_tokens(
+        token_program,
+        treasury_token_account.to_account_info(),
+        destination.to_account_info(),
+        treasury.clone(),
+        mint,
+        amount,
+        &[seeds],
+    )
+}
+
+fn set_paused(authority: &mut Account<VaultAuthority>, paused: bool, reason: u8) -> Result<()> {
+    authority.paused = paused;
+    authority.pause_reason = reason;
+
+    let clock = Clock::get()?;
+    emit!(PauseEvent {
+        authority: authority.key(),
+        paused,
+        reason,
+        slot: clock.slot,
+        timestamp: clock.unix_timestamp,
+    });
+
+    if paused {
+        msg!("Protocol paused (reason {})", reason);
+    } else {
+        msg!("Protocol unpaused");
+    }
+    Ok(())
+}
+
+// ============ Account Validation Contexts ============
+
+#[derive(Accounts)]
+#[instruction(sub_account: u16)]
+pub struct InitializeVault<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        init,
+        payer = user,
+        space = CollateralVault::LEN,
+        seeds = [
+            b"vault",
+            user.key().as_ref(),
+            mint.key().as_ref(),
+            &sub_account.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub vault: Account<'info, CollateralVault>,
+
+    #[account(
+        init,
+        payer = user,
+        associated_token::mint = mint,
+        associated_token::authority = vault,
+        associated_token::token_program = token_program,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseVault<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [
+            b"vault",
+            user.key().as_ref(),
+            vault.mint.as_ref(),
+            &vault.sub_account.to_le_bytes(),
+        ],
+        bump = vault.bump,
+        constraint = vault.owner == user.key() @ VaultError::UnauthorizedOwner,
+    )]
+    pub vault: Account<'info, CollateralVault>,
+
+    #[account(
+        mut,
+        address = vault.token_account @ VaultError::InvalidVaultState,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateVault<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: an old-layout vault does not deserialize as `CollateralVault`;
+    /// the handler checks its discriminator and size
+    #[account(mut, owner = crate::ID @ VaultError::InvalidVaultState)]
+    pub vault: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeAuthority<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = VaultAuthority::LEN,
+        seeds = [b"authority"],
+        bump
+    )]
+    pub authority: Account<'info, VaultAuthority>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = ProtocolCounters::LEN,
+        seeds = [b"protocol_counters"],
+        bump
+    )]
+    pub counters: Account<'info, ProtocolCounters>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageAuthority<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"authority"],
+        bump = authority.bump,
+        has_one = admin @ VaultError::UnauthorizedAdmin
+    )]
+    pub authority: Account<'info, VaultAuthority>,
+}
+
+#[derive(Accounts)]
+#[instruction(program_id: Pubkey)]
+pub struct AddAuthorizedProgram<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"authority"],
+        bump = authority.bump,
+        has_one = admin @ VaultError::UnauthorizedAdmin
+    )]
+    pub authority: Account<'info, VaultAuthority>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = AuthorizedProgram::LEN,
+        seeds = [b"authorized_program", program_id.as_ref()],
+        bump
+    )]
+    pub authorized_program: Account<'info, AuthorizedProgram>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(program_id: Pubkey)]
+pub struct RemoveAuthorizedProgram<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"authority"],
+        bump = authority.bump,
+        has_one = admin @ VaultError::UnauthorizedAdmin
+    )]
+    pub authority: Account<'info, VaultAuthority>,
+
+    #[account(
+        mut,
+        seeds = [b"authorized_program", program_id.as_ref()],
+        bump = authorized_program.bump,
+    )]
+    pub authorized_program: Account<'info, AuthorizedProgram>,
+}
+
+#[derive(Accounts)]
+#[instruction(program_id: Pubkey)]
+pub struct MigrateAuthorizedProgram<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"authority"],
+        bump = authority.bump,
+        constraint = authority.authorized_programs.contains(&program_id) @ VaultError::ProgramNotAuthorized,
+    )]
+    pub authority: Account<'info, VaultAuthority>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = AuthorizedProgram::LEN,
+        seeds = [b"authorized_program", program_id.as_ref()],
+        bump
+    )]
+    pub authorized_program: Account<'info, AuthorizedProgram>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    pub pending_admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"authority"],
+        bump = authority.bump,
+        constraint = authority.pending_admin == Some(pending_admin.key()) @ VaultError::NotPendingAdmin,
+    )]
+    pub authority: Account<'info, VaultAuthority>,
+}
+
+#[derive(Accounts)]
+pub struct FreezeVault<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"authority"],
+        bump = authority.bump,
+        has_one = admin @ VaultError::UnauthorizedAdmin
+    )]
+    pub authority: Account<'info, VaultAuthority>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"vault",
+            vault.owner.as_ref(),
+            vault.mint.as_ref(),
+            &vault.sub_account.to_le_bytes(),
+        ],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, CollateralVault>,
+}
+
+#[derive(Accounts)]
+pub struct ForceUnlock<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"authority"],
+        bump = authority.bump,
+        has_one = admin @ VaultError::UnauthorizedAdmin
+    )]
+    pub authority: Account<'info, VaultAuthority>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"vault",
+            vault.owner.as_ref(),
+            vault.mint.as_ref(),
+            &vault.sub_account.to_le_bytes(),
+        ],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, CollateralVault>,
+
+    #[account(
+        mut,
+        seeds = [b"yield_index", vault.mint.as_ref()],
+        bump = yield_index.bump,
+    )]
+    pub yield_index: Option<Account<'info, YieldIndex>>,
+
+    #[account(
+        seeds = [AuthorizedProgram::SEED, program_lock.program.as_ref()],
+        bump = authorized_program.bump,
+    )]
+    pub authorized_program: Account<'info, AuthorizedProgram>,
+
+    #[account(
+        mut,
+        seeds = [b"program_lock", vault.key().as_ref(), program_lock.program.as_ref()],
+        bump = program_lock.bump,
+    )]
+    pub program_lock: Account<'info, ProgramLock>,
+}
+
+#[derive(Accounts)]
+pub struct EnableMultisig<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"authority"],
+        bump = authority.bump,
+        has_one = admin @ VaultError::UnauthorizedAdmin
+    )]
+    pub authority: Account<'info, VaultAuthority>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = AdminMultisig::LEN,
+        seeds = [b"multisig"],
+        bump
+    )]
+    pub multisig: Account<'info, AdminMultisig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateProposal<'info> {
+    #[account(
+        mut,
+        constraint = multisig.is_signer(&proposer.key()) @ VaultError::NotMultisigSigner,
+    )]
+    pub proposer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig"],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, AdminMultisig>,
+
+    #[account(
+        init,
+        payer = proposer,
+        space = AdminProposal::LEN,
+        seeds = [b"proposal", multisig.proposal_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub proposal: Account<'info, AdminProposal>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveProposal<'info> {
+    #[account(
+        constraint = multisig.is_signer(&signer.key()) @ VaultError::NotMultisigSigner,
+    )]
+    pub signer: Signer<'info>,
+
+    #[account(
+        seeds = [b"multisig"],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, AdminMultisig>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal.index.to_le_bytes().as_ref()],
+        bump = proposal.bump,
+        constraint = proposal.signer_set_seqno == multisig.signer_set_seqno @ VaultError::StaleProposal,
+    )]
+    pub proposal: Account<'info, AdminProposal>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteProposal<'info> {
+    /// Also pays for the `AuthorizedProgram` PDA created by `AddAuthorizedProgram`
+    #[account(
+        mut,
+        constraint = multisig.is_signer(&executor.key()) @ VaultError::NotMultisigSigner,
+    )]
+    pub executor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig"],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, AdminMultisig>,
+
+    /// The multisig must still hold the admin role
+    #[account(
+        mut,
+        seeds = [b"authority"],
+        bump = authority.bump,
+        constraint = authority.admin == multisig.key() @ VaultError::UnauthorizedAdmin,
+    )]
+    pub authority: Account<'info, VaultAuthority>,
+
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [b"proposal", proposal.index.to_le_bytes().as_ref()],
+        bump = proposal.bump,
+        constraint = proposal.signer_set_seqno == multisig.signer_set_seqno @ VaultError::StaleProposal,
+    )]
+    pub proposal: Account<'info, AdminProposal>,
+
+    /// Receives the proposal rent back
+    #[account(
+        mut,
+        address = proposal.proposer @ VaultError::InvalidVaultState,
+    )]
+    pub proposer: SystemAccount<'info>,
+
+    /// Only required by `AdminAction::SetVaultFrozen` and `ForceUnlock`
+    #[account(mut)]
+    pub vault: Option<Account<'info, CollateralVault>>,
+
+    /// CHECK: `AuthorizedProgram` PDA of the action's target program, only
+    /// required by `Add/RemoveAuthorizedProgram` and `ForceUnlock`; its address
+    /// is checked against the proposal and `AddAuthorizedProgram` creates it
+    #[account(mut)]
+    pub authorized_program: Option<UncheckedAccount<'info>>,
+
+    /// Only required by `AdminAction::ForceUnlock`
+    #[account(mut)]
+    pub program_lock: Option<Account<'info, ProgramLock>>,
+
+    /// CHECK: Treasury PDA, only required by `AdminAction::CollectFees`;
+    /// verified by `sweep_fees`
+    pub treasury: Option<UncheckedAccount<'info>>,
+
+    /// Only required by `AdminAction::CollectFees`
+    #[account(mut)]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Receives a treasury payment: the destination of `AdminAction::CollectFees`,
+    /// the yield reserve of `AdminAction::DistributeYield` or the fund token
+    /// account of `AdminAction::CollectFeesToInsurance`
+    #[account(mut)]
+    pub fee_destination: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: `YieldIndex` PDA of the action's mint, only required by
+    /// `Initialize/DistributeYield` and `ForceUnlock`; its address is checked
+    /// against the proposal and `InitializeYieldIndex` creates it
+    #[account(mut)]
+    pub yield_index: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: `InsuranceFund` PDA of the action's mint, only required by the
+    /// insurance fund actions; its address is checked against the proposal and
+    /// `InitializeInsuranceFund` creates it
+    #[account(mut)]
+    pub insurance_fund: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Associated token account created for a new yield index or
+    /// insurance fund, only required by `InitializeYieldIndex` and
+    /// `InitializeInsuranceFund`; its address is checked by the associated
+    /// token program
+    #[account(mut)]
+    pub reserve_token_account: Option<UncheckedAccount<'info>>,
+
+    /// Only required by `AdminAction::DistributeYield`
+    #[account(
+        mut,
+        seeds = [b"protocol_counters"],
+        bump = counters.bump,
+    )]
+    pub counters: Option<Account<'info, ProtocolCounters>>,
+
+    /// Mint of the action, required by the actions that move or hold tokens
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CollectFees<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"authority"],
+        bump = authority.bump,
+        has_one = admin @ VaultError::UnauthorizedAdmin
+    )]
+    pub authority: Account<'info, VaultAuthority>,
+
+    /// CHECK: PDA owning the treasury token accounts; holds no data and only
+    /// signs the sweep
+    #[account(
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeYieldIndex<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"authority"],
+        bump = authority.bump,
+        has_one = admin @ VaultError::UnauthorizedAdmin
+    )]
+    pub authority: Account<'info, VaultAuthority>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = YieldIndex::LEN,
+        seeds = [b"yield_index", mint.key().as_ref()],
+        bump
+    )]
+    pub yield_index: Account<'info, YieldIndex>,
+
+    #[account(
+        init,
+        payer = admin,
+        associated_token::mint = mint,
+        associated_token::authority = yield_index,
+        associated_token::token_program = token_program,
+    )]
+    pub yield_reserve: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DistributeYield<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"authority"],
+        bump = authority.bump,
+        has_one = admin @ VaultError::UnauthorizedAdmin
+    )]
+    pub authority: Account<'info, VaultAuthority>,
+
+    #[account(
+        mut,
+        seeds = [b"yield_index", yield_index.mint.as_ref()],
+        bump = yield_index.bump,
+    )]
+    pub yield_index: Account<'info, YieldIndex>,
+
+    #[account(
+        mut,
+        address = yield_index.reserve @ VaultError::InvalidVaultState,
+    )]
+    pub yield_reserve: InterfaceAccount<'info, TokenAccount>,
+
+    /// Admin token account the yield is paid from
+    #[account(
+        mut,
+        constraint = source_token_account.mint == yield_index.mint @ VaultError::MintMismatch,
+    )]
+    pub source_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol_counters"],
+        bump = counters.bump,
+    )]
+    pub counters: Account<'info, ProtocolCounters>,
+
+    #[account(
+        address = yield_index.mint @ VaultError::MintMismatch,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeInsuranceFund<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"authority"],
+        bump = authority.bump,
+        has_one = admin @ VaultError::UnauthorizedAdmin
+    )]
+    pub authority: Account<'info, VaultAuthority>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = InsuranceFund::LEN,
+        seeds = [b"insurance_fund", mint.key().as_ref()],
+        bump
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    #[account(
+        init,
+        payer = admin,
+        associated_token::mint = mint,
+        associated_token::authority = insurance_fund,
+        associated_token::token_program = token_program,
+    )]
+    pub fund_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageInsuranceFund<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"authority"],
+        bump = authority.bump,
+        has_one = admin @ VaultError::UnauthorizedAdmin
+    )]
+    pub authority: Account<'info, VaultAuthority>,
+
+    #[account(
+        mut,
+        seeds = [b"insurance_fund", insurance_fund.mint.as_ref()],
+        bump = insurance_fund.bump,
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+}
+
+#[derive(Accounts)]
+pub struct CollectFeesToInsurance<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"authority"],
+        bump = authority.bump,
+        has_one = admin @ VaultError::UnauthorizedAdmin
+    )]
+    pub authority: Account<'info, VaultAuthority>,
+
+    /// CHECK: PDA owning the treasury token accounts; holds no data and only
+    /// signs the sweep
+    #[account(
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"insurance_fund", insurance_fund.mint.as_ref()],
+        bump = insurance_fund.bump,
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    #[account(
+        mut,
+        address = insurance_fund.token_account @ VaultError::InvalidVaultState,
+    )]
+    pub fund_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        address = insurance_fund.mint @ VaultError::MintMismatch,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"vault",
+            user.key().as_ref(),
+            vault.mint.as_ref(),
+            &vault.sub_account.to_le_bytes(),
+        ],
+        bump = vault.bump,
+        has_one = owner @ VaultError::UnauthorizedOwner,
+    )]
+    pub vault: Account<'info, CollateralVault>,
+
+    #[account(
+        mut,
+        constraint = user_token_account.mint == vault.mint @ VaultError::MintMismatch,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = vault.token_account @ VaultError::InvalidVaultState,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"authority"],
+        bump = authority.bump,
+        constraint = !authority.paused @ VaultError::ProtocolPaused,
+    )]
+    pub authority: Account<'info, VaultAuthority>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol_counters"],
+        bump = counters.bump,
+    )]
+    pub counters: Account<'info, ProtocolCounters>,
+
+    #[account(
+        mut,
+        seeds = [b"yield_index", vault.mint.as_ref()],
+        bump = yield_index.bump,
+    )]
+    pub yield_index: Option<Account<'info, YieldIndex>>,
+
+    #[account(
+        address = vault.mint @ VaultError::MintMismatch,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub owner: SystemAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct DepositFor<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"vault",
+            beneficiary.key().as_ref(),
+            vault.mint.as_ref(),
+            &vault.sub_account.to_le_bytes(),
+        ],
+        bump = vault.bump,
+        constraint = vault.owner == beneficiary.key() @ VaultError::UnauthorizedOwner,
+    )]
+    pub vault: Account<'info, CollateralVault>,
+
+    #[account(
+        mut,
+        constraint = payer_token_account.mint == vault.mint @ VaultError::MintMismatch,
+    )]
+    pub payer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = vault.token_account @ VaultError::InvalidVaultState,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"authority"],
+        bump = authority.bump,
+        constraint = !authority.paused @ VaultError::ProtocolPaused,
+    )]
+    pub authority: Account<'info, VaultAuthority>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol_counters"],
+        bump = counters.bump,
+    )]
+    pub counters: Account<'info, ProtocolCounters>,
+
+    #[account(
+        mut,
+        seeds = [b"yield_index", vault.mint.as_ref()],
+        bump = yield_index.bump,
+    )]
+    pub yield_index: Option<Account<'info, YieldIndex>>,
+
+    #[account(
+        address = vault.mint @ VaultError::MintMismatch,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Owner of the vault being funded; does not sign
+    pub beneficiary: SystemAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"vault",
+            user.key().as_ref(),
+            vault.mint.as_ref(),
+            &vault.sub_account.to_le_bytes(),
+        ],
+        bump = vault.bump,
+        has_one = owner @ VaultError::UnauthorizedOwner,
+        constraint = !vault.is_frozen() @ VaultError::VaultFrozen,
+    )]
+    pub vault: Account<'info, CollateralVault>,
+
+    #[account(
+        mut,
+        constraint = user_token_account.mint == vault.mint @ VaultError::MintMismatch,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = vault.token_account @ VaultError::InvalidVaultState,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"authority"],
+        bump = authority.bump,
+        constraint = !authority.paused @ VaultError::ProtocolPaused,
+    )]
+    pub authority: Account<'info, VaultAuthority>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol_counters"],
+        bump = counters.bump,
+    )]
+    pub counters: Account<'info, ProtocolCounters>,
+
+    /// Treasury token account of the vault's mint that receives the protocol fee
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == VaultAuthority::find_treasury_address().0
+            @ VaultError::InvalidTreasuryAccount,
+        constraint = treasury_token_account.mint == vault.mint @ VaultError::InvalidTreasuryAccount,
+    )]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"yield_index", vault.mint.as_ref()],
+        bump = yield_index.bump,
+    )]
+    pub yield_index: Option<Account<'info, YieldIndex>>,
+
+    #[account(
+        mut,
+        constraint = yield_index.as_ref().map(|index| index.reserve) == Some(yield_reserve.key())
+            @ VaultError::InvalidVaultState,
+    )]
+    pub yield_reserve: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        address = vault.mint @ VaultError::MintMismatch,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub owner: SystemAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct RequestWithdrawal<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"vault",
+            user.key().as_ref(),
+            vault.mint.as_ref(),
+            &vault.sub_account.to_le_bytes(),
+        ],
+        bump = vault.bump,
+        constraint = vault.owner == user.key() @ VaultError::UnauthorizedOwner,
+        constraint = !vault.is_frozen() @ VaultError::VaultFrozen,
+    )]
+    pub vault: Account<'info, CollateralVault>,
+
+    #[account(
+        seeds = [b"authority"],
+        bump = authority.bump,
+        constraint = !authority.paused @ VaultError::ProtocolPaused,
+    )]
+    pub authority: Account<'info, VaultAuthority>,
+
+    #[account(
+        mut,
+        seeds = [b"yield_index", vault.mint.as_ref()],
+        bump = yield_index.bump,
+    )]
+    pub yield_index: Option<Account<'info, YieldIndex>>,
+
+    /// One outstanding request per vault; it is closed on execute or cancel
+    #[account(
+        init,
+        payer = user,
+        space = PendingWithdrawal::LEN,
+        seeds = [b"pending_withdrawal", vault.key().as_ref()],
+        bump
+    )]
+    pub pending_withdrawal: Account<'info, PendingWithdrawal>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteWithdrawal<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"vault",
+            user.key().as_ref(),
+            vault.mint.as_ref(),
+            &vault.sub_account.to_le_bytes(),
+        ],
+        bump = vault.bump,
+        constraint = vault.owner == user.key() @ VaultError::UnauthorizedOwner,
+        constraint = !vault.is_frozen() @ VaultError::VaultFrozen,
+    )]
+    pub vault: Account<'info, CollateralVault>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"pending_withdrawal", vault.key().as_ref()],
+        bump = pending_withdrawal.bump,
+    )]
+    pub pending_withdrawal: Account<'info, PendingWithdrawal>,
+
+    #[account(
+        mut,
+        constraint = user_token_account.mint == vault.mint @ VaultError::MintMismatch,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = vault.token_account @ VaultError::InvalidVaultState,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"authority"],
+        bump = authority.bump,
+        constraint = !authority.paused @ VaultError::ProtocolPaused,
+    )]
+    pub authority: Account<'info, VaultAuthority>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol_counters"],
+        bump = counters.bump,
+    )]
+    pub counters: Account<'info, ProtocolCounters>,
+
+    /// Treasury token account of the vault's mint that receives the protocol fee
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == VaultAuthority::find_treasury_address().0
+            @ VaultError::InvalidTreasuryAccount,
+        constraint = treasury_token_account.mint == vault.mint @ VaultError::InvalidTreasuryAccount,
+    )]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"yield_index", vault.mint.as_ref()],
+        bump = yield_index.bump,
+    )]
+    pub yield_index: Option<Account<'info, YieldIndex>>,
+
+    #[account(
+        mut,
+        constraint = yield_index.as_ref().map(|index| index.reserve) == Some(yield_reserve.key())
+            @ VaultError::InvalidVaultState,
+    )]
+    pub yield_reserve: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        address = vault.mint @ VaultError::MintMismatch,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CancelWithdrawal<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"vault",
+            user.key().as_ref(),
+            vault.mint.as_ref(),
+            &vault.sub_account.to_le_bytes(),
+        ],
+        bump = vault.bump,
+        constraint = vault.owner == user.key() @ VaultError::UnauthorizedOwner,
+    )]
+    pub vault: Account<'info, CollateralVault>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"pending_withdrawal", vault.key().as_ref()],
+        bump = pending_withdrawal.bump,
+    )]
+    pub pending_withdrawal: Account<'info, PendingWithdrawal>,
+
+    #[account(
+        mut,
+        seeds = [b"yield_index", vault.mint.as_ref()],
+        bump = yield_index.bump,
+    )]
+    pub yield_index: Option<Account<'info, YieldIndex>>,
+}
+
+#[derive(Accounts)]
+pub struct TransferBetweenSubAccounts<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"vault",
+            user.key().as_ref(),
+            from_vault.mint.as_ref(),
+            &from_vault.sub_account.to_le_bytes(),
+        ],
+        bump = from_vault.bump,
+        constraint = from_vault.owner == user.key() @ VaultError::UnauthorizedOwner,
+        constraint = !from_vault.is_frozen() @ VaultError::VaultFrozen,
+    )]
+    pub from_vault: Account<'info, CollateralVault>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"vault",
+            user.key().as_ref(),
+            to_vault.mint.as_ref(),
+            &to_vault.sub_account.to_le_bytes(),
+        ],
+        bump = to_vault.bump,
+        constraint = to_vault.owner == user.key() @ VaultError::UnauthorizedOwner,
+        constraint = to_vault.key() != from_vault.key() @ VaultError::InvalidVaultState,
+        constraint = to_vault.mint == from_vault.mint @ VaultError::MintMismatch,
+    )]
+    pub to_vault: Account<'info, CollateralVault>,
+
+    #[account(
+        mut,
+        address = from_vault.token_account @ VaultError::InvalidVaultState,
+    )]
+    pub from_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = to_vault.token_account @ VaultError::InvalidVaultState,
+    )]
+    pub to_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"authority"],
+        bump = authority.bump,
+        constraint = !authority.paused @ VaultError::ProtocolPaused,
+    )]
+    pub authority: Account<'info, VaultAuthority>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol_counters"],
+        bump = counters.bump,
+    )]
+    pub counters: Account<'info, ProtocolCounters>,
+
+    #[account(
+        mut,
+        seeds = [b"yield_index", from_vault.mint.as_ref()],
+        bump = yield_index.bump,
+    )]
+    pub yield_index: Option<Account<'info, YieldIndex>>,
+
+    #[account(
+        mut,
+        constraint = yield_index.as_ref().map(|index| index.reserve) == Some(yield_reserve.key())
+            @ VaultError::InvalidVaultState,
+    )]
+    pub yield_reserve: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        address = from_vault.mint @ VaultError::MintMismatch,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct LockCollateral<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"vault",
+            vault.owner.as_ref(),
+            vault.mint.as_ref(),
+            &vault.sub_account.to_le_bytes(),
+        ],
+        bump = vault.bump,
+        constraint = !vault.is_frozen() @ VaultError::VaultFrozen,
+    )]
+    pub vault: Account<'info, CollateralVault>,
+
+    #[account(
+        seeds = [b"authority"],
+        bump = authority.bump,
+        constraint = !authority.paused @ VaultError::ProtocolPaused,
+    )]
+    pub authority: Account<'info, VaultAuthority>,
+
+    #[account(
+        mut,
+        seeds = [b"yield_index", vault.mint.as_ref()],
+        bump = yield_index.bump,
+    )]
+    pub yield_index: Option<Account<'info, YieldIndex>>,
+
+    /// CHECK: Program invoking this instruction; only its key is used, and the
+    /// `caller_authority` signer proves the CPI originates from it
+    pub caller_program: UncheckedAccount<'info>,
+
+    /// PDA of `caller_program` that it signs with via `invoke_signed`
+    #[account(
+        constraint = caller_authority.key() == VaultAuthority::caller_authority_address(&caller_program.key())
+            @ VaultError::UnauthorizedProgram,
+    )]
+    pub caller_authority: Signer<'info>,
+
+    /// CHECK: `AuthorizedProgram` PDA of `caller_program`; a missing or
+    /// disabled record is rejected by `load_enabled`
+    #[account(
+        constraint = AuthorizedProgram::load_enabled(&authorized_program, &caller_program.key()).is_some()
+            @ VaultError::UnauthorizedProgram,
+    )]
+    pub authorized_program: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = ProgramLock::LEN,
+        seeds = [b"program_lock", vault.key().as_ref(), caller_program.key().as_ref()],
+        bump
+    )]
+    pub program_lock: Account<'info, ProgramLock>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnlockCollateral<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"vault",
+            vault.owner.as_ref(),
+            vault.mint.as_ref(),
+            &vault.sub_account.to_le_bytes(),
+        ],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, CollateralVault>,
+
+    #[account(
+        seeds = [b"authority"],
+        bump = authority.bump,
+    )]
+    pub authority: Account<'info, VaultAuthority>,
+
+    #[account(
+        mut,
+        seeds = [b"yield_index", vault.mint.as_ref()],
+        bump = yield_index.bump,
+    )]
+    pub yield_index: Option<Account<'info, YieldIndex>>,
+
+    /// CHECK: Program invoking this instruction; only its key is used, and the
+    /// `caller_authority` signer proves the CPI originates from it
+    pub caller_program: UncheckedAccount<'info>,
+
+    /// PDA of `caller_program` that it signs with via `invoke_signed`
+    #[account(
+        constraint = caller_authority.key() == VaultAuthority::caller_authority_address(&caller_program.key())
+            @ VaultError::UnauthorizedProgram,
+    )]
+    pub caller_authority: Signer<'info>,
+
+    /// CHECK: `AuthorizedProgram` PDA of `caller_program`; a missing record is
+    /// rejected by `load`, while a disabled program may still release its locks
+    #[account(
+        constraint = AuthorizedProgram::load(&authorized_program, &caller_program.key()).is_some()
+            @ VaultError::UnauthorizedProgram,
+    )]
+    pub authorized_program: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"program_lock", vault.key().as_ref(), caller_program.key().as_ref()],
+        bump = program_lock.bump,
+    )]
+    pub program_lock: Account<'info, ProgramLock>,
+}
+
+#[derive(Accounts)]
+pub struct BatchLockCollateral<'info> {
+    #[account(
+        seeds = [b"authority"],
+        bump = authority.bump,
+        constraint = !authority.paused @ VaultError::ProtocolPaused,
+    )]
+    pub authority: Account<'info, VaultAuthority>,
+
+    /// Yield index of the batch's mint; every vault must hold this mint
+    #[account(
+        mut,
+        seeds = [b"yield_index", yield_index.mint.as_ref()],
+        bump = yield_index.bump,
+    )]
+    pub yield_index: Account<'info, YieldIndex>,
+
+    /// CHECK: Program invoking this instruction; only its key is used, and the
+    /// `caller_authority` signer proves the CPI originates from it
+    pub caller_program: UncheckedAccount<'info>,
+
+    /// PDA of `caller_program` that it signs with via `invoke_signed`
+    #[account(
+        constraint = caller_authority.key() == VaultAuthority::caller_authority_address(&caller_program.key())
+            @ VaultError::UnauthorizedProgram,
+    )]
+    pub caller_authority: Signer<'info>,
+
+    /// CHECK: `AuthorizedProgram` PDA of `caller_program`; a missing or
+    /// disabled record is rejected by `load_enabled`
+    #[account(
+        constraint = AuthorizedProgram::load_enabled(&authorized_program, &caller_program.key()).is_some()
+            @ VaultError::UnauthorizedProgram,
+    )]
+    pub authorized_program: UncheckedAccount<'info>,
+
+    /// Pays for program lock ledgers created by the batch
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BatchUnlockCollateral<'info> {
+    #[account(
+        seeds = [b"authority"],
+        bump = authority.bump,
+    )]
+    pub authority: Account<'info, VaultAuthority>,
+
+    /// Yield index of the batch's mint; every vault must hold this mint
+    #[account(
+        mut,
+        seeds = [b"yield_index", yield_index.mint.as_ref()],
+        bump = yield_index.bump,
+    )]
+    pub yield_index: Account<'info, YieldIndex>,
+
+    /// CHECK: Program invoking this instruction; only its key is used, and the
+    /// `caller_authority` signer proves the CPI originates from it
+    pub caller_program: UncheckedAccount<'info>,
+
+    /// PDA of `caller_program` that it signs with via `invoke_signed`
+    #[account(
+        constraint = caller_authority.key() == VaultAuthority::caller_authority_address(&caller_program.key())
+            @ VaultError::UnauthorizedProgram,
+    )]
+    pub caller_authority: Signer<'info>,
+
+    /// CHECK: `AuthorizedProgram` PDA of `caller_program`; a missing record is
+    /// rejected by `load`, while a disabled program may still release its locks
+    #[account(
+        constraint = AuthorizedProgram::load(&authorized_program, &caller_program.key()).is_some()
+            @ VaultError::UnauthorizedProgram,
+    )]
+    pub authorized_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct TransferCollateral<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"vault",
+            from_vault.owner.as_ref(),
+            from_vault.mint.as_ref(),
+            &from_vault.sub_account.to_le_bytes(),
+        ],
+        bump = from_vault.bump,
+        constraint = !from_vault.is_frozen() @ VaultError::VaultFrozen,
+    )]
+    pub from_vault: Account<'info, CollateralVault>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"vault",
+            to_vault.owner.as_ref(),
+            to_vault.mint.as_ref(),
+            &to_vault.sub_account.to_le_bytes(),
+        ],
+        bump = to_vault.bump,
+        constraint = to_vault.key() != from_vault.key() @ VaultError::InvalidVaultState,
+        constraint = to_vault.mint == from_vault.mint @ VaultError::MintMismatch,
+    )]
+    pub to_vault: Account<'info, CollateralVault>,
+
+    #[account(
+        mut,
+        address = from_vault.token_account @ VaultError::InvalidVaultState,
+    )]
+    pub from_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = to_vault.token_account @ VaultError::InvalidVaultState,
+    )]
+    pub to_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"authority"],
+        bump = authority.bump,
+        constraint = !authority.paused @ VaultError::ProtocolPaused,
+    )]
+    pub authority: Account<'info, VaultAuthority>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol_counters"],
+        bump = counters.bump,
+    )]
+    pub counters: Account<'info, ProtocolCounters>,
+
+    /// Treasury token account of the vaults' mint that receives the protocol fee
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == VaultAuthority::find_treasury_address().0
+            @ VaultError::InvalidTreasuryAccount,
+        constraint = treasury_token_account.mint == from_vault.mint @ VaultError::InvalidTreasuryAccount,
+    )]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"yield_index", from_vault.mint.as_ref()],
+        bump = yield_index.bump,
+    )]
+    pub yield_index: Option<Account<'info, YieldIndex>>,
+
+    #[account(
+        mut,
+        constraint = yield_index.as_ref().map(|index| index.reserve) == Some(yield_reserve.key())
+            @ VaultError::InvalidVaultState,
+    )]
+    pub yield_reserve: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Program invoking this instruction; only its key is used, and the
+    /// `caller_authority` signer proves the CPI originates from it
+    pub caller_program: UncheckedAccount<'info>,
+
+    /// PDA of `caller_program` that it signs with via `invoke_signed`
+    #[account(
+        constraint = caller_authority.key() == VaultAuthority::caller_authority_address(&caller_program.key())
+            @ VaultError::UnauthorizedProgram,
+    )]
+    pub caller_authority: Signer<'info>,
+
+    /// CHECK: `AuthorizedProgram` PDA of `caller_program`; a missing or
+    /// disabled record is rejected by `load_enabled`
+    #[account(
+        constraint = AuthorizedProgram::load_enabled(&authorized_program, &caller_program.key()).is_some()
+            @ VaultError::UnauthorizedProgram,
+    )]
+    pub authorized_program: UncheckedAccount<'info>,
+
+    #[account(
+        address = from_vault.mint @ VaultError::MintMismatch,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SettleLocked<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"vault",
+            from_vault.owner.as_ref(),
+            from_vault.mint.as_ref(),
+            &from_vault.sub_account.to_le_bytes(),
+        ],
+        bump = from_vault.bump,
+    )]
+    pub from_vault: Account<'info, CollateralVault>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"vault",
+            to_vault.owner.as_ref(),
+            to_vault.mint.as_ref(),
+            &to_vault.sub_account.to_le_bytes(),
+        ],
+        bump = to_vault.bump,
+        constraint = to_vault.key() != from_vault.key() @ VaultError::InvalidVaultState,
+        constraint = to_vault.mint == from_vault.mint @ VaultError::MintMismatch,
+    )]
+    pub to_vault: Account<'info, CollateralVault>,
+
+    #[account(
+        mut,
+        address = from_vault.token_account @ VaultError::InvalidVaultState,
+    )]
+    pub from_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = to_vault.token_account @ VaultError::InvalidVaultState,
+    )]
+    pub to_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"authority"],
+        bump = authority.bump,
+        constraint = !authority.paused @ VaultError::ProtocolPaused,
+    )]
+    pub authority: Account<'info, VaultAuthority>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol_counters"],
+        bump = counters.bump,
+    )]
+    pub counters: Account<'info, ProtocolCounters>,
+
+    #[account(
+        mut,
+        seeds = [b"yield_index", from_vault.mint.as_ref()],
+        bump = yield_index.bump,
+    )]
+    pub yield_index: Option<Account<'info, YieldIndex>>,
+
+    #[account(
+        mut,
+        constraint = yield_index.as_ref().map(|index| index.reserve) == Some(yield_reserve.key())
+            @ VaultError::InvalidVaultState,
+    )]
+    pub yield_reserve: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Program invoking this instruction; only its key is used, and the
+    /// `caller_authority` signer proves the CPI originates from it
+    pub caller_program: UncheckedAccount<'info>,
+
+    /// PDA of `caller_program` that it signs with via `invoke_signed`
+    #[account(
+        constraint = caller_authority.key() == VaultAuthority::caller_authority_address(&caller_program.key())
+            @ VaultError::UnauthorizedProgram,
+    )]
+    pub caller_authority: Signer<'info>,
+
+    /// CHECK: `AuthorizedProgram` PDA of `caller_program`; a missing or
+    /// disabled record is rejected by `load_enabled`
+    #[account(
+        constraint = AuthorizedProgram::load_enabled(&authorized_program, &caller_program.key()).is_some()
+            @ VaultError::UnauthorizedProgram,
+    )]
+    pub authorized_program: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"program_lock", from_vault.key().as_ref(), caller_program.key().as_ref()],
+        bump = program_lock.bump,
+    )]
+    pub program_lock: Account<'info, ProgramLock>,
+
+    #[account(
+        address = from_vault.mint @ VaultError::MintMismatch,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct Borrow<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"vault",
+            vault.owner.as_ref(),
+            vault.mint.as_ref(),
+            &vault.sub_account.to_le_bytes(),
+        ],
+        bump = vault.bump,
+        constraint = !vault.is_frozen() @ VaultError::VaultFrozen,
+    )]
+    pub vault: Account<'info, CollateralVault>,
+
+    #[account(
+        mut,
+        address = vault.token_account @ VaultError::InvalidVaultState,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Token account the borrowed collateral is sent to
+    #[account(
+        mut,
+        constraint = destination_token_account.mint == vault.mint @ VaultError::MintMismatch,
+    )]
+    pub destination_token_account: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         seeds = [b"authority"],
         bump = authority.bump,
+        constraint = !authority.paused @ VaultError::ProtocolPaused,
     )]
     pub authority: Account<'info, VaultAuthority>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol_counters"],
+        bump = counters.bump,
+    )]
+    pub counters: Account<'info, ProtocolCounters>,
+
+    #[account(
+        mut,
+        seeds = [b"yield_index", vault.mint.as_ref()],
+        bump = yield_index.bump,
+    )]
+    pub yield_index: Option<Account<'info, YieldIndex>>,
+
+    #[account(
+        mut,
+        constraint = yield_index.as_ref().map(|index| index.reserve) == Some(yield_reserve.key())
+            @ VaultError::InvalidVaultState,
+    )]
+    pub yield_reserve: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Program invoking this instruction; only its key is used, and the
+    /// `caller_authority` signer proves the CPI originates from it
+    pub caller_program: UncheckedAccount<'info>,
+
+    /// PDA of `caller_program` that it signs with via `invoke_signed`
+    #[account(
+        constraint = caller_authority.key() == VaultAuthority::caller_authority_address(&caller_program.key())
+            @ VaultError::UnauthorizedProgram,
+    )]
+    pub caller_authority: Signer<'info>,
+
+    /// CHECK: `AuthorizedProgram` PDA of `caller_program`; a missing or
+    /// disabled record is rejected by `load_enabled`
+    #[account(
+        constraint = AuthorizedProgram::load_enabled(&authorized_program, &caller_program.key()).is_some()
+            @ VaultError::UnauthorizedProgram,
+    )]
+    pub authorized_program: UncheckedAccount<'info>,
+
+    /// CHECK: Instructions sysvar, searched for the `repay` of this borrow
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    #[account(
+        address = vault.mint @ VaultError::MintMismatch,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
-pub struct TransferCollateral<'info> {
+pub struct Repay<'info> {
+    /// Must stay the first account: `borrow` finds its repay by the vault at
+    /// `REPAY_VAULT_ACCOUNT`
     #[account(
         mut,
-        seeds = [b"vault", from_vault.owner.as_ref()],
-        bump = from_vault.bump,
+        seeds = [
+            b"vault",
+            vault.owner.as_ref(),
+            vault.mint.as_ref(),
+            &vault.sub_account.to_le_bytes(),
+        ],
+        bump = vault.bump,
     )]
-    pub from_vault: Account<'info, CollateralVault>,
+    pub vault: Account<'info, CollateralVault>,
+
+    #[account(
+        mut,
+        address = vault.token_account @ VaultError::InvalidVaultState,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
 
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = payer_token_account.mint == vault.mint @ VaultError::MintMismatch,
+    )]
+    pub payer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Treasury token account of the vault's mint that receives the protocol fee
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == VaultAuthority::find_treasury_address().0
+            @ VaultError::InvalidTreasuryAccount,
+        constraint = treasury_token_account.mint == vault.mint @ VaultError::InvalidTreasuryAccount,
+    )]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"authority"],
+        bump = authority.bump,
+    )]
+    pub authority: Account<'info, VaultAuthority>,
+
+    #[account(
+        mut,
+        seeds = [b"yield_index", vault.mint.as_ref()],
+        bump = yield_index.bump,
+    )]
+    pub yield_index: Option<Account<'info, YieldIndex>>,
+
+    #[account(
+        address = vault.mint @ VaultError::MintMismatch,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SettleToInsurance<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"vault",
+            vault.owner.as_ref(),
+            vault.mint.as_ref(),
+            &vault.sub_account.to_le_bytes(),
+        ],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, CollateralVault>,
+
+    #[account(
+        mut,
+        address = vault.token_account @ VaultError::InvalidVaultState,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"insurance_fund", vault.mint.as_ref()],
+        bump = insurance_fund.bump,
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    #[account(
+        mut,
+        address = insurance_fund.token_account @ VaultError::InvalidVaultState,
+    )]
+    pub fund_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"authority"],
+        bump = authority.bump,
+        constraint = !authority.paused @ VaultError::ProtocolPaused,
+    )]
+    pub authority: Account<'info, VaultAuthority>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol_counters"],
+        bump = counters.bump,
+    )]
+    pub counters: Account<'info, ProtocolCounters>,
+
+    #[account(
+        mut,
+        seeds = [b"yield_index", vault.mint.as_ref()],
+        bump = yield_index.bump,
+    )]
+    pub yield_index: Option<Account<'info, YieldIndex>>,
+
+    #[account(
+        mut,
+        constraint = yield_index.as_ref().map(|index| index.reserve) == Some(yield_reserve.key())
+            @ VaultError::InvalidVaultState,
+    )]
+    pub yield_reserve: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Program invoking this instruction; only its key is used, and the
+    /// `caller_authority` signer proves the CPI originates from it
+    pub caller_program: UncheckedAccount<'info>,
+
+    /// PDA of `caller_program` that it signs with via `invoke_signed`
+    #[account(
+        constraint = caller_authority.key() == VaultAuthority::caller_authority_address(&caller_program.key())
+            @ VaultError::UnauthorizedProgram,
+    )]
+    pub caller_authority: Signer<'info>,
+
+    /// CHECK: `AuthorizedProgram` PDA of `caller_program`; a missing or
+    /// disabled record is rejected by `load_enabled`
+    #[account(
+        constraint = AuthorizedProgram::load_enabled(&authorized_program, &caller_program.key()).is_some()
+            @ VaultError::UnauthorizedProgram,
+    )]
+    pub authorized_program: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"program_lock", vault.key().as_ref(), caller_program.key().as_ref()],
+        bump = program_lock.bump,
+    )]
+    pub program_lock: Account<'info, ProgramLock>,
+
+    #[account(
+        address = vault.mint @ VaultError::MintMismatch,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CoverShortfall<'info> {
+    /// Vault owed the bad debt
     #[account(
         mut,
-        seeds = [b"vault", to_vault.owner.as_ref()],
+        seeds = [
+            b"vault",
+            to_vault.owner.as_ref(),
+            to_vault.mint.as_ref(),
+            &to_vault.sub_account.to_le_bytes(),
+        ],
         bump = to_vault.bump,
     )]
     pub to_vault: Account<'info, CollateralVault>,
 
-    #[account(mut)]
-    pub from_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        address = to_vault.token_account @ VaultError::InvalidVaultState,
+    )]
+    pub to_token_account: InterfaceAccount<'info, TokenAccount>,
 
-    #[account(mut)]
-    pub to_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"insurance_fund", to_vault.mint.as_ref()],
+        bump = insurance_fund.bump,
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    #[account(
+        mut,
+        address = insurance_fund.token_account @ VaultError::InvalidVaultState,
+    )]
+    pub fund_token_account: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         seeds = [b"authority"],
         bump = authority.bump,
+        constraint = !authority.paused @ VaultError::ProtocolPaused,
     )]
     pub authority: Account<'info, VaultAuthority>,
 
-    pub token_program: Program<'info, Token>,
+    #[account(
+        mut,
+        seeds = [b"protocol_counters"],
+        bump = counters.bump,
+    )]
+    pub counters: Account<'info, ProtocolCounters>,
+
+    #[account(
+        mut,
+        seeds = [b"yield_index", to_vault.mint.as_ref()],
+        bump = yield_index.bump,
+    )]
+    pub yield_index: Account<'info, YieldIndex>,
+
+    #[account(
+        mut,
+        address = yield_index.reserve @ VaultError::InvalidVaultState,
+    )]
+    pub yield_reserve: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Program invoking this instruction; only its key is used, and the
+    /// `caller_authority` signer proves the CPI originates from it
+    pub caller_program: UncheckedAccount<'info>,
+
+    /// PDA of `caller_program` that it signs with via `invoke_signed`
+    #[account(
+        constraint = caller_authority.key() == VaultAuthority::caller_authority_address(&caller_program.key())
+            @ VaultError::UnauthorizedProgram,
+    )]
+    pub caller_authority: Signer<'info>,
+
+    /// CHECK: `AuthorizedProgram` PDA of `caller_program`; a missing or
+    /// disabled record is rejected by `load_enabled`
+    #[account(
+        constraint = AuthorizedProgram::load_enabled(&authorized_program, &caller_program.key()).is_some()
+            @ VaultError::UnauthorizedProgram,
+    )]
+    pub authorized_program: UncheckedAccount<'info>,
+
+    #[account(
+        address = to_vault.mint @ VaultError::MintMismatch,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(program: Pubkey)]
+pub struct GetVaultState<'info> {
+    #[account(
+        seeds = [
+            b"vault",
+            vault.owner.as_ref(),
+            vault.mint.as_ref(),
+            &vault.sub_account.to_le_bytes(),
+        ],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, CollateralVault>,
+
+    #[account(
+        seeds = [b"yield_index", vault.mint.as_ref()],
+        bump = yield_index.bump,
+    )]
+    pub yield_index: Option<Account<'info, YieldIndex>>,
+
+    /// CHECK: `ProgramLock` PDA of `program` in the vault; may not exist yet,
+    /// in which case nothing is locked
+    #[account(
+        seeds = [b"program_lock", vault.key().as_ref(), program.as_ref()],
+        bump,
+    )]
+    pub program_lock: UncheckedAccount<'info>,
 }
@@ -6,18 +6,28 @@ pub struct CollateralVault {
     /// Owner of the vault (user's public key)
     pub owner: Pubkey,
     
-    /// Associated token account that holds the actual USDT
+    /// Collateral mint held by this vault (USDT, USDC, ...)
+    pub mint: Pubkey,
+    
+    /// Sub-account index; an owner can hold isolated vaults of the same mint
+    pub sub_account: u16,
+    
+    /// Associated token account that holds the collateral tokens
     pub token_account: Pubkey,
     
-    /// Total balance in the vault (locked + available)
+    /// Total balance in the vault: locked + available + pending, plus any
+    /// collateral `borrow` carved out of available into `borrowed`
     pub total_balance: u64,
     
     /// Balance locked for open positions
     pub locked_balance: u64,
     
-    /// Available balance for withdrawal (total - locked)
+    /// Available balance for withdrawal (total - locked - pending)
     pub available_balance: u64,
     
+    /// Balance reserved by a requested withdrawal until its timelock expires
+    pub pending_balance: u64,
+    
     /// Cumulative amount deposited over lifetime
     pub total_deposited: u64,
     
@@ -30,43 +40,710 @@ pub struct CollateralVault {
     /// Last activity timestamp
     pub last_updated: i64,
     
+    /// Set by the admin; a frozen vault still takes deposits and unlocks, but
+    /// rejects withdrawals, new locks and outgoing transfers
+    pub status: VaultStatus,
+    
+    /// Amount withdrawn from this vault in the current rate-limit bucket
+    pub withdrawal_window: WithdrawalWindow,
+    
+    /// Shares of the mint's `YieldIndex` held for the yield-bearing balance
+    pub yield_shares: u64,
+    
+    /// `YieldIndex::index` when `yield_shares` was last set; yield accrues on
+    /// the increase since then
+    pub yield_checkpoint: u64,
+    
+    /// Yield credited to the balances but still held in the yield reserve
+    pub unsettled_yield: u64,
+    
     /// PDA bump seed
     pub bump: u8,
+    
+    /// Account layout version; older vaults are upgraded by `migrate_vault`
+    pub version: u8,
+    
+    /// Number of mutations applied to this vault; every vault event carries
+    /// the value after its mutation, so a gap means an event was missed
+    pub sequence: u64,
+    
+    /// Available collateral lent out by `borrow` and not yet repaid; still
+    /// counted in `total_balance`, and always 0 once a transaction completes
+    pub borrowed: u64,
+    
+    /// `YieldIndex::loss_index` when the vault's socialized losses were last
+    /// booked; its `loss_weight` owes the increase since then
+    pub loss_checkpoint: u64,
+    
+    /// Socialized loss owed beyond the available balance, booked as soon as
+    /// collateral becomes available
+    pub unpaid_loss: u64,
+    
+    /// Socialized loss debited from the balances but still held in the vault
+    /// token account until `settle_yield` moves it to the yield reserve
+    pub unsettled_loss: u64,
+    
+    /// Amount withdrawn in the rate-limit bucket before `withdrawal_window`
+    pub previous_window_withdrawn: u64,
+    
+    /// Available balance counted in `YieldIndex::total_loss_weight` when the
+    /// vault was last reshared; socialized losses are haircut pro rata to it
+    pub loss_weight: u64,
+    
+    /// Socialized shortfall `cover_shortfall` owes this vault, credited to its
+    /// balances only as the haircuts collected in the loss reserve are paid out
+    pub pending_cover: u64,
 }
 
 impl CollateralVault {
-    pub const LEN: usize = 8 + // discriminator
+    /// Current account layout version
+    pub const VERSION: u8 = 1;
+    
+    pub const LEN: usize = Self::LEGACY_LEN +
+        1 +  // version
+        8 +  // sequence
+        8 +  // borrowed
+        8 +  // loss_checkpoint
+        8 +  // unpaid_loss
+        8 +  // unsettled_loss
+        8 +  // previous_window_withdrawn
+        8 +  // loss_weight
+        8;   // pending_cover
+
+    /// Size of a vault from before mints and sub-accounts: owner, token
+    /// account, seven balances and timestamps, and bump. Its PDA was derived
+    /// from the owner alone, so it cannot be rewritten into a vault the
+    /// current seeds reach.
+    pub const BASELINE_LEN: usize = 8 + 32 + 32 + 7 * 8 + 1;
+    
+    /// Size of a layout 0 vault, which ends at `bump` and has no version
+    pub const LEGACY_LEN: usize = 8 + // discriminator
         32 + // owner
+        32 + // mint
+        2 +  // sub_account
         32 + // token_account
         8 +  // total_balance
         8 +  // locked_balance
         8 +  // available_balance
+        8 +  // pending_balance
         8 +  // total_deposited
         8 +  // total_withdrawn
         8 +  // created_at
         8 +  // last_updated
+        1 +  // status
+        WithdrawalWindow::LEN + // withdrawal_window
+        8 +  // yield_shares
+        8 +  // yield_checkpoint
+        8 +  // unsettled_yield
         1;   // bump
+
+    /// Advance `sequence` for a mutation and return the new value
+    pub fn next_sequence(&mut self) -> Option<u64> {
+        self.sequence = self.sequence.checked_add(1)?;
+        Some(self.sequence)
+    }
+
+    /// Whether collateral may leave the vault or be locked
+    pub fn is_frozen(&self) -> bool {
+        self.status == VaultStatus::Frozen
+    }
+
+    /// Balance that earns yield: available collateral, plus locked collateral
+    /// if the mint's index includes it
+    pub fn yield_bearing_balance(&self, include_locked: bool) -> Option<u64> {
+        if include_locked {
+            self.available_balance.checked_add(self.locked_balance)
+        } else {
+            Some(self.available_balance)
+        }
+    }
+}
+
+/// Admin-controlled state of a vault. Serialized as one byte, like the
+/// `frozen` flag it replaced, so existing vaults keep their state.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum VaultStatus {
+    #[default]
+    Active,
+    /// Frozen by the admin, e.g. for a compliance review
+    Frozen,
+}
+
+/// Withdrawals counted against a rate limit over a rolling window. Time is cut
+/// into `window`-second buckets counted from the moment the window length was
+/// set (`VaultAuthority::withdrawal_window_start`); the rolling total is the
+/// current bucket plus the part of the previous bucket that still overlaps the
+/// last `window` seconds, assuming its withdrawals were spread evenly. The previous bucket's amount is kept by the owning account, after
+/// the layout 0 fields.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct WithdrawalWindow {
+    /// Start of the current bucket
+    pub start: i64,
+    
+    /// Amount withdrawn since `start`
+    pub withdrawn: u64,
+}
+
+impl WithdrawalWindow {
+    pub const LEN: usize = 8 + // start
+        8;   // withdrawn
+
+    /// Add `amount` to the bucket containing `now`, rolling the buckets
+    /// forward first; returns the amount withdrawn over the last `window`
+    /// seconds, rounded up. Buckets started before `origin` were cut for
+    /// another window length and are discarded.
+    pub fn record(
+        &mut self,
+        previous: &mut u64,
+        now: i64,
+        window: i64,
+        origin: i64,
+        amount: u64,
+    ) -> Option<u64> {
+        let start = now.checked_sub(now.checked_sub(origin)?.rem_euclid(window))?;
+        if self.start < origin {
+            *previous = 0;
+            self.start = start;
+            self.withdrawn = 0;
+        } else if start != self.start {
+            // Only an adjacent bucket still overlaps the rolling window
+            *previous = if self.start >= start.saturating_sub(window) {
+                self.withdrawn
+            } else {
+                0
+            };
+            self.start = start;
+            self.withdrawn = 0;
+        }
+        self.withdrawn = self.withdrawn.checked_add(amount)?;
+
+        let overlap = window.checked_sub(now.checked_sub(start)?)?;
+        let carried = (*previous as u128)
+            .checked_mul(overlap as u128)?
+            .div_ceil(window as u128);
+        self.withdrawn.checked_add(u64::try_from(carried).ok()?)
+    }
 }
 
 /// Authority account that manages authorized programs
 #[account]
 pub struct VaultAuthority {
-    /// Programs authorized to lock/unlock collateral
+    /// Legacy list of authorized programs; entries are moved into
+    /// `AuthorizedProgram` PDAs by `migrate_authorized_program`
     pub authorized_programs: Vec<Pubkey>,
     
     /// Admin who can add/remove authorized programs
     pub admin: Pubkey,
     
+    /// Admin proposed by `propose_admin`, who must accept to take over
+    pub pending_admin: Option<Pubkey>,
+    
+    /// Seconds a requested withdrawal waits before it can be executed
+    /// (0 allows instant withdrawals)
+    pub withdrawal_delay: i64,
+    
+    /// Emergency pause: deposits, withdrawals, locks and transfers are rejected
+    pub paused: bool,
+    
+    /// Operator-defined reason code for the current pause (0 when not paused)
+    pub pause_reason: u8,
+    
+    /// Maximum `total_balance` a deposit may bring a vault to (0 = no cap)
+    pub max_vault_balance: u64,
+    
+    /// Maximum `ProtocolCounters::total_value_locked` a deposit may bring the
+    /// program to (0 = no cap)
+    pub max_tvl: u64,
+    
+    /// Length in seconds of the withdrawal rate-limit window (0 disables rate limiting)
+    pub withdrawal_window: i64,
+    
+    /// When `withdrawal_window` last changed length; rate-limit buckets are
+    /// counted from here so none outlives the length it was cut for
+    pub withdrawal_window_start: i64,
+    
+    /// Maximum amount a single vault may withdraw per window (0 = no limit)
+    pub max_vault_withdrawal: u64,
+    
+    /// Maximum amount withdrawn across all vaults per window (0 = no limit)
+    pub max_global_withdrawal: u64,
+    
+    /// Protocol fee in basis points charged on withdrawals and transfers
+    pub fee_bps: u16,
+    
+    /// Fee in basis points `repay` charges on collateral lent by `borrow`
+    pub borrow_fee_bps: u16,
+    
     /// PDA bump seed
     pub bump: u8,
 }
 
 impl VaultAuthority {
+    /// Capacity of the legacy `authorized_programs` vector
     pub const MAX_AUTHORIZED_PROGRAMS: usize = 10;
     
+    /// Upper bound for `withdrawal_delay` (7 days)
+    pub const MAX_WITHDRAWAL_DELAY: i64 = 7 * 24 * 60 * 60;
+    
+    /// Upper bound for `withdrawal_window` (7 days)
+    pub const MAX_WITHDRAWAL_WINDOW: i64 = 7 * 24 * 60 * 60;
+    
+    /// Upper bound for `fee_bps` and `borrow_fee_bps` (10%)
+    pub const MAX_FEE_BPS: u16 = 1_000;
+    
     pub const LEN: usize = 8 + // discriminator
         4 + (32 * Self::MAX_AUTHORIZED_PROGRAMS) + // authorized_programs vector
         32 + // admin
+        1 + 32 + // pending_admin
+        8 +  // withdrawal_delay
+        1 +  // paused
+        1 +  // pause_reason
+        8 +  // max_vault_balance
+        8 +  // max_tvl
+        8 +  // withdrawal_window
+        8 +  // withdrawal_window_start
+        8 +  // max_vault_withdrawal
+        8 +  // max_global_withdrawal
+        2 +  // fee_bps
+        2 +  // borrow_fee_bps
+        1;   // bump
+
+    /// Seed of the PDA that owns the treasury token accounts
+    pub const TREASURY_SEED: &'static [u8] = b"treasury";
+
+    /// Seed an authorized program uses to derive the PDA it signs CPIs with
+    pub const CALLER_SEED: &'static [u8] = b"vault_caller";
+
+    /// Derive the PDA a program signs with when calling into the vault
+    pub fn caller_authority_address(program_id: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[Self::CALLER_SEED], program_id).0
+    }
+
+    /// Derive the treasury PDA; fees are paid into token accounts it owns
+    pub fn find_treasury_address() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::TREASURY_SEED], &crate::ID)
+    }
+
+    /// Protocol fee charged on `amount`, rounded down
+    pub fn fee_for(&self, amount: u64) -> Option<u64> {
+        bps_of(amount, self.fee_bps)
+    }
+
+    /// Fee charged on `amount` borrowed, rounded down
+    pub fn borrow_fee_for(&self, amount: u64) -> Option<u64> {
+        bps_of(amount, self.borrow_fee_bps)
+    }
+}
+
+/// `bps` basis points of `amount`, rounded down
+fn bps_of(amount: u64, bps: u16) -> Option<u64> {
+    let fee = (amount as u128).checked_mul(bps as u128)? / 10_000;
+    u64::try_from(fee).ok()
+}
+
+/// Program-wide counters, maintained by every instruction that moves tokens
+/// into or out of the vaults
+#[account]
+pub struct ProtocolCounters {
+    /// Sum of `total_balance` over all vaults
+    pub total_value_locked: u64,
+    
+    /// Amount withdrawn across all vaults in the current rate-limit bucket
+    pub withdrawal_window: WithdrawalWindow,
+    
+    /// Amount withdrawn across all vaults in the bucket before `withdrawal_window`
+    pub previous_window_withdrawn: u64,
+    
+    /// Last activity timestamp
+    pub last_updated: i64,
+    
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl ProtocolCounters {
+    pub const LEN: usize = 8 + // discriminator
+        8 +  // total_value_locked
+        WithdrawalWindow::LEN + // withdrawal_window
+        8 +  // previous_window_withdrawn
+        8 +  // last_updated
+        1;   // bump
+}
+
+/// Share-based yield accounting for the vaults of one collateral mint: each
+/// vault holds shares worth `shares * index / SCALE` tokens, and
+/// `distribute_yield` raises the index. Socialized losses run the other way:
+/// `cover_shortfall` raises `loss_index` and every vault pays its shares'
+/// part of the increase the next time it is touched.
+#[account]
+pub struct YieldIndex {
+    /// Collateral mint this index applies to
+    pub mint: Pubkey,
+    
+    /// Token value of one share, scaled by `SCALE` (starts at 1.0)
+    pub index: u64,
+    
+    /// Shares held across all vaults of the mint
+    pub total_shares: u64,
+    
+    /// Whether locked collateral earns yield too (fixed at initialization)
+    pub include_locked: bool,
+    
+    /// Token account owned by this PDA that holds distributed yield until it
+    /// is settled into the vaults
+    pub reserve: Pubkey,
+    
+    /// Cumulative amount distributed as yield
+    pub total_distributed: u64,
+    
+    /// Cumulative socialized loss per token of loss weight, scaled by `SCALE`
+    pub loss_index: u64,
+    
+    /// Available balance of every vault of the mint as of its last reshare;
+    /// the base socialized losses are spread over
+    pub total_loss_weight: u64,
+    
+    /// Haircuts collected into `reserve` and not yet paid out to the vaults
+    /// they cover; yield is only ever paid out of the rest of the reserve
+    pub loss_reserve: u64,
+    
+    /// Last activity timestamp
+    pub last_updated: i64,
+    
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl YieldIndex {
+    pub const SEED: &'static [u8] = b"yield_index";
+    
+    /// Fixed-point scale of `index`
+    pub const SCALE: u64 = 1_000_000_000;
+    
+    pub const LEN: usize = 8 + // discriminator
+        32 + // mint
+        8 +  // index
+        8 +  // total_shares
+        1 +  // include_locked
+        32 + // reserve
+        8 +  // total_distributed
+        8 +  // loss_index
+        8 +  // total_loss_weight
+        8 +  // loss_reserve
+        8 +  // last_updated
+        1;   // bump
+
+    /// Derive the yield index PDA of a mint
+    pub fn find_address(mint: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, mint.as_ref()], &crate::ID)
+    }
+
+    /// Token value of `shares`, rounded down
+    pub fn shares_to_tokens(&self, shares: u64) -> Option<u64> {
+        let tokens = (shares as u128).checked_mul(self.index as u128)? / Self::SCALE as u128;
+        u64::try_from(tokens).ok()
+    }
+
+    /// Shares worth `tokens`, rounded down so shares never exceed the balance
+    pub fn tokens_to_shares(&self, tokens: u64) -> Option<u64> {
+        let shares = (tokens as u128).checked_mul(Self::SCALE as u128)? / self.index as u128;
+        u64::try_from(shares).ok()
+    }
+}
+
+/// Insurance fund of one collateral mint: liquidation penalties and swept fees
+/// are paid in, and `cover_shortfall` pays bad debt out of it
+#[account]
+pub struct InsuranceFund {
+    /// Collateral mint this fund holds
+    pub mint: Pubkey,
+    
+    /// Token account owned by this PDA that holds the fund
+    pub token_account: Pubkey,
+    
+    /// Whether a shortfall the fund cannot cover is haircut from every other
+    /// vault of the mint, pro rata to its yield shares
+    pub socialize_losses: bool,
+    
+    /// Cumulative liquidation penalties and fees paid in
+    pub total_contributed: u64,
+    
+    /// Cumulative amount paid out of the fund
+    pub total_covered: u64,
+    
+    /// Cumulative amount haircut from vaults because the fund ran short
+    pub total_socialized: u64,
+    
+    /// Last activity timestamp
+    pub last_updated: i64,
+    
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl InsuranceFund {
+    pub const SEED: &'static [u8] = b"insurance_fund";
+    
+    pub const LEN: usize = 8 + // discriminator
+        32 + // mint
+        32 + // token_account
+        1 +  // socialize_losses
+        8 +  // total_contributed
+        8 +  // total_covered
+        8 +  // total_socialized
+        8 +  // last_updated
+        1;   // bump
+
+    /// Derive the insurance fund PDA of a mint
+    pub fn find_address(mint: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, mint.as_ref()], &crate::ID)
+    }
+}
+
+/// Authorization of one integration program to lock, unlock or move collateral
+#[account]
+pub struct AuthorizedProgram {
+    /// Program this authorization applies to
+    pub program_id: Pubkey,
+    
+    /// Human-readable name of the integration (e.g. "perps")
+    pub label: String,
+    
+    /// Maximum amount this program may have locked in a single vault (0 = no limit)
+    pub max_lock: u64,
+    
+    /// Disabled programs can no longer lock or move collateral, but can still
+    /// unlock what they locked
+    pub enabled: bool,
+    
+    /// Timestamp when the program was first authorized
+    pub created_at: i64,
+    
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl AuthorizedProgram {
+    pub const SEED: &'static [u8] = b"authorized_program";
+    
+    pub const MAX_LABEL_LEN: usize = 32;
+    
+    pub const LEN: usize = 8 + // discriminator
+        32 + // program_id
+        4 + Self::MAX_LABEL_LEN + // label
+        8 +  // max_lock
+        1 +  // enabled
+        8 +  // created_at
+        1;   // bump
+
+    /// Derive the authorization PDA of a program
+    pub fn find_address(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, program_id.as_ref()], &crate::ID)
+    }
+
+    /// Load the authorization record of `program_id` from an unchecked account,
+    /// returning `None` unless it exists, enabled or not
+    pub fn load(info: &AccountInfo, program_id: &Pubkey) -> Option<Self> {
+        if info.owner != &crate::ID {
+            return None;
+        }
+        let data = info.try_borrow_data().ok()?;
+        let record = Self::try_deserialize(&mut &data[..]).ok()?;
+        (record.program_id == *program_id).then_some(record)
+    }
+
+    /// Same as `load`, also returning `None` if the program is disabled
+    pub fn load_enabled(info: &AccountInfo, program_id: &Pubkey) -> Option<Self> {
+        Self::load(info, program_id).filter(|record| record.enabled)
+    }
+
+    /// Check whether locking `program_locked` in total stays within `max_lock`
+    pub fn allows_lock(&self, program_locked: u64) -> bool {
+        self.max_lock == 0 || program_locked <= self.max_lock
+    }
+}
+
+/// Per-(vault, program) ledger of collateral locked by one authorized program
+#[account]
+pub struct ProgramLock {
+    /// Vault the collateral is locked in
+    pub vault: Pubkey,
+    
+    /// Authorized program that owns this lock
+    pub program: Pubkey,
+    
+    /// Amount currently locked by this program
+    pub locked_amount: u64,
+    
+    /// Last activity timestamp
+    pub last_updated: i64,
+    
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl ProgramLock {
+    pub const SEED: &'static [u8] = b"program_lock";
+    
+    pub const LEN: usize = 8 + // discriminator
+        32 + // vault
+        32 + // program
+        8 +  // locked_amount
+        8 +  // last_updated
+        1;   // bump
+
+    /// Derive the lock ledger PDA of a program in a vault
+    pub fn find_address(vault: &Pubkey, program: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, vault.as_ref(), program.as_ref()], &crate::ID)
+    }
+}
+
+/// Withdrawal requested by a vault owner, executable once the timelock expires
+#[account]
+pub struct PendingWithdrawal {
+    /// Vault the withdrawal is drawn from
+    pub vault: Pubkey,
+    
+    /// Amount reserved for the withdrawal
+    pub amount: u64,
+    
+    /// Timestamp when the withdrawal was requested
+    pub requested_at: i64,
+    
+    /// Earliest timestamp at which the withdrawal can be executed
+    pub executable_at: i64,
+    
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl PendingWithdrawal {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // vault
+        8 +  // amount
+        8 +  // requested_at
+        8 +  // executable_at
+        1;   // bump
+}
+
+/// M-of-N signer set that can take over the `VaultAuthority` admin role
+#[account]
+pub struct AdminMultisig {
+    /// Keys allowed to create and approve proposals
+    pub signers: Vec<Pubkey>,
+    
+    /// Approvals required before a proposal can be executed
+    pub threshold: u8,
+    
+    /// Bumped whenever the signer set changes, invalidating open proposals
+    pub signer_set_seqno: u32,
+    
+    /// Number of proposals created so far (index of the next proposal)
+    pub proposal_count: u64,
+    
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl AdminMultisig {
+    pub const MAX_SIGNERS: usize = 10;
+
+    pub const LEN: usize = 8 + // discriminator
+        4 + (32 * Self::MAX_SIGNERS) + // signers vector
+        1 +  // threshold
+        4 +  // signer_set_seqno
+        8 +  // proposal_count
+        1;   // bump
+
+    /// Check whether a key belongs to the signer set
+    pub fn is_signer(&self, key: &Pubkey) -> bool {
+        self.signers.contains(key)
+    }
+
+    /// Check a signer set before it is stored: no duplicates and 1 <= threshold <= signers
+    pub fn is_valid_config(signers: &[Pubkey], threshold: u8) -> bool {
+        let unique = signers
+            .iter()
+            .enumerate()
+            .all(|(i, key)| !signers[..i].contains(key));
+        unique
+            && signers.len() <= Self::MAX_SIGNERS
+            && threshold > 0
+            && threshold as usize <= signers.len()
+    }
+}
+
+/// Admin action a multisig proposal executes once approved
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
+pub enum AdminAction {
+    AddAuthorizedProgram { program_id: Pubkey, label: String, max_lock: u64 },
+    RemoveAuthorizedProgram { program_id: Pubkey },
+    SetWithdrawalDelay { withdrawal_delay: i64 },
+    SetDepositCaps { max_vault_balance: u64, max_tvl: u64 },
+    SetWithdrawalLimits { window: i64, max_vault_withdrawal: u64, max_global_withdrawal: u64 },
+    SetFee { fee_bps: u16 },
+    SetBorrowFee { borrow_fee_bps: u16 },
+    /// Sweep a treasury token account into `destination`
+    CollectFees { treasury_token_account: Pubkey, destination: Pubkey },
+    /// Create the yield index and reserve of `mint`
+    InitializeYieldIndex { mint: Pubkey, include_locked: bool },
+    /// Pay `amount` tokens of the treasury into the yield reserve of `mint`
+    DistributeYield { mint: Pubkey, amount: u64 },
+    /// Create the insurance fund of `mint` and its token account
+    InitializeInsuranceFund { mint: Pubkey, socialize_losses: bool },
+    SetSocializeLosses { mint: Pubkey, socialize_losses: bool },
+    /// Sweep a treasury token account into the insurance fund of its mint
+    CollectFeesToInsurance { treasury_token_account: Pubkey },
+    Pause { reason: u8 },
+    Unpause,
+    SetVaultFrozen { vault: Pubkey, frozen: bool },
+    /// Release collateral a disabled program still holds locked in a vault
+    ForceUnlock { vault: Pubkey, program: Pubkey, amount: u64 },
+    /// Hand the admin role to a single key, which must then call `accept_admin`
+    ProposeAdmin { new_admin: Pubkey },
+    UpdateSigners { signers: Vec<Pubkey>, threshold: u8 },
+}
+
+impl AdminAction {
+    /// Serialized size of the largest variant (`UpdateSigners`)
+    pub const MAX_LEN: usize = 1 + // variant
+        4 + (32 * AdminMultisig::MAX_SIGNERS) + // signers vector
+        1;   // threshold
+}
+
+/// Admin action proposed by a multisig signer and awaiting approvals
+#[account]
+pub struct AdminProposal {
+    /// Sequential index, also used in the PDA seeds
+    pub index: u64,
+    
+    /// Signer who created the proposal; receives the rent back on execution
+    pub proposer: Pubkey,
+    
+    /// Action applied on execution
+    pub action: AdminAction,
+    
+    /// Signers who approved so far (the proposer approves on creation)
+    pub approvals: Vec<Pubkey>,
+    
+    /// Signer set the approvals were collected from
+    pub signer_set_seqno: u32,
+    
+    /// Timestamp when the proposal was created
+    pub created_at: i64,
+    
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl AdminProposal {
+    pub const LEN: usize = 8 + // discriminator
+        8 +  // index
+        32 + // proposer
+        AdminAction::MAX_LEN + // action
+        4 + (32 * AdminMultisig::MAX_SIGNERS) + // approvals vector
+        4 +  // signer_set_seqno
+        8 +  // created_at
         1;   // bump
 }
 
@@ -78,25 +755,119 @@ pub enum TransactionType {
     Lock,
     Unlock,
     Transfer,
+    Settlement,
+}
+
+/// Why locked collateral was seized by `settle_locked`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SettlementReason {
+    /// Realized loss paid to the counterparty
+    Settlement,
+    /// Position liquidated
+    Liquidation,
+    /// Penalty charged on top of a liquidation loss
+    LiquidationPenalty,
+}
+
+/// Snapshot of a vault written to return data by `get_vault_state`, so
+/// integrations never depend on the `CollateralVault` layout. Fields are only
+/// appended, with `version` bumped when they are; readers must ignore bytes
+/// past the fields they know.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct VaultState {
+    /// `VaultState::VERSION` of the program that wrote it
+    pub version: u8,
+
+    pub vault: Pubkey,
+
+    pub owner: Pubkey,
+
+    pub mint: Pubkey,
+
+    pub sub_account: u16,
+
+    pub status: VaultStatus,
+
+    /// Balances include yield accrued since the vault was last touched
+    pub total_balance: u64,
+
+    pub locked_balance: u64,
+
+    pub available_balance: u64,
+
+    pub pending_balance: u64,
+
+    pub borrowed: u64,
+
+    /// Program whose lock is reported in `program_locked`
+    pub program: Pubkey,
+
+    /// Collateral `program` has locked in the vault (0 if it never locked)
+    pub program_locked: u64,
+
+    /// `CollateralVault::sequence` at the time of the read
+    pub sequence: u64,
+
+    pub slot: u64,
+}
+
+impl VaultState {
+    /// Current return data version
+    pub const VERSION: u8 = 1;
 }
 
 /// Event emitted when a deposit occurs
 #[event]
 pub struct DepositEvent {
+    /// Vault owner
     pub user: Pubkey,
+    /// Signer the tokens came from; differs from `user` for `deposit_for`
+    pub payer: Pubkey,
     pub vault: Pubkey,
     pub amount: u64,
     pub new_balance: u64,
+    /// `CollateralVault::sequence` after this deposit; every vault event
+    /// carries it, so indexers can detect events they missed
+    pub sequence: u64,
+    /// Slot the event was emitted in; every event carries it
+    pub slot: u64,
     pub timestamp: i64,
 }
 
-/// Event emitted when a withdrawal occurs
+/// Event emitted when a withdrawal occurs; `amount` is debited from the vault,
+/// of which `fee` goes to the treasury
 #[event]
 pub struct WithdrawalEvent {
     pub user: Pubkey,
     pub vault: Pubkey,
     pub amount: u64,
+    pub fee: u64,
     pub new_balance: u64,
+    pub sequence: u64,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+/// Event emitted when a timelocked withdrawal is requested
+#[event]
+pub struct WithdrawalRequestedEvent {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub amount: u64,
+    pub executable_at: i64,
+    pub sequence: u64,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+/// Event emitted when a pending withdrawal is cancelled
+#[event]
+pub struct WithdrawalCancelledEvent {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub amount: u64,
+    pub sequence: u64,
+    pub slot: u64,
     pub timestamp: i64,
 }
 
@@ -104,9 +875,13 @@ pub struct WithdrawalEvent {
 #[event]
 pub struct LockEvent {
     pub vault: Pubkey,
+    pub program: Pubkey,
     pub amount: u64,
+    pub program_locked: u64,
     pub locked_balance: u64,
     pub available_balance: u64,
+    pub sequence: u64,
+    pub slot: u64,
     pub timestamp: i64,
 }
 
@@ -114,26 +889,318 @@ pub struct LockEvent {
 #[event]
 pub struct UnlockEvent {
     pub vault: Pubkey,
+    pub program: Pubkey,
     pub amount: u64,
+    pub program_locked: u64,
     pub locked_balance: u64,
     pub available_balance: u64,
+    pub sequence: u64,
+    pub slot: u64,
     pub timestamp: i64,
 }
 
-/// Event emitted when collateral is transferred between vaults
+/// Event emitted when collateral is transferred between vaults; `amount` is
+/// debited from `from_vault`, of which `fee` goes to the treasury
 #[event]
 pub struct TransferEvent {
     pub from_vault: Pubkey,
     pub to_vault: Pubkey,
     pub amount: u64,
+    pub fee: u64,
+    pub from_sequence: u64,
+    pub to_sequence: u64,
+    pub slot: u64,
     pub timestamp: i64,
 }
 
-/// Event emitted when vault authority is updated
+/// Event emitted when locked collateral is settled out of a vault
+#[event]
+pub struct SettlementEvent {
+    pub from_vault: Pubkey,
+    pub to_vault: Pubkey,
+    pub program: Pubkey,
+    pub amount: u64,
+    pub reason: SettlementReason,
+    pub locked_balance: u64,
+    pub program_locked: u64,
+    pub from_sequence: u64,
+    pub to_sequence: u64,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+/// Event emitted when an authorized program borrows a vault's available
+/// collateral for the rest of the transaction
+#[event]
+pub struct BorrowEvent {
+    pub vault: Pubkey,
+    pub program: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub sequence: u64,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+/// Event emitted when borrowed collateral is returned to its vault
+#[event]
+pub struct RepayEvent {
+    pub vault: Pubkey,
+    pub payer: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub sequence: u64,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+/// Event emitted when a liquidation penalty or swept fees are paid into an
+/// insurance fund; `vault` and `sequence` are set for penalties only
+#[event]
+pub struct InsuranceContributionEvent {
+    pub insurance_fund: Pubkey,
+    pub vault: Option<Pubkey>,
+    pub amount: u64,
+    pub fund_balance: u64,
+    pub sequence: Option<u64>,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+/// Event emitted when an authorized program covers bad debt owed to a vault;
+/// `uncovered` is the part neither the fund nor socialization could pay
+#[event]
+pub struct ShortfallCoveredEvent {
+    pub insurance_fund: Pubkey,
+    pub to_vault: Pubkey,
+    pub program: Pubkey,
+    pub amount: u64,
+    pub from_fund: u64,
+    pub socialized: u64,
+    pub uncovered: u64,
+    pub fund_balance: u64,
+    pub to_sequence: u64,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+/// Event emitted when a shortfall is socialized across the vaults of a mint;
+/// each vault books its part when it is next touched
+#[event]
+pub struct LossSocializedEvent {
+    pub mint: Pubkey,
+    pub to_vault: Pubkey,
+    pub amount: u64,
+    pub loss_index: u64,
+    pub total_loss_weight: u64,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+/// Event emitted when the admin switches an insurance fund's socialized-loss mode
+#[event]
+pub struct SocializeLossesUpdatedEvent {
+    pub insurance_fund: Pubkey,
+    pub socialize_losses: bool,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+/// Event emitted when a vault is closed and its rent returned
+#[event]
+pub struct VaultClosedEvent {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub sequence: u64,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+/// Event emitted when a vault is upgraded to the current account layout
+#[event]
+pub struct VaultMigratedEvent {
+    pub vault: Pubkey,
+    pub version: u8,
+    pub sequence: u64,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+/// Event emitted when an owner moves collateral between their own sub-accounts
+#[event]
+pub struct SubAccountTransferEvent {
+    pub owner: Pubkey,
+    pub from_vault: Pubkey,
+    pub to_vault: Pubkey,
+    pub amount: u64,
+    pub from_sequence: u64,
+    pub to_sequence: u64,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+/// Event emitted when a program is authorized, updated or disabled
 #[event]
 pub struct AuthorityUpdatedEvent {
     pub authority: Pubkey,
     pub program: Pubkey,
     pub authorized: bool,
+    pub label: String,
+    pub max_lock: u64,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+/// Event emitted when the admin changes the withdrawal timelock
+#[event]
+pub struct WithdrawalDelayUpdatedEvent {
+    pub authority: Pubkey,
+    pub withdrawal_delay: i64,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+/// Event emitted when the admin changes the deposit caps
+#[event]
+pub struct DepositCapsUpdatedEvent {
+    pub authority: Pubkey,
+    pub max_vault_balance: u64,
+    pub max_tvl: u64,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+/// Event emitted when the admin changes the withdrawal rate limits
+#[event]
+pub struct WithdrawalLimitsUpdatedEvent {
+    pub authority: Pubkey,
+    pub window: i64,
+    pub max_vault_withdrawal: u64,
+    pub max_global_withdrawal: u64,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+/// Event emitted when the admin changes the protocol fee
+#[event]
+pub struct FeeUpdatedEvent {
+    pub authority: Pubkey,
+    pub fee_bps: u16,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+/// Event emitted when the admin changes the borrow fee
+#[event]
+pub struct BorrowFeeUpdatedEvent {
+    pub authority: Pubkey,
+    pub borrow_fee_bps: u16,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+/// Event emitted when the admin sweeps a treasury token account
+#[event]
+pub struct FeesCollectedEvent {
+    pub treasury_token_account: Pubkey,
+    pub destination: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+/// Event emitted when the admin distributes yield to the vaults of a mint
+#[event]
+pub struct YieldDistributedEvent {
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub index: u64,
+    pub total_shares: u64,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+/// Event emitted when the protocol is paused or unpaused
+#[event]
+pub struct PauseEvent {
+    pub authority: Pubkey,
+    pub paused: bool,
+    pub reason: u8,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+/// Event emitted when the admin freezes or unfreezes a vault
+#[event]
+pub struct VaultFreezeEvent {
+    pub vault: Pubkey,
+    pub admin: Pubkey,
+    pub previous_status: VaultStatus,
+    pub status: VaultStatus,
+    pub sequence: u64,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+/// Event emitted when the admin proposes a new admin
+#[event]
+pub struct AdminProposedEvent {
+    pub authority: Pubkey,
+    pub admin: Pubkey,
+    pub pending_admin: Pubkey,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+/// Event emitted when a proposed admin accepts and takes over
+#[event]
+pub struct AdminChangedEvent {
+    pub authority: Pubkey,
+    pub previous_admin: Pubkey,
+    pub new_admin: Pubkey,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+/// Event emitted when the multisig signer set is created or replaced
+#[event]
+pub struct MultisigUpdatedEvent {
+    pub multisig: Pubkey,
+    pub signers: Vec<Pubkey>,
+    pub threshold: u8,
+    pub signer_set_seqno: u32,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+/// Event emitted when a multisig signer creates a proposal
+#[event]
+pub struct ProposalCreatedEvent {
+    pub proposal: Pubkey,
+    pub index: u64,
+    pub proposer: Pubkey,
+    pub action: AdminAction,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+/// Event emitted when a multisig signer approves a proposal
+#[event]
+pub struct ProposalApprovedEvent {
+    pub proposal: Pubkey,
+    pub signer: Pubkey,
+    pub approvals: u8,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+/// Event emitted when an approved proposal is executed
+#[event]
+pub struct ProposalExecutedEvent {
+    pub proposal: Pubkey,
+    pub index: u64,
+    pub action: AdminAction,
+    pub slot: u64,
     pub timestamp: i64,
 }
@@ -0,0 +1,93 @@
+mod common;
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use vault_manager::VaultError;
+
+fn propose_ix(env: &TestEnv, admin: Pubkey, new_admin: Pubkey) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::ManageAuthority {
+            admin,
+            authority: env.authority,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::ProposeAdmin { new_admin }.data(),
+    }
+}
+
+fn accept_ix(env: &TestEnv, pending_admin: Pubkey) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::AcceptAdmin {
+            pending_admin,
+            authority: env.authority,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::AcceptAdmin {}.data(),
+    }
+}
+
+#[tokio::test]
+async fn proposed_admin_takes_over_after_accepting() {
+    let mut env = setup(0).await;
+    let old_admin = env.admin.pubkey();
+    let new_admin = Keypair::new();
+
+    let propose = propose_ix(&env, old_admin, new_admin.pubkey());
+    send(&mut env.context, &[propose], &[]).await.unwrap();
+
+    // Nothing changes until the new key signs the acceptance
+    let authority = load_authority(&mut env).await;
+    assert_eq!(authority.admin, old_admin);
+    assert_eq!(authority.pending_admin, Some(new_admin.pubkey()));
+
+    let accept = accept_ix(&env, new_admin.pubkey());
+    send(&mut env.context, &[accept], &[&new_admin]).await.unwrap();
+
+    let authority = load_authority(&mut env).await;
+    assert_eq!(authority.admin, new_admin.pubkey());
+    assert_eq!(authority.pending_admin, None);
+
+    // The previous admin has lost its rights
+    let propose = propose_ix(&env, old_admin, old_admin);
+    let result = send(&mut env.context, &[propose], &[]).await;
+    assert_vault_error(result, VaultError::UnauthorizedAdmin);
+}
+
+#[tokio::test]
+async fn only_pending_admin_can_accept() {
+    let mut env = setup(0).await;
+    let new_admin = Keypair::new();
+    let intruder = Keypair::new();
+
+    // Accepting with no proposal outstanding fails
+    let accept = accept_ix(&env, new_admin.pubkey());
+    let result = send(&mut env.context, &[accept], &[&new_admin]).await;
+    assert_vault_error(result, VaultError::NotPendingAdmin);
+
+    let propose = propose_ix(&env, env.admin.pubkey(), new_admin.pubkey());
+    send(&mut env.context, &[propose], &[]).await.unwrap();
+
+    let accept = accept_ix(&env, intruder.pubkey());
+    let result = send(&mut env.context, &[accept], &[&intruder]).await;
+    assert_vault_error(result, VaultError::NotPendingAdmin);
+
+    let authority = load_authority(&mut env).await;
+    assert_eq!(authority.admin, env.admin.pubkey());
+}
+
+#[tokio::test]
+async fn only_admin_can_propose() {
+    let mut env = setup(0).await;
+    let user = env.user.insecure_clone();
+
+    let propose = propose_ix(&env, user.pubkey(), user.pubkey());
+    let result = send(&mut env.context, &[propose], &[&user]).await;
+    assert_vault_error(result, VaultError::UnauthorizedAdmin);
+}
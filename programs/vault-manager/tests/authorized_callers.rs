@@ -0,0 +1,214 @@
+mod common;
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use vault_manager::{AuthorizedProgram, VaultAuthority, VaultError};
+
+fn transfer_ix(
+    env: &TestEnv,
+    caller: Pubkey,
+    claimed_caller: Pubkey,
+    to_vault: Pubkey,
+    to_token_account: Pubkey,
+    amount: u64,
+) -> Instruction {
+    via_caller(
+        caller,
+        claimed_caller,
+        vault_manager::accounts::TransferCollateral {
+            from_vault: env.vault,
+            to_vault,
+            from_token_account: env.vault_token_account,
+            to_token_account,
+            authority: env.authority,
+            counters: counters_address(),
+            treasury_token_account: treasury_token_address(&env.mint),
+            yield_index: Some(yield_index_address(&env.mint)),
+            yield_reserve: Some(yield_reserve_address(&env.mint)),
+            caller_program: claimed_caller,
+            caller_authority: VaultAuthority::caller_authority_address(&claimed_caller),
+            authorized_program: AuthorizedProgram::find_address(&claimed_caller).0,
+            mint: env.mint,
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None),
+        vault_manager::instruction::TransferCollateral { amount }.data(),
+    )
+}
+
+#[tokio::test]
+async fn authorized_program_can_lock_and_unlock() {
+    let mut env = setup(1_000).await;
+
+    let lock = lock_ix(&env, TRUSTED_CALLER, 400);
+    send(&mut env.context, &[lock], &[]).await.unwrap();
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.locked_balance, 400);
+    assert_eq!(vault.available_balance, 600);
+
+    let unlock = unlock_ix(&env, TRUSTED_CALLER, 150);
+    send(&mut env.context, &[unlock], &[]).await.unwrap();
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.locked_balance, 250);
+    assert_eq!(vault.available_balance, 750);
+}
+
+#[tokio::test]
+async fn rogue_program_cannot_lock() {
+    let mut env = setup(1_000).await;
+
+    let lock = via_caller(
+        ROGUE_CALLER,
+        ROGUE_CALLER,
+        lock_accounts(&env, ROGUE_CALLER),
+        vault_manager::instruction::LockCollateral { amount: 400 }.data(),
+    );
+    let result = send(&mut env.context, &[lock], &[]).await;
+    assert_vault_error(result, VaultError::UnauthorizedProgram);
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.locked_balance, 0);
+}
+
+#[tokio::test]
+async fn rogue_program_cannot_impersonate_authorized_program() {
+    let mut env = setup(1_000).await;
+
+    let lock = via_caller(
+        ROGUE_CALLER,
+        TRUSTED_CALLER,
+        lock_accounts(&env, TRUSTED_CALLER),
+        vault_manager::instruction::LockCollateral { amount: 400 }.data(),
+    );
+    let result = send(&mut env.context, &[lock], &[]).await;
+    assert_vault_error(result, VaultError::UnauthorizedProgram);
+}
+
+#[tokio::test]
+async fn rogue_program_cannot_unlock() {
+    let mut env = setup(1_000).await;
+
+    let lock = lock_ix(&env, TRUSTED_CALLER, 400);
+    send(&mut env.context, &[lock], &[]).await.unwrap();
+
+    let unlock = via_caller(
+        ROGUE_CALLER,
+        ROGUE_CALLER,
+        unlock_accounts(&env, ROGUE_CALLER),
+        vault_manager::instruction::UnlockCollateral { amount: 400 }.data(),
+    );
+    assert!(send(&mut env.context, &[unlock], &[]).await.is_err());
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.locked_balance, 400);
+}
+
+#[tokio::test]
+async fn removed_program_can_still_unlock() {
+    let mut env = setup(1_000).await;
+
+    let lock = lock_ix(&env, TRUSTED_CALLER, 400);
+    send(&mut env.context, &[lock], &[]).await.unwrap();
+
+    let admin = env.admin.insecure_clone();
+    let remove = Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::RemoveAuthorizedProgram {
+            admin: admin.pubkey(),
+            authority: env.authority,
+            authorized_program: AuthorizedProgram::find_address(&TRUSTED_CALLER).0,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::RemoveAuthorizedProgram {
+            program_id: TRUSTED_CALLER,
+        }
+        .data(),
+    };
+    send(&mut env.context, &[remove], &[]).await.unwrap();
+
+    let lock = lock_ix(&env, TRUSTED_CALLER, 100);
+    let result = send(&mut env.context, &[lock], &[]).await;
+    assert_vault_error(result, VaultError::UnauthorizedProgram);
+
+    // Winding down must not strand the collateral the program locked
+    let unlock = unlock_ix(&env, TRUSTED_CALLER, 400);
+    send(&mut env.context, &[unlock], &[]).await.unwrap();
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.locked_balance, 0);
+    assert_eq!(vault.available_balance, 1_000);
+}
+
+#[tokio::test]
+async fn rogue_program_cannot_transfer() {
+    let mut env = setup(1_000).await;
+    let counterparty = Keypair::new();
+    let (to_vault, to_token_account, _) = create_funded_vault(&mut env, &counterparty, 0).await;
+
+    let transfer = transfer_ix(
+        &env,
+        ROGUE_CALLER,
+        ROGUE_CALLER,
+        to_vault,
+        to_token_account,
+        400,
+    );
+    let result = send(&mut env.context, &[transfer], &[]).await;
+    assert_vault_error(result, VaultError::UnauthorizedProgram);
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.total_balance, 1_000);
+}
+
+#[tokio::test]
+async fn transfer_to_the_same_vault_is_rejected() {
+    let mut env = setup(1_000).await;
+    let (vault, vault_token_account) = (env.vault, env.vault_token_account);
+
+    let transfer = transfer_ix(
+        &env,
+        TRUSTED_CALLER,
+        TRUSTED_CALLER,
+        vault,
+        vault_token_account,
+        400,
+    );
+    let result = send(&mut env.context, &[transfer], &[]).await;
+    assert_vault_error(result, VaultError::InvalidVaultState);
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.total_balance, 1_000);
+    assert_eq!(vault.available_balance, 1_000);
+}
+
+#[tokio::test]
+async fn direct_call_without_program_signer_is_rejected() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+
+    let lock = Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::LockCollateral {
+            vault: env.vault,
+            authority: env.authority,
+            yield_index: Some(yield_index_address(&env.mint)),
+            caller_program: TRUSTED_CALLER,
+            caller_authority: user.pubkey(),
+            authorized_program: AuthorizedProgram::find_address(&TRUSTED_CALLER).0,
+            program_lock: program_lock_address(&env.vault, &TRUSTED_CALLER),
+            payer: user.pubkey(),
+            system_program: solana_sdk::system_program::ID,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::LockCollateral { amount: 400 }.data(),
+    };
+    let result = send(&mut env.context, &[lock], &[&user]).await;
+    assert_vault_error(result, VaultError::UnauthorizedProgram);
+}
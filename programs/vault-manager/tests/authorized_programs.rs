@@ -0,0 +1,193 @@
+mod common;
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, signature::Signer, system_program};
+use vault_manager::{AuthorizedProgram, ProgramLock, VaultError};
+
+fn remove_program_ix(env: &TestEnv, program_id: Pubkey) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::RemoveAuthorizedProgram {
+            admin: env.admin.pubkey(),
+            authority: env.authority,
+            authorized_program: AuthorizedProgram::find_address(&program_id).0,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::RemoveAuthorizedProgram { program_id }.data(),
+    }
+}
+
+fn force_unlock_ix(env: &TestEnv, program_id: Pubkey, amount: u64) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::ForceUnlock {
+            admin: env.admin.pubkey(),
+            authority: env.authority,
+            vault: env.vault,
+            yield_index: Some(yield_index_address(&env.mint)),
+            authorized_program: AuthorizedProgram::find_address(&program_id).0,
+            program_lock: program_lock_address(&env.vault, &program_id),
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::ForceUnlock { amount }.data(),
+    }
+}
+
+async fn load_record(env: &mut TestEnv, program_id: Pubkey) -> AuthorizedProgram {
+    load_account(env, AuthorizedProgram::find_address(&program_id).0).await
+}
+
+#[tokio::test]
+async fn setup_creates_one_record_per_program() {
+    let mut env = setup(0).await;
+
+    let record = load_record(&mut env, TRUSTED_CALLER).await;
+    assert_eq!(record.program_id, TRUSTED_CALLER);
+    assert_eq!(record.label, "perps");
+    assert_eq!(record.max_lock, 0);
+    assert!(record.enabled);
+
+    // New programs no longer go through the legacy vector
+    let authority = load_authority(&mut env).await;
+    assert!(authority.authorized_programs.is_empty());
+}
+
+#[tokio::test]
+async fn max_lock_caps_collateral_locked_per_vault() {
+    let mut env = setup(1_000).await;
+
+    let update = add_program_ix(env.admin.pubkey(), TRUSTED_CALLER, "perps", 500);
+    send(&mut env.context, &[update], &[]).await.unwrap();
+
+    let lock = lock_ix(&env, TRUSTED_CALLER, 400);
+    send(&mut env.context, &[lock], &[]).await.unwrap();
+
+    let lock = lock_ix(&env, TRUSTED_CALLER, 101);
+    let result = send(&mut env.context, &[lock], &[]).await;
+    assert_vault_error(result, VaultError::ProgramLockLimitExceeded);
+
+    // Topping up to exactly the cap is fine, and other programs are unaffected
+    let lock = lock_ix(&env, TRUSTED_CALLER, 100);
+    send(&mut env.context, &[lock], &[]).await.unwrap();
+    let lock = lock_ix(&env, SECOND_CALLER, 300);
+    send(&mut env.context, &[lock], &[]).await.unwrap();
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.locked_balance, 800);
+}
+
+#[tokio::test]
+async fn label_longer_than_limit_is_rejected() {
+    let mut env = setup(0).await;
+
+    let label = "x".repeat(AuthorizedProgram::MAX_LABEL_LEN + 1);
+    let add = add_program_ix(env.admin.pubkey(), ROGUE_CALLER, &label, 0);
+    let result = send(&mut env.context, &[add], &[]).await;
+    assert_vault_error(result, VaultError::ProgramLabelTooLong);
+}
+
+#[tokio::test]
+async fn disabled_program_can_be_re_enabled() {
+    let mut env = setup(1_000).await;
+    let created_at = load_record(&mut env, TRUSTED_CALLER).await.created_at;
+
+    let remove = remove_program_ix(&env, TRUSTED_CALLER);
+    send(&mut env.context, &[remove], &[]).await.unwrap();
+
+    let record = load_record(&mut env, TRUSTED_CALLER).await;
+    assert!(!record.enabled);
+
+    let lock = lock_ix(&env, TRUSTED_CALLER, 400);
+    let result = send(&mut env.context, &[lock], &[]).await;
+    assert_vault_error(result, VaultError::UnauthorizedProgram);
+
+    let add = add_program_ix(env.admin.pubkey(), TRUSTED_CALLER, "perps-v2", 0);
+    send(&mut env.context, &[add], &[]).await.unwrap();
+
+    let record = load_record(&mut env, TRUSTED_CALLER).await;
+    assert!(record.enabled);
+    assert_eq!(record.label, "perps-v2");
+    assert_eq!(record.created_at, created_at);
+
+    let lock = lock_ix(&env, TRUSTED_CALLER, 400);
+    send(&mut env.context, &[lock], &[]).await.unwrap();
+}
+
+#[tokio::test]
+async fn only_admin_can_authorize_programs() {
+    let mut env = setup(0).await;
+    let user = env.user.insecure_clone();
+
+    let add = add_program_ix(user.pubkey(), ROGUE_CALLER, "rogue", 0);
+    let result = send(&mut env.context, &[add], &[&user]).await;
+    assert_vault_error(result, VaultError::UnauthorizedAdmin);
+}
+
+#[tokio::test]
+async fn migration_requires_a_legacy_entry() {
+    let mut env = setup(0).await;
+
+    let migrate = Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::MigrateAuthorizedProgram {
+            payer: env.admin.pubkey(),
+            authority: env.authority,
+            authorized_program: AuthorizedProgram::find_address(&ROGUE_CALLER).0,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::MigrateAuthorizedProgram {
+            program_id: ROGUE_CALLER,
+        }
+        .data(),
+    };
+    let result = send(&mut env.context, &[migrate], &[]).await;
+    assert_vault_error(result, VaultError::ProgramNotAuthorized);
+}
+
+#[tokio::test]
+async fn admin_can_force_unlock_a_disabled_program() {
+    let mut env = setup(1_000).await;
+
+    let lock = lock_ix(&env, TRUSTED_CALLER, 400);
+    send(&mut env.context, &[lock], &[]).await.unwrap();
+
+    // An enabled program still manages its own locks
+    let force = force_unlock_ix(&env, TRUSTED_CALLER, 400);
+    let result = send(&mut env.context, &[force], &[]).await;
+    assert_vault_error(result, VaultError::ProgramStillEnabled);
+
+    let remove = remove_program_ix(&env, TRUSTED_CALLER);
+    send(&mut env.context, &[remove], &[]).await.unwrap();
+
+    let force = force_unlock_ix(&env, TRUSTED_CALLER, 401);
+    let result = send(&mut env.context, &[force], &[]).await;
+    assert_vault_error(result, VaultError::InvalidUnlockAmount);
+
+    let force = force_unlock_ix(&env, TRUSTED_CALLER, 400);
+    send(&mut env.context, &[force], &[]).await.unwrap();
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.locked_balance, 0);
+    assert_eq!(vault.available_balance, 1_000);
+
+    let lock_address = program_lock_address(&env.vault, &TRUSTED_CALLER);
+    let program_lock: ProgramLock = load_account(&mut env, lock_address).await;
+    assert_eq!(program_lock.locked_amount, 0);
+}
+
+#[tokio::test]
+async fn only_admin_can_force_unlock() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+
+    let lock = lock_ix(&env, TRUSTED_CALLER, 400);
+    let remove = remove_program_ix(&env, TRUSTED_CALLER);
+    send(&mut env.context, &[lock, remove], &[]).await.unwrap();
+
+    let mut force = force_unlock_ix(&env, TRUSTED_CALLER, 400);
+    force.accounts[0].pubkey = user.pubkey();
+    let result = send(&mut env.context, &[force], &[&user]).await;
+    assert_vault_error(result, VaultError::UnauthorizedAdmin);
+}
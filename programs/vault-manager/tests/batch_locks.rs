@@ -0,0 +1,192 @@
+mod common;
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+};
+use vault_manager::{AuthorizedProgram, CollateralVault, ProgramLock, VaultAuthority, VaultError};
+
+/// (vault, program lock) pairs of a batch issued by `caller`
+fn batch_accounts(vaults: &[Pubkey], caller: Pubkey) -> Vec<AccountMeta> {
+    vaults
+        .iter()
+        .flat_map(|vault| {
+            [
+                AccountMeta::new(*vault, false),
+                AccountMeta::new(program_lock_address(vault, &caller), false),
+            ]
+        })
+        .collect()
+}
+
+fn batch_lock_ix(env: &TestEnv, caller: Pubkey, entries: &[(Pubkey, u64)]) -> Instruction {
+    let vaults: Vec<Pubkey> = entries.iter().map(|(vault, _)| *vault).collect();
+    let mut accounts = vault_manager::accounts::BatchLockCollateral {
+        authority: env.authority,
+        yield_index: yield_index_address(&env.mint),
+        caller_program: caller,
+        caller_authority: VaultAuthority::caller_authority_address(&caller),
+        authorized_program: AuthorizedProgram::find_address(&caller).0,
+        payer: env.context.payer.pubkey(),
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+    accounts.extend(batch_accounts(&vaults, caller));
+
+    let amounts = entries.iter().map(|(_, amount)| *amount).collect();
+    via_caller(
+        caller,
+        caller,
+        accounts,
+        vault_manager::instruction::BatchLock { amounts }.data(),
+    )
+}
+
+fn batch_unlock_ix(env: &TestEnv, caller: Pubkey, entries: &[(Pubkey, u64)]) -> Instruction {
+    let vaults: Vec<Pubkey> = entries.iter().map(|(vault, _)| *vault).collect();
+    let mut accounts = vault_manager::accounts::BatchUnlockCollateral {
+        authority: env.authority,
+        yield_index: yield_index_address(&env.mint),
+        caller_program: caller,
+        caller_authority: VaultAuthority::caller_authority_address(&caller),
+        authorized_program: AuthorizedProgram::find_address(&caller).0,
+    }
+    .to_account_metas(None);
+    accounts.extend(batch_accounts(&vaults, caller));
+
+    let amounts = entries.iter().map(|(_, amount)| *amount).collect();
+    via_caller(
+        caller,
+        caller,
+        accounts,
+        vault_manager::instruction::BatchUnlock { amounts }.data(),
+    )
+}
+
+/// The test vault plus two more vaults of the test mint, holding 1_000 each
+async fn setup_three_vaults() -> (TestEnv, Vec<Pubkey>) {
+    let mut env = setup(1_000).await;
+    let mut vaults = vec![env.vault];
+    for _ in 0..2 {
+        let user = Keypair::new();
+        let (vault, _, _) = create_funded_vault(&mut env, &user, 1_000).await;
+        vaults.push(vault);
+    }
+    (env, vaults)
+}
+
+#[tokio::test]
+async fn batch_lock_locks_every_vault() {
+    let (mut env, vaults) = setup_three_vaults().await;
+
+    let lock = batch_lock_ix(
+        &env,
+        TRUSTED_CALLER,
+        &[(vaults[0], 100), (vaults[1], 200), (vaults[2], 300)],
+    );
+    send(&mut env.context, &[lock], &[]).await.unwrap();
+
+    for (vault, amount) in vaults.iter().zip([100, 200, 300]) {
+        let state: CollateralVault = load_account(&mut env, *vault).await;
+        assert_eq!(state.locked_balance, amount);
+        assert_eq!(state.available_balance, 1_000 - amount);
+
+        let program_lock: ProgramLock =
+            load_account(&mut env, program_lock_address(vault, &TRUSTED_CALLER)).await;
+        assert_eq!(program_lock.vault, *vault);
+        assert_eq!(program_lock.program, TRUSTED_CALLER);
+        assert_eq!(program_lock.locked_amount, amount);
+    }
+
+    // Existing ledgers are reused by the next batch
+    let lock = batch_lock_ix(&env, TRUSTED_CALLER, &[(vaults[0], 50)]);
+    send(&mut env.context, &[lock], &[]).await.unwrap();
+    let program_lock = load_program_lock(&mut env, TRUSTED_CALLER).await;
+    assert_eq!(program_lock.locked_amount, 150);
+}
+
+#[tokio::test]
+async fn batch_is_all_or_nothing() {
+    let (mut env, vaults) = setup_three_vaults().await;
+
+    let lock = batch_lock_ix(
+        &env,
+        TRUSTED_CALLER,
+        &[(vaults[0], 100), (vaults[1], 200), (vaults[2], 5_000)],
+    );
+    let result = send(&mut env.context, &[lock], &[]).await;
+    assert_vault_error(result, VaultError::InsufficientBalanceToLock);
+
+    for vault in &vaults {
+        let state: CollateralVault = load_account(&mut env, *vault).await;
+        assert_eq!(state.locked_balance, 0);
+    }
+}
+
+#[tokio::test]
+async fn batch_unlock_releases_every_vault() {
+    let (mut env, vaults) = setup_three_vaults().await;
+
+    let lock = batch_lock_ix(
+        &env,
+        TRUSTED_CALLER,
+        &[(vaults[0], 100), (vaults[1], 200), (vaults[2], 300)],
+    );
+    send(&mut env.context, &[lock], &[]).await.unwrap();
+
+    let unlock = batch_unlock_ix(
+        &env,
+        TRUSTED_CALLER,
+        &[(vaults[0], 100), (vaults[1], 150), (vaults[2], 300)],
+    );
+    send(&mut env.context, &[unlock], &[]).await.unwrap();
+
+    for (vault, locked) in vaults.iter().zip([0, 50, 0]) {
+        let state: CollateralVault = load_account(&mut env, *vault).await;
+        assert_eq!(state.locked_balance, locked);
+        assert_eq!(state.available_balance, 1_000 - locked);
+    }
+
+    // Only collateral the caller locked itself can be released
+    let unlock = batch_unlock_ix(&env, TRUSTED_CALLER, &[(vaults[1], 100)]);
+    let result = send(&mut env.context, &[unlock], &[]).await;
+    assert_vault_error(result, VaultError::InvalidUnlockAmount);
+}
+
+#[tokio::test]
+async fn malformed_batches_are_rejected() {
+    let (mut env, vaults) = setup_three_vaults().await;
+
+    // The same vault twice would overwrite its first entry
+    let lock = batch_lock_ix(&env, TRUSTED_CALLER, &[(vaults[0], 100), (vaults[0], 100)]);
+    let result = send(&mut env.context, &[lock], &[]).await;
+    assert_vault_error(result, VaultError::InvalidBatch);
+
+    // One amount without its accounts
+    let mut lock = batch_lock_ix(&env, TRUSTED_CALLER, &[(vaults[0], 100), (vaults[1], 100)]);
+    lock.accounts.truncate(lock.accounts.len() - 2);
+    let result = send(&mut env.context, &[lock], &[]).await;
+    assert_vault_error(result, VaultError::InvalidBatch);
+
+    // A vault of another mint than the batch's yield index
+    let other_mint = create_mint(&mut env.context).await;
+    let user = env.user.insecure_clone();
+    let (other_vault, _, _) =
+        create_funded_vault_for_mint(&mut env, &user, other_mint, 1_000).await;
+    let lock = batch_lock_ix(&env, TRUSTED_CALLER, &[(vaults[0], 100), (other_vault, 100)]);
+    let result = send(&mut env.context, &[lock], &[]).await;
+    assert_vault_error(result, VaultError::MintMismatch);
+}
+
+#[tokio::test]
+async fn batch_lock_requires_an_authorized_caller() {
+    let (mut env, vaults) = setup_three_vaults().await;
+
+    let lock = batch_lock_ix(&env, ROGUE_CALLER, &[(vaults[0], 100)]);
+    let result = send(&mut env.context, &[lock], &[]).await;
+    assert_vault_error(result, VaultError::UnauthorizedProgram);
+}
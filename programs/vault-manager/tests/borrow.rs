@@ -0,0 +1,226 @@
+mod common;
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    sysvar,
+};
+use vault_manager::{AuthorizedProgram, VaultAuthority, VaultError};
+
+/// Borrow `amount` from `vault` into the user's wallet through `caller`
+fn borrow_ix(env: &TestEnv, caller: Pubkey, vault: Pubkey, amount: u64) -> Instruction {
+    let accounts = vault_manager::accounts::Borrow {
+        vault,
+        vault_token_account: token_address(&vault, &env.mint, &env.token_program),
+        destination_token_account: env.user_token_account,
+        authority: env.authority,
+        counters: counters_address(),
+        yield_index: Some(yield_index_address(&env.mint)),
+        yield_reserve: Some(yield_reserve_address(&env.mint)),
+        caller_program: caller,
+        caller_authority: VaultAuthority::caller_authority_address(&caller),
+        authorized_program: AuthorizedProgram::find_address(&caller).0,
+        instructions: sysvar::instructions::ID,
+        mint: env.mint,
+        token_program: env.token_program,
+    }
+    .to_account_metas(None);
+    via_caller(
+        caller,
+        caller,
+        accounts,
+        vault_manager::instruction::Borrow { amount }.data(),
+    )
+}
+
+/// Repay `vault`'s borrow from the user's wallet
+fn repay_ix(env: &TestEnv, vault: Pubkey) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::Repay {
+            vault,
+            vault_token_account: token_address(&vault, &env.mint, &env.token_program),
+            payer: env.user.pubkey(),
+            payer_token_account: env.user_token_account,
+            treasury_token_account: treasury_token_address(&env.mint),
+            authority: env.authority,
+            yield_index: Some(yield_index_address(&env.mint)),
+            mint: env.mint,
+            token_program: env.token_program,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::Repay {}.data(),
+    }
+}
+
+fn fee_ix(env: &TestEnv, fee_bps: u16) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::ManageAuthority {
+            admin: env.admin.pubkey(),
+            authority: env.authority,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::SetFee { fee_bps }.data(),
+    }
+}
+
+fn borrow_fee_ix(env: &TestEnv, borrow_fee_bps: u16) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::ManageAuthority {
+            admin: env.admin.pubkey(),
+            authority: env.authority,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::SetBorrowFee { borrow_fee_bps }.data(),
+    }
+}
+
+/// Mint `amount` tokens of the test mint into the user's wallet
+async fn fund_user(env: &mut TestEnv, amount: u64) {
+    let mint_to = spl_token::instruction::mint_to(
+        &env.token_program,
+        &env.mint,
+        &env.user_token_account,
+        &env.admin.pubkey(),
+        &[],
+        amount,
+    )
+    .unwrap();
+    send(&mut env.context, &[mint_to], &[]).await.unwrap();
+}
+
+#[tokio::test]
+async fn borrow_and_repay_in_one_transaction() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+    let lock = lock_ix(&env, TRUSTED_CALLER, 300);
+    // Only the borrow fee applies, not the withdrawal fee
+    let fee = fee_ix(&env, 500);
+    let borrow_fee = borrow_fee_ix(&env, 100);
+    send(&mut env.context, &[lock, fee, borrow_fee], &[])
+        .await
+        .unwrap();
+    fund_user(&mut env, 10).await;
+
+    let borrow = borrow_ix(&env, TRUSTED_CALLER, env.vault, 700);
+    let repay = repay_ix(&env, env.vault);
+    send(&mut env.context, &[borrow, repay], &[&user]).await.unwrap();
+
+    // The vault is made whole and the fee goes to the treasury
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.total_balance, 1_000);
+    assert_eq!(vault.available_balance, 700);
+    assert_eq!(vault.locked_balance, 300);
+    assert_eq!(vault.borrowed, 0);
+    let vault_token_account = env.vault_token_account;
+    assert_eq!(token_balance(&mut env, vault_token_account).await, 1_000);
+    let treasury_token_account = treasury_token_address(&env.mint);
+    assert_eq!(token_balance(&mut env, treasury_token_account).await, 7);
+    let user_token_account = env.user_token_account;
+    assert_eq!(token_balance(&mut env, user_token_account).await, 3);
+
+    // Borrow and repay are one mutation each
+    assert_eq!(vault.sequence, 4);
+}
+
+#[tokio::test]
+async fn borrow_requires_a_later_repay() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+    let other_user = Keypair::new();
+    let (other_vault, _, _) = create_funded_vault(&mut env, &other_user, 1_000).await;
+
+    let borrow = borrow_ix(&env, TRUSTED_CALLER, env.vault, 500);
+    let result = send(&mut env.context, &[borrow], &[]).await;
+    assert_vault_error(result, VaultError::MissingRepay);
+
+    // Nothing is borrowed yet, so a repay cannot precede its borrow
+    let repay = repay_ix(&env, env.vault);
+    let borrow = borrow_ix(&env, TRUSTED_CALLER, env.vault, 500);
+    let result = send(&mut env.context, &[repay, borrow], &[&user]).await;
+    assert_vault_error(result, VaultError::NoOutstandingBorrow);
+
+    // A repay of another vault does not cover the borrow
+    let borrow = borrow_ix(&env, TRUSTED_CALLER, env.vault, 500);
+    let repay = repay_ix(&env, other_vault);
+    let result = send(&mut env.context, &[borrow, repay], &[&user]).await;
+    assert_vault_error(result, VaultError::MissingRepay);
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.available_balance, 1_000);
+    assert_eq!(vault.borrowed, 0);
+}
+
+#[tokio::test]
+async fn borrow_never_touches_locked_collateral() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+    let lock = lock_ix(&env, TRUSTED_CALLER, 300);
+    send(&mut env.context, &[lock], &[]).await.unwrap();
+
+    let borrow = borrow_ix(&env, TRUSTED_CALLER, env.vault, 701);
+    let repay = repay_ix(&env, env.vault);
+    let result = send(&mut env.context, &[borrow, repay], &[&user]).await;
+    assert_vault_error(result, VaultError::InsufficientBorrowBalance);
+
+    // Borrowed collateral can be neither withdrawn nor locked until repaid
+    let borrow = borrow_ix(&env, TRUSTED_CALLER, env.vault, 600);
+    let withdraw = withdraw_ix(&env, 200);
+    let repay = repay_ix(&env, env.vault);
+    let result = send(&mut env.context, &[borrow, withdraw, repay], &[&user]).await;
+    assert_vault_error(result, VaultError::InsufficientBalance);
+
+    let borrow = borrow_ix(&env, TRUSTED_CALLER, env.vault, 600);
+    let lock = lock_ix(&env, TRUSTED_CALLER, 200);
+    let repay = repay_ix(&env, env.vault);
+    let result = send(&mut env.context, &[borrow, lock, repay], &[&user]).await;
+    assert_vault_error(result, VaultError::InsufficientBalanceToLock);
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.locked_balance, 300);
+    assert_eq!(vault.available_balance, 700);
+}
+
+#[tokio::test]
+async fn one_borrow_per_vault_at_a_time() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+
+    let first = borrow_ix(&env, TRUSTED_CALLER, env.vault, 100);
+    let second = borrow_ix(&env, TRUSTED_CALLER, env.vault, 100);
+    let repay = repay_ix(&env, env.vault);
+    let result = send(&mut env.context, &[first, second, repay], &[&user]).await;
+    assert_vault_error(result, VaultError::BorrowOutstanding);
+}
+
+#[tokio::test]
+async fn borrow_requires_an_authorized_caller() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+
+    let borrow = borrow_ix(&env, ROGUE_CALLER, env.vault, 100);
+    let repay = repay_ix(&env, env.vault);
+    let result = send(&mut env.context, &[borrow, repay], &[&user]).await;
+    assert_vault_error(result, VaultError::UnauthorizedProgram);
+}
+
+#[tokio::test]
+async fn borrow_fee_out_of_range_is_rejected() {
+    let mut env = setup(0).await;
+
+    let borrow_fee = borrow_fee_ix(&env, VaultAuthority::MAX_FEE_BPS + 1);
+    let result = send(&mut env.context, &[borrow_fee], &[]).await;
+    assert_vault_error(result, VaultError::InvalidFee);
+
+    let borrow_fee = borrow_fee_ix(&env, VaultAuthority::MAX_FEE_BPS);
+    send(&mut env.context, &[borrow_fee], &[]).await.unwrap();
+
+    let authority = load_authority(&mut env).await;
+    assert_eq!(authority.borrow_fee_bps, VaultAuthority::MAX_FEE_BPS);
+    assert_eq!(authority.fee_bps, 0);
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use solana_sdk::{instruction::Instruction, signature::Signer};
+use vault_manager::VaultError;
+
+fn close_ix(env: &TestEnv) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::CloseVault {
+            user: env.user.pubkey(),
+            vault: env.vault,
+            vault_token_account: env.vault_token_account,
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::CloseVault {}.data(),
+    }
+}
+
+#[tokio::test]
+async fn close_empty_vault_returns_rent_to_owner() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+
+    let withdraw = withdraw_ix(&env, 1_000);
+    send(&mut env.context, &[withdraw], &[&user]).await.unwrap();
+
+    let banks = &mut env.context.banks_client;
+    let vault_rent = banks.get_account(env.vault).await.unwrap().unwrap().lamports;
+    let token_rent = banks
+        .get_account(env.vault_token_account)
+        .await
+        .unwrap()
+        .unwrap()
+        .lamports;
+    let before = banks.get_balance(user.pubkey()).await.unwrap();
+
+    let close = close_ix(&env);
+    send(&mut env.context, &[close], &[&user]).await.unwrap();
+
+    let banks = &mut env.context.banks_client;
+    assert!(banks.get_account(env.vault).await.unwrap().is_none());
+    assert!(banks
+        .get_account(env.vault_token_account)
+        .await
+        .unwrap()
+        .is_none());
+    let after = banks.get_balance(user.pubkey()).await.unwrap();
+    assert_eq!(after, before + vault_rent + token_rent);
+}
+
+#[tokio::test]
+async fn close_with_balance_is_rejected() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+
+    let close = close_ix(&env);
+    let result = send(&mut env.context, &[close], &[&user]).await;
+    assert_vault_error(result, VaultError::VaultNotEmpty);
+}
+
+#[tokio::test]
+async fn close_with_locked_collateral_is_rejected() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+
+    let lock = lock_ix(&env, TRUSTED_CALLER, 1_000);
+    send(&mut env.context, &[lock], &[]).await.unwrap();
+
+    let close = close_ix(&env);
+    let result = send(&mut env.context, &[close], &[&user]).await;
+    assert_vault_error(result, VaultError::HasOpenPositions);
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.locked_balance, 1_000);
+}
@@ -0,0 +1,714 @@
+#![allow(dead_code)]
+
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    program::invoke_signed,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction, system_program,
+    transaction::{Transaction, TransactionError},
+};
+use spl_token_2022::extension::{ExtensionType, StateWithExtensions};
+use vault_manager::{
+    AuthorizedProgram, CollateralVault, ProgramLock, VaultAuthority, VaultError, YieldIndex,
+};
+
+/// Authorized integration program (e.g. perps)
+pub const TRUSTED_CALLER: Pubkey = Pubkey::new_from_array([7u8; 32]);
+/// Second authorized integration program (e.g. options)
+pub const SECOND_CALLER: Pubkey = Pubkey::new_from_array([8u8; 32]);
+/// Program that is never added to the authorized list
+pub const ROGUE_CALLER: Pubkey = Pubkey::new_from_array([9u8; 32]);
+
+fn process_vault_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    vault_manager::entry(program_id, accounts, data)
+}
+
+/// Mock integration program: forwards its instruction data to the vault
+/// program (account 0), signing with its own caller PDA.
+fn process_caller_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let (caller_authority, bump) =
+        Pubkey::find_program_address(&[VaultAuthority::CALLER_SEED], program_id);
+    let forwarded = &accounts[1..];
+
+    let ix = Instruction {
+        program_id: *accounts[0].key,
+        accounts: forwarded
+            .iter()
+            .map(|a| AccountMeta {
+                pubkey: *a.key,
+                is_signer: a.is_signer || *a.key == caller_authority,
+                is_writable: a.is_writable,
+            })
+            .collect(),
+        data: data.to_vec(),
+    };
+
+    invoke_signed(&ix, accounts, &[&[VaultAuthority::CALLER_SEED, &[bump]]])
+}
+
+pub struct TestEnv {
+    pub context: ProgramTestContext,
+    pub admin: Keypair,
+    pub user: Keypair,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    pub vault_token_account: Pubkey,
+    pub user_token_account: Pubkey,
+    pub authority: Pubkey,
+    pub token_program: Pubkey,
+}
+
+/// Start a validator with the vault program and mock callers, an authority
+/// with `TRUSTED_CALLER` and `SECOND_CALLER` authorized, and one user vault
+/// holding `deposit_amount`.
+pub async fn setup(deposit_amount: u64) -> TestEnv {
+    let (mut context, admin, authority) = start().await;
+    let mint = create_mint(&mut context).await;
+    finish_setup(context, admin, authority, mint, spl_token::ID, deposit_amount).await
+}
+
+/// Same as `setup`, with the test vault holding a Token-2022 mint that is
+/// created with `extensions` initialized by `extension_ixs`
+pub async fn setup_token_2022(
+    deposit_amount: u64,
+    mint: Keypair,
+    extensions: &[ExtensionType],
+    extension_ixs: Vec<Instruction>,
+) -> TestEnv {
+    let (mut context, admin, authority) = start().await;
+    create_token_2022_mint(&mut context, &mint, extensions, extension_ixs).await;
+    init_mint_accounts(&mut context, mint.pubkey(), spl_token_2022::ID, false).await;
+    finish_setup(
+        context,
+        admin,
+        authority,
+        mint.pubkey(),
+        spl_token_2022::ID,
+        deposit_amount,
+    )
+    .await
+}
+
+async fn start() -> (ProgramTestContext, Keypair, Pubkey) {
+    let mut program_test = ProgramTest::new(
+        "vault_manager",
+        vault_manager::ID,
+        processor!(process_vault_instruction),
+    );
+    for (name, id) in [
+        ("trusted_caller", TRUSTED_CALLER),
+        ("second_caller", SECOND_CALLER),
+        ("rogue_caller", ROGUE_CALLER),
+    ] {
+        program_test.add_program(name, id, processor!(process_caller_instruction));
+    }
+
+    let mut context = program_test.start_with_context().await;
+    let admin = context.payer.insecure_clone();
+    let (authority, _) = Pubkey::find_program_address(&[b"authority"], &vault_manager::ID);
+
+    let mut setup_ixs = vec![Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::InitializeAuthority {
+            admin: admin.pubkey(),
+            authority,
+            counters: counters_address(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::InitializeAuthority {}.data(),
+    }];
+    for (program_id, label) in [(TRUSTED_CALLER, "perps"), (SECOND_CALLER, "options")] {
+        setup_ixs.push(add_program_ix(admin.pubkey(), program_id, label, 0));
+    }
+    send(&mut context, &setup_ixs, &[]).await.unwrap();
+    (context, admin, authority)
+}
+
+async fn finish_setup(
+    context: ProgramTestContext,
+    admin: Keypair,
+    authority: Pubkey,
+    mint: Pubkey,
+    token_program: Pubkey,
+    deposit_amount: u64,
+) -> TestEnv {
+    let mut env = TestEnv {
+        context,
+        admin,
+        user: Keypair::new(),
+        mint,
+        vault: Pubkey::default(),
+        vault_token_account: Pubkey::default(),
+        user_token_account: Pubkey::default(),
+        authority,
+        token_program,
+    };
+    let user = env.user.insecure_clone();
+    let (vault, vault_token_account, user_token_account) =
+        create_funded_vault(&mut env, &user, deposit_amount).await;
+    env.vault = vault;
+    env.vault_token_account = vault_token_account;
+    env.user_token_account = user_token_account;
+    env
+}
+
+/// Authorize `program_id` as the single admin, with an optional lock cap
+pub fn add_program_ix(
+    admin: Pubkey,
+    program_id: Pubkey,
+    label: &str,
+    max_lock: u64,
+) -> Instruction {
+    let (authority, _) = Pubkey::find_program_address(&[b"authority"], &vault_manager::ID);
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::AddAuthorizedProgram {
+            admin,
+            authority,
+            authorized_program: AuthorizedProgram::find_address(&program_id).0,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::AddAuthorizedProgram {
+            program_id,
+            label: label.to_string(),
+            max_lock,
+        }
+        .data(),
+    }
+}
+
+/// Create a 6-decimal SPL mint with the context payer as mint authority, the
+/// treasury token account that collects its fees, and its yield index (which
+/// excludes locked collateral). The authority must already be initialized.
+pub async fn create_mint(context: &mut ProgramTestContext) -> Pubkey {
+    create_mint_with_yield(context, false).await
+}
+
+/// Same as `create_mint`, choosing whether locked collateral earns yield
+pub async fn create_mint_with_yield(
+    context: &mut ProgramTestContext,
+    include_locked: bool,
+) -> Pubkey {
+    let mint = new_mint(context).await;
+    init_mint_accounts(context, mint, spl_token::ID, include_locked).await;
+    mint
+}
+
+/// Create a 6-decimal mint with a treasury token account but no yield index
+pub async fn create_mint_without_yield(context: &mut ProgramTestContext) -> Pubkey {
+    let payer = context.payer.insecure_clone();
+    let mint = new_mint(context).await;
+    let ix = spl_associated_token_account::instruction::create_associated_token_account(
+        &payer.pubkey(),
+        &VaultAuthority::find_treasury_address().0,
+        &mint,
+        &spl_token::ID,
+    );
+    send(context, &[ix], &[]).await.unwrap();
+    mint
+}
+
+async fn new_mint(context: &mut ProgramTestContext) -> Pubkey {
+    let payer = context.payer.insecure_clone();
+    let mint = Keypair::new();
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    let ixs = [
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &mint.pubkey(),
+            rent.minimum_balance(spl_token::state::Mint::LEN),
+            spl_token::state::Mint::LEN as u64,
+            &spl_token::ID,
+        ),
+        spl_token::instruction::initialize_mint(
+            &spl_token::ID,
+            &mint.pubkey(),
+            &payer.pubkey(),
+            None,
+            6,
+        )
+        .unwrap(),
+    ];
+    send(context, &ixs, &[&mint]).await.unwrap();
+    mint.pubkey()
+}
+
+/// Create a 6-decimal Token-2022 mint with the context payer as mint
+/// authority. `extension_ixs` initialize `extensions` before the mint itself.
+pub async fn create_token_2022_mint(
+    context: &mut ProgramTestContext,
+    mint: &Keypair,
+    extensions: &[ExtensionType],
+    extension_ixs: Vec<Instruction>,
+) {
+    let payer = context.payer.insecure_clone();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let space =
+        ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(extensions)
+            .unwrap();
+
+    let mut ixs = vec![system_instruction::create_account(
+        &payer.pubkey(),
+        &mint.pubkey(),
+        rent.minimum_balance(space),
+        space as u64,
+        &spl_token_2022::ID,
+    )];
+    ixs.extend(extension_ixs);
+    ixs.push(
+        spl_token_2022::instruction::initialize_mint(
+            &spl_token_2022::ID,
+            &mint.pubkey(),
+            &payer.pubkey(),
+            None,
+            6,
+        )
+        .unwrap(),
+    );
+    send(context, &ixs, &[mint]).await.unwrap();
+}
+
+/// Create the treasury token account and yield index of `mint`
+pub async fn init_mint_accounts(
+    context: &mut ProgramTestContext,
+    mint: Pubkey,
+    token_program: Pubkey,
+    include_locked: bool,
+) {
+    let payer = context.payer.insecure_clone();
+    let ixs = [
+        spl_associated_token_account::instruction::create_associated_token_account(
+            &payer.pubkey(),
+            &VaultAuthority::find_treasury_address().0,
+            &mint,
+            &token_program,
+        ),
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::InitializeYieldIndex {
+                admin: payer.pubkey(),
+                authority: Pubkey::find_program_address(&[b"authority"], &vault_manager::ID).0,
+                yield_index: yield_index_address(&mint),
+                yield_reserve: token_address(&yield_index_address(&mint), &mint, &token_program),
+                mint,
+                token_program,
+                associated_token_program: spl_associated_token_account::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::InitializeYieldIndex { include_locked }.data(),
+        },
+    ];
+    send(context, &ixs, &[]).await.unwrap();
+}
+
+/// Fund `user` with SOL and tokens, then initialize their vault and deposit
+/// `deposit_amount`. Returns (vault, vault token account, user token account).
+pub async fn create_funded_vault(
+    env: &mut TestEnv,
+    user: &Keypair,
+    deposit_amount: u64,
+) -> (Pubkey, Pubkey, Pubkey) {
+    let mint = env.mint;
+    create_funded_vault_for_mint(env, user, mint, deposit_amount).await
+}
+
+/// Same as `create_funded_vault`, for the user's vault of `mint`
+pub async fn create_funded_vault_for_mint(
+    env: &mut TestEnv,
+    user: &Keypair,
+    mint: Pubkey,
+    deposit_amount: u64,
+) -> (Pubkey, Pubkey, Pubkey) {
+    create_funded_sub_account(env, user, mint, 0, deposit_amount).await
+}
+
+/// Same as `create_funded_vault_for_mint`, for one of the user's sub-accounts
+pub async fn create_funded_sub_account(
+    env: &mut TestEnv,
+    user: &Keypair,
+    mint: Pubkey,
+    sub_account: u16,
+    deposit_amount: u64,
+) -> (Pubkey, Pubkey, Pubkey) {
+    let admin = env.admin.insecure_clone();
+    let token_program = env
+        .context
+        .banks_client
+        .get_account(mint)
+        .await
+        .unwrap()
+        .unwrap()
+        .owner;
+    let vault = vault_address(&user.pubkey(), &mint, sub_account);
+    let vault_token_account = token_address(&vault, &mint, &token_program);
+    let user_token_account = token_address(&user.pubkey(), &mint, &token_program);
+    // The mint may not have a yield index
+    let yield_index = yield_index_address(&mint);
+    let yield_index = env
+        .context
+        .banks_client
+        .get_account(yield_index)
+        .await
+        .unwrap()
+        .map(|_| yield_index);
+
+    let mut funding_ixs = vec![
+        system_instruction::transfer(&admin.pubkey(), &user.pubkey(), 1_000_000_000),
+        spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+            &admin.pubkey(),
+            &user.pubkey(),
+            &mint,
+            &token_program,
+        ),
+    ];
+    if deposit_amount > 0 {
+        funding_ixs.push(
+            spl_token_2022::instruction::mint_to(
+                &token_program,
+                &mint,
+                &user_token_account,
+                &admin.pubkey(),
+                &[],
+                deposit_amount,
+            )
+            .unwrap(),
+        );
+    }
+    send(&mut env.context, &funding_ixs, &[]).await.unwrap();
+
+    let mut user_ixs = vec![initialize_vault_ix(
+        &user.pubkey(),
+        &mint,
+        &token_program,
+        sub_account,
+    )];
+    if deposit_amount > 0 {
+        user_ixs.push(Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::Deposit {
+                user: user.pubkey(),
+                vault,
+                user_token_account,
+                vault_token_account,
+                authority: env.authority,
+                counters: counters_address(),
+                yield_index,
+                owner: user.pubkey(),
+                mint,
+                token_program,
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::Deposit {
+                amount: deposit_amount,
+            }
+            .data(),
+        });
+    }
+    send(&mut env.context, &user_ixs, &[user]).await.unwrap();
+
+    (vault, vault_token_account, user_token_account)
+}
+
+/// Initialize `user`'s vault for `mint` at `sub_account`
+pub fn initialize_vault_ix(
+    user: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+    sub_account: u16,
+) -> Instruction {
+    let vault = vault_address(user, mint, sub_account);
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::InitializeVault {
+            user: *user,
+            vault,
+            vault_token_account: token_address(&vault, mint, token_program),
+            mint: *mint,
+            token_program: *token_program,
+            associated_token_program: spl_associated_token_account::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::InitializeVault { sub_account }.data(),
+    }
+}
+
+pub async fn send(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let payer = context.payer.insecure_clone();
+    let mut all_signers = vec![&payer];
+    all_signers.extend_from_slice(signers);
+
+    let blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&payer.pubkey()),
+        &all_signers,
+        blockhash,
+    );
+    context.banks_client.process_transaction(tx).await
+}
+
+/// Move the validator clock forward by `seconds`
+pub async fn advance_clock(env: &mut TestEnv, seconds: i64) {
+    let mut clock: Clock = env.context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp += seconds;
+    env.context.set_sysvar(&clock);
+}
+
+pub fn vault_address(owner: &Pubkey, mint: &Pubkey, sub_account: u16) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"vault", owner.as_ref(), mint.as_ref(), &sub_account.to_le_bytes()],
+        &vault_manager::ID,
+    )
+    .0
+}
+
+/// Route an instruction through one of the mock caller programs, which signs
+/// with its own caller PDA while claiming to be `claimed_caller`.
+pub fn via_caller(
+    caller: Pubkey,
+    claimed_caller: Pubkey,
+    mut accounts: Vec<AccountMeta>,
+    data: Vec<u8>,
+) -> Instruction {
+    let own_authority = VaultAuthority::caller_authority_address(&caller);
+    for meta in accounts.iter_mut() {
+        if meta.pubkey == VaultAuthority::caller_authority_address(&claimed_caller) {
+            meta.pubkey = own_authority;
+            meta.is_signer = false;
+        }
+    }
+
+    let mut metas = vec![AccountMeta::new_readonly(vault_manager::ID, false)];
+    metas.extend(accounts);
+    Instruction {
+        program_id: caller,
+        accounts: metas,
+        data,
+    }
+}
+
+/// Associated token account of `owner` for `mint` under `token_program`
+pub fn token_address(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    spl_associated_token_account::get_associated_token_address_with_program_id(
+        owner,
+        mint,
+        token_program,
+    )
+}
+
+/// Treasury token account of an SPL Token `mint`, created alongside the mint
+pub fn treasury_token_address(mint: &Pubkey) -> Pubkey {
+    spl_associated_token_account::get_associated_token_address(
+        &VaultAuthority::find_treasury_address().0,
+        mint,
+    )
+}
+
+pub fn yield_index_address(mint: &Pubkey) -> Pubkey {
+    YieldIndex::find_address(mint).0
+}
+
+/// Token account of the yield index that holds undistributed yield, for an
+/// SPL Token `mint`
+pub fn yield_reserve_address(mint: &Pubkey) -> Pubkey {
+    spl_associated_token_account::get_associated_token_address(&yield_index_address(mint), mint)
+}
+
+pub fn counters_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"protocol_counters"], &vault_manager::ID).0
+}
+
+pub fn program_lock_address(vault: &Pubkey, program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"program_lock", vault.as_ref(), program.as_ref()],
+        &vault_manager::ID,
+    )
+    .0
+}
+
+pub fn lock_accounts(env: &TestEnv, claimed_caller: Pubkey) -> Vec<AccountMeta> {
+    vault_manager::accounts::LockCollateral {
+        vault: env.vault,
+        authority: env.authority,
+        yield_index: Some(yield_index_address(&env.mint)),
+        caller_program: claimed_caller,
+        caller_authority: VaultAuthority::caller_authority_address(&claimed_caller),
+        authorized_program: AuthorizedProgram::find_address(&claimed_caller).0,
+        program_lock: program_lock_address(&env.vault, &claimed_caller),
+        payer: env.context.payer.pubkey(),
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None)
+}
+
+pub fn unlock_accounts(env: &TestEnv, claimed_caller: Pubkey) -> Vec<AccountMeta> {
+    vault_manager::accounts::UnlockCollateral {
+        vault: env.vault,
+        authority: env.authority,
+        yield_index: Some(yield_index_address(&env.mint)),
+        caller_program: claimed_caller,
+        caller_authority: VaultAuthority::caller_authority_address(&claimed_caller),
+        authorized_program: AuthorizedProgram::find_address(&claimed_caller).0,
+        program_lock: program_lock_address(&env.vault, &claimed_caller),
+    }
+    .to_account_metas(None)
+}
+
+/// Lock `amount` in the test vault through an authorized caller
+pub fn lock_ix(env: &TestEnv, caller: Pubkey, amount: u64) -> Instruction {
+    via_caller(
+        caller,
+        caller,
+        lock_accounts(env, caller),
+        vault_manager::instruction::LockCollateral { amount }.data(),
+    )
+}
+
+/// Unlock `amount` in the test vault through an authorized caller
+pub fn unlock_ix(env: &TestEnv, caller: Pubkey, amount: u64) -> Instruction {
+    via_caller(
+        caller,
+        caller,
+        unlock_accounts(env, caller),
+        vault_manager::instruction::UnlockCollateral { amount }.data(),
+    )
+}
+
+/// Pause the protocol, signed by `admin`
+pub fn pause_ix(env: &TestEnv, admin: Pubkey, reason: u8) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::ManageAuthority {
+            admin,
+            authority: env.authority,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::Pause { reason }.data(),
+    }
+}
+
+/// Deposit `amount` from the user's wallet into the test vault
+pub fn deposit_ix(env: &TestEnv, amount: u64) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::Deposit {
+            user: env.user.pubkey(),
+            vault: env.vault,
+            user_token_account: env.user_token_account,
+            vault_token_account: env.vault_token_account,
+            authority: env.authority,
+            counters: counters_address(),
+            yield_index: Some(yield_index_address(&env.mint)),
+            owner: env.user.pubkey(),
+            mint: env.mint,
+            token_program: env.token_program,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::Deposit { amount }.data(),
+    }
+}
+
+/// Withdraw `amount` from the test vault back to the user's wallet
+pub fn withdraw_ix(env: &TestEnv, amount: u64) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::Withdraw {
+            user: env.user.pubkey(),
+            vault: env.vault,
+            user_token_account: env.user_token_account,
+            vault_token_account: env.vault_token_account,
+            authority: env.authority,
+            counters: counters_address(),
+            treasury_token_account: token_address(
+                &VaultAuthority::find_treasury_address().0,
+                &env.mint,
+                &env.token_program,
+            ),
+            yield_index: Some(yield_index_address(&env.mint)),
+            yield_reserve: Some(token_address(
+                &yield_index_address(&env.mint),
+                &env.mint,
+                &env.token_program,
+            )),
+            owner: env.user.pubkey(),
+            mint: env.mint,
+            token_program: env.token_program,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::Withdraw { amount }.data(),
+    }
+}
+
+pub async fn load_account<T: AccountDeserialize>(env: &mut TestEnv, address: Pubkey) -> T {
+    let account = env
+        .context
+        .banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .unwrap();
+    T::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+pub async fn load_vault(env: &mut TestEnv) -> CollateralVault {
+    let vault = env.vault;
+    load_account(env, vault).await
+}
+
+pub async fn load_authority(env: &mut TestEnv) -> VaultAuthority {
+    let authority = env.authority;
+    load_account(env, authority).await
+}
+
+pub async fn load_program_lock(env: &mut TestEnv, program: Pubkey) -> ProgramLock {
+    let address = program_lock_address(&env.vault, &program);
+    load_account(env, address).await
+}
+
+pub async fn token_balance(env: &mut TestEnv, token_account: Pubkey) -> u64 {
+    let account = env
+        .context
+        .banks_client
+        .get_account(token_account)
+        .await
+        .unwrap()
+        .unwrap();
+    StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+        .unwrap()
+        .base
+        .amount
+}
+
+pub fn assert_vault_error(result: Result<(), BanksClientError>, expected: VaultError) {
+    match result.unwrap_err().unwrap() {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => {
+            assert_eq!(code, u32::from(expected));
+        }
+        other => panic!("unexpected error: {:?}", other),
+    }
+}
@@ -0,0 +1,103 @@
+mod common;
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use vault_manager::{ProtocolCounters, VaultError};
+
+fn caps_ix(env: &TestEnv, admin: Pubkey, max_vault_balance: u64, max_tvl: u64) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::ManageAuthority {
+            admin,
+            authority: env.authority,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::SetDepositCaps {
+            max_vault_balance,
+            max_tvl,
+        }
+        .data(),
+    }
+}
+
+async fn total_value_locked(env: &mut TestEnv) -> u64 {
+    let counters: ProtocolCounters = load_account(env, counters_address()).await;
+    counters.total_value_locked
+}
+
+#[tokio::test]
+async fn counters_track_deposits_and_withdrawals() {
+    let mut env = setup(1_000).await;
+    assert_eq!(total_value_locked(&mut env).await, 1_000);
+
+    let other = Keypair::new();
+    create_funded_vault(&mut env, &other, 500).await;
+    assert_eq!(total_value_locked(&mut env).await, 1_500);
+
+    let withdraw = withdraw_ix(&env, 600);
+    let user = env.user.insecure_clone();
+    send(&mut env.context, &[withdraw], &[&user]).await.unwrap();
+    assert_eq!(total_value_locked(&mut env).await, 900);
+}
+
+#[tokio::test]
+async fn deposit_above_vault_cap_is_rejected() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+
+    // Move funds back to the wallet so they can be re-deposited
+    let withdraw = withdraw_ix(&env, 600);
+    let caps = caps_ix(&env, env.admin.pubkey(), 700, 0);
+    send(&mut env.context, &[withdraw, caps], &[&user]).await.unwrap();
+
+    let authority = load_authority(&mut env).await;
+    assert_eq!(authority.max_vault_balance, 700);
+    assert_eq!(authority.max_tvl, 0);
+
+    // Filling the vault up to exactly the cap is allowed
+    let deposit = deposit_ix(&env, 300);
+    send(&mut env.context, &[deposit], &[&user]).await.unwrap();
+
+    let deposit = deposit_ix(&env, 1);
+    let result = send(&mut env.context, &[deposit], &[&user]).await;
+    assert_vault_error(result, VaultError::VaultDepositCapExceeded);
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.total_balance, 700);
+}
+
+#[tokio::test]
+async fn deposit_above_tvl_cap_is_rejected() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+    let other = Keypair::new();
+    create_funded_vault(&mut env, &other, 500).await;
+
+    let withdraw = withdraw_ix(&env, 200);
+    let caps = caps_ix(&env, env.admin.pubkey(), 0, 1_499);
+    send(&mut env.context, &[withdraw, caps], &[&user]).await.unwrap();
+
+    // 1_300 locked program-wide: the other vault's deposit counts too
+    let deposit = deposit_ix(&env, 200);
+    let result = send(&mut env.context, &[deposit], &[&user]).await;
+    assert_vault_error(result, VaultError::TvlCapExceeded);
+
+    let deposit = deposit_ix(&env, 199);
+    send(&mut env.context, &[deposit], &[&user]).await.unwrap();
+    assert_eq!(total_value_locked(&mut env).await, 1_499);
+}
+
+#[tokio::test]
+async fn only_admin_can_set_caps() {
+    let mut env = setup(0).await;
+    let user = env.user.insecure_clone();
+
+    let caps = caps_ix(&env, user.pubkey(), 1, 1);
+    let result = send(&mut env.context, &[caps], &[&user]).await;
+    assert_vault_error(result, VaultError::UnauthorizedAdmin);
+}
@@ -0,0 +1,150 @@
+mod common;
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use vault_manager::{ProtocolCounters, VaultError};
+
+fn deposit_for_ix(
+    env: &TestEnv,
+    payer: Pubkey,
+    beneficiary: Pubkey,
+    vault: Pubkey,
+    vault_token_account: Pubkey,
+    amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::DepositFor {
+            payer,
+            vault,
+            payer_token_account: token_address(&payer, &env.mint, &env.token_program),
+            vault_token_account,
+            authority: env.authority,
+            counters: counters_address(),
+            yield_index: Some(yield_index_address(&env.mint)),
+            mint: env.mint,
+            beneficiary,
+            token_program: env.token_program,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::DepositFor { amount }.data(),
+    }
+}
+
+/// Create a wallet holding `amount` tokens of the test mint
+async fn funded_payer(env: &mut TestEnv, amount: u64) -> Keypair {
+    let payer = Keypair::new();
+    let admin = env.admin.pubkey();
+    let token_account = token_address(&payer.pubkey(), &env.mint, &env.token_program);
+    let ixs = [
+        spl_associated_token_account::instruction::create_associated_token_account(
+            &admin,
+            &payer.pubkey(),
+            &env.mint,
+            &env.token_program,
+        ),
+        spl_token::instruction::mint_to(
+            &env.token_program,
+            &env.mint,
+            &token_account,
+            &admin,
+            &[],
+            amount,
+        )
+        .unwrap(),
+    ];
+    send(&mut env.context, &ixs, &[]).await.unwrap();
+    payer
+}
+
+#[tokio::test]
+async fn payer_funds_another_users_vault() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+    let payer = funded_payer(&mut env, 500).await;
+
+    let deposit = deposit_for_ix(
+        &env,
+        payer.pubkey(),
+        user.pubkey(),
+        env.vault,
+        env.vault_token_account,
+        500,
+    );
+    send(&mut env.context, &[deposit], &[&payer]).await.unwrap();
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.owner, user.pubkey());
+    assert_eq!(vault.total_balance, 1_500);
+    assert_eq!(vault.available_balance, 1_500);
+    assert_eq!(vault.total_deposited, 1_500);
+    let payer_token_account = token_address(&payer.pubkey(), &env.mint, &env.token_program);
+    assert_eq!(token_balance(&mut env, payer_token_account).await, 0);
+
+    let counters: ProtocolCounters = load_account(&mut env, counters_address()).await;
+    assert_eq!(counters.total_value_locked, 1_500);
+
+    // The funds belong to the owner, who can withdraw them
+    let withdraw = withdraw_ix(&env, 1_500);
+    send(&mut env.context, &[withdraw], &[&user]).await.unwrap();
+    let user_token_account = env.user_token_account;
+    assert_eq!(token_balance(&mut env, user_token_account).await, 1_500);
+}
+
+#[tokio::test]
+async fn beneficiary_must_own_the_vault() {
+    let mut env = setup(1_000).await;
+    let payer = funded_payer(&mut env, 500).await;
+
+    // The vault seeds are derived from the named beneficiary
+    let deposit = deposit_for_ix(
+        &env,
+        payer.pubkey(),
+        payer.pubkey(),
+        env.vault,
+        env.vault_token_account,
+        500,
+    );
+    assert!(send(&mut env.context, &[deposit], &[&payer]).await.is_err());
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.total_balance, 1_000);
+}
+
+#[tokio::test]
+async fn deposit_for_respects_deposit_caps() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+    let payer = funded_payer(&mut env, 500).await;
+
+    let caps = Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::ManageAuthority {
+            admin: env.admin.pubkey(),
+            authority: env.authority,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::SetDepositCaps {
+            max_vault_balance: 1_200,
+            max_tvl: 0,
+        }
+        .data(),
+    };
+    send(&mut env.context, &[caps], &[]).await.unwrap();
+
+    let deposit = deposit_for_ix(
+        &env,
+        payer.pubkey(),
+        user.pubkey(),
+        env.vault,
+        env.vault_token_account,
+        500,
+    );
+    let result = send(&mut env.context, &[deposit], &[&payer]).await;
+    assert_vault_error(result, VaultError::VaultDepositCapExceeded);
+}
@@ -0,0 +1,363 @@
+mod common;
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+};
+use vault_manager::{
+    AuthorizedProgram, CollateralVault, InsuranceFund, ProtocolCounters, VaultAuthority,
+    VaultError, YieldIndex,
+};
+
+fn fund_address(env: &TestEnv) -> Pubkey {
+    InsuranceFund::find_address(&env.mint).0
+}
+
+fn fund_token_address(env: &TestEnv) -> Pubkey {
+    token_address(&fund_address(env), &env.mint, &env.token_program)
+}
+
+fn init_fund_ix(env: &TestEnv, socialize_losses: bool) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::InitializeInsuranceFund {
+            admin: env.admin.pubkey(),
+            authority: env.authority,
+            insurance_fund: fund_address(env),
+            fund_token_account: fund_token_address(env),
+            mint: env.mint,
+            token_program: env.token_program,
+            associated_token_program: spl_associated_token_account::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::InitializeInsuranceFund { socialize_losses }.data(),
+    }
+}
+
+fn socialize_ix(env: &TestEnv, admin: Pubkey, socialize_losses: bool) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::ManageInsuranceFund {
+            admin,
+            authority: env.authority,
+            insurance_fund: fund_address(env),
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::SetSocializeLosses { socialize_losses }.data(),
+    }
+}
+
+/// Seize `amount` of the collateral `caller` locked in the test vault into the fund
+fn settle_to_insurance_ix(env: &TestEnv, caller: Pubkey, amount: u64) -> Instruction {
+    let accounts = vault_manager::accounts::SettleToInsurance {
+        vault: env.vault,
+        vault_token_account: env.vault_token_account,
+        insurance_fund: fund_address(env),
+        fund_token_account: fund_token_address(env),
+        authority: env.authority,
+        counters: counters_address(),
+        yield_index: Some(yield_index_address(&env.mint)),
+        yield_reserve: Some(yield_reserve_address(&env.mint)),
+        caller_program: caller,
+        caller_authority: VaultAuthority::caller_authority_address(&caller),
+        authorized_program: AuthorizedProgram::find_address(&caller).0,
+        program_lock: program_lock_address(&env.vault, &caller),
+        mint: env.mint,
+        token_program: env.token_program,
+    }
+    .to_account_metas(None);
+    via_caller(
+        caller,
+        caller,
+        accounts,
+        vault_manager::instruction::SettleToInsurance { amount }.data(),
+    )
+}
+
+/// Cover `amount` owed to `to_vault`
+fn cover_ix(env: &TestEnv, caller: Pubkey, to_vault: Pubkey, amount: u64) -> Instruction {
+    let accounts = vault_manager::accounts::CoverShortfall {
+        to_vault,
+        to_token_account: token_address(&to_vault, &env.mint, &env.token_program),
+        insurance_fund: fund_address(env),
+        fund_token_account: fund_token_address(env),
+        authority: env.authority,
+        counters: counters_address(),
+        yield_index: yield_index_address(&env.mint),
+        yield_reserve: yield_reserve_address(&env.mint),
+        caller_program: caller,
+        caller_authority: VaultAuthority::caller_authority_address(&caller),
+        authorized_program: AuthorizedProgram::find_address(&caller).0,
+        mint: env.mint,
+        token_program: env.token_program,
+    }
+    .to_account_metas(None);
+    via_caller(
+        caller,
+        caller,
+        accounts,
+        vault_manager::instruction::CoverShortfall { amount }.data(),
+    )
+}
+
+/// Initialize the fund and pay `penalty` into it out of the test vault
+async fn funded_insurance(env: &mut TestEnv, socialize_losses: bool, penalty: u64) {
+    let init = init_fund_ix(env, socialize_losses);
+    let lock = lock_ix(env, TRUSTED_CALLER, 300);
+    send(&mut env.context, &[init, lock], &[]).await.unwrap();
+    if penalty > 0 {
+        let settle = settle_to_insurance_ix(env, TRUSTED_CALLER, penalty);
+        send(&mut env.context, &[settle], &[]).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn liquidation_penalty_funds_insurance() {
+    let mut env = setup(1_000).await;
+    let (fund, fund_token_account) = (fund_address(&env), fund_token_address(&env));
+    funded_insurance(&mut env, false, 100).await;
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.total_balance, 900);
+    assert_eq!(vault.locked_balance, 200);
+    assert_eq!(vault.available_balance, 700);
+    assert_eq!(load_program_lock(&mut env, TRUSTED_CALLER).await.locked_amount, 200);
+
+    let fund: InsuranceFund = load_account(&mut env, fund).await;
+    assert_eq!(fund.total_contributed, 100);
+    assert_eq!(token_balance(&mut env, fund_token_account).await, 100);
+
+    // The fund is outside the vaults
+    let counters: ProtocolCounters = load_account(&mut env, counters_address()).await;
+    assert_eq!(counters.total_value_locked, 900);
+
+    // Only collateral the caller locked can be seized
+    let settle = settle_to_insurance_ix(&env, TRUSTED_CALLER, 300);
+    let result = send(&mut env.context, &[settle], &[]).await;
+    assert_vault_error(result, VaultError::InsufficientLockedBalance);
+}
+
+#[tokio::test]
+async fn fees_are_swept_into_insurance() {
+    let mut env = setup(1_000).await;
+    let (fund, fund_token_account) = (fund_address(&env), fund_token_address(&env));
+    let treasury_token_account = treasury_token_address(&env.mint);
+    let user = env.user.insecure_clone();
+    funded_insurance(&mut env, false, 0).await;
+
+    let fee = Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::ManageAuthority {
+            admin: env.admin.pubkey(),
+            authority: env.authority,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::SetFee { fee_bps: 100 }.data(),
+    };
+    send(&mut env.context, &[fee], &[]).await.unwrap();
+    let withdraw = withdraw_ix(&env, 500);
+    send(&mut env.context, &[withdraw], &[&user]).await.unwrap();
+
+    let collect = Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::CollectFeesToInsurance {
+            admin: env.admin.pubkey(),
+            authority: env.authority,
+            treasury: VaultAuthority::find_treasury_address().0,
+            treasury_token_account: treasury_token_address(&env.mint),
+            insurance_fund: fund_address(&env),
+            fund_token_account: fund_token_address(&env),
+            mint: env.mint,
+            token_program: env.token_program,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::CollectFeesToInsurance {}.data(),
+    };
+    send(&mut env.context, &[collect], &[]).await.unwrap();
+
+    assert_eq!(token_balance(&mut env, treasury_token_account).await, 0);
+    assert_eq!(token_balance(&mut env, fund_token_account).await, 5);
+    let fund: InsuranceFund = load_account(&mut env, fund).await;
+    assert_eq!(fund.total_contributed, 5);
+}
+
+#[tokio::test]
+async fn insurance_settlements_are_rejected_while_paused() {
+    let mut env = setup(1_000).await;
+    funded_insurance(&mut env, false, 100).await;
+    let winner = Keypair::new();
+    let (to_vault, _, _) = create_funded_vault(&mut env, &winner, 1_000).await;
+
+    let pause = pause_ix(&env, env.admin.pubkey(), 1);
+    send(&mut env.context, &[pause], &[]).await.unwrap();
+
+    let settle = settle_to_insurance_ix(&env, TRUSTED_CALLER, 100);
+    let result = send(&mut env.context, &[settle], &[]).await;
+    assert_vault_error(result, VaultError::ProtocolPaused);
+
+    let cover = cover_ix(&env, TRUSTED_CALLER, to_vault, 50);
+    let result = send(&mut env.context, &[cover], &[]).await;
+    assert_vault_error(result, VaultError::ProtocolPaused);
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.locked_balance, 200);
+    let credited: CollateralVault = load_account(&mut env, to_vault).await;
+    assert_eq!(credited.total_balance, 1_000);
+}
+
+#[tokio::test]
+async fn fund_covers_shortfall_up_to_its_balance() {
+    let mut env = setup(1_000).await;
+    let (fund, fund_token_account) = (fund_address(&env), fund_token_address(&env));
+    funded_insurance(&mut env, false, 200).await;
+    let winner = Keypair::new();
+    let (to_vault, _, _) = create_funded_vault(&mut env, &winner, 1_000).await;
+
+    let cover = cover_ix(&env, TRUSTED_CALLER, to_vault, 150);
+    send(&mut env.context, &[cover], &[]).await.unwrap();
+
+    let credited: CollateralVault = load_account(&mut env, to_vault).await;
+    assert_eq!(credited.total_balance, 1_150);
+    assert_eq!(credited.available_balance, 1_150);
+    assert_eq!(token_balance(&mut env, fund_token_account).await, 50);
+
+    // With socialization off, the fund pays what it has and the rest stays uncovered
+    let cover = cover_ix(&env, TRUSTED_CALLER, to_vault, 100);
+    send(&mut env.context, &[cover], &[]).await.unwrap();
+
+    let credited: CollateralVault = load_account(&mut env, to_vault).await;
+    assert_eq!(credited.total_balance, 1_200);
+    assert_eq!(token_balance(&mut env, fund_token_account).await, 0);
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.total_balance, 800);
+
+    let fund: InsuranceFund = load_account(&mut env, fund).await;
+    assert_eq!(fund.total_covered, 200);
+    assert_eq!(fund.total_socialized, 0);
+
+    // Fund payouts enter the vaults: 2_000 deposited, 200 paid in and back out
+    let counters: ProtocolCounters = load_account(&mut env, counters_address()).await;
+    assert_eq!(counters.total_value_locked, 2_000);
+}
+
+/// Point the test environment at `owner`'s vault of the test mint
+fn use_vault(env: &mut TestEnv, owner: &Keypair) {
+    env.user = owner.insecure_clone();
+    env.vault = vault_address(&owner.pubkey(), &env.mint, 0);
+    env.vault_token_account = token_address(&env.vault, &env.mint, &env.token_program);
+    env.user_token_account = token_address(&owner.pubkey(), &env.mint, &env.token_program);
+}
+
+/// Assert that no vault tracks more than its token account holds
+async fn assert_backed(env: &mut TestEnv, vaults: &[Pubkey]) {
+    for &vault in vaults {
+        let state: CollateralVault = load_account(env, vault).await;
+        let tokens = token_balance(env, state.token_account).await;
+        assert!(
+            state.total_balance <= tokens,
+            "vault {vault} tracks {} but holds {tokens}",
+            state.total_balance
+        );
+    }
+}
+
+#[tokio::test]
+async fn socialized_loss_is_shared_by_every_vault_of_the_mint() {
+    // Locked collateral earns yield on this mint, but only available
+    // collateral is haircut
+    let mut env = setup(0).await;
+    let user = env.user.insecure_clone();
+    env.mint = create_mint_with_yield(&mut env.context, true).await;
+    create_funded_vault(&mut env, &user, 1_000).await;
+    use_vault(&mut env, &user);
+    let fund = fund_address(&env);
+    let (yield_index, reserve) = (yield_index_address(&env.mint), yield_reserve_address(&env.mint));
+    funded_insurance(&mut env, false, 0).await;
+    let other = Keypair::new();
+    let (other_vault, _, _) = create_funded_vault(&mut env, &other, 1_000).await;
+    let winner = Keypair::new();
+    let (to_vault, to_token_account, _) = create_funded_vault(&mut env, &winner, 1_000).await;
+    let vaults = [env.vault, other_vault, to_vault];
+
+    let socialize = socialize_ix(&env, env.admin.pubkey(), true);
+    send(&mut env.context, &[socialize], &[]).await.unwrap();
+
+    // The caller picks no vaults: 700 and 1_000 available outside the
+    // credited vault owe 140 and 200 of the 340 shortfall
+    let cover = cover_ix(&env, TRUSTED_CALLER, to_vault, 340);
+    send(&mut env.context, &[cover], &[]).await.unwrap();
+
+    let index: YieldIndex = load_account(&mut env, yield_index).await;
+    assert_eq!(index.loss_index, YieldIndex::SCALE / 5);
+    let credited: CollateralVault = load_account(&mut env, to_vault).await;
+    assert_eq!(credited.total_balance, 1_000);
+    assert_eq!(credited.pending_cover, 340);
+    assert_backed(&mut env, &vaults).await;
+
+    // Each vault pays into the loss reserve the next time it is touched
+    let withdraw = withdraw_ix(&env, 60);
+    send(&mut env.context, &[withdraw], &[&user]).await.unwrap();
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.available_balance, 500);
+    assert_eq!(vault.locked_balance, 300);
+    assert_eq!(vault.total_balance, 800);
+    assert_eq!(token_balance(&mut env, reserve).await, 140);
+    assert_backed(&mut env, &vaults).await;
+
+    // The credited vault is paid only what has been collected so far
+    use_vault(&mut env, &winner);
+    let withdraw = withdraw_ix(&env, 100);
+    send(&mut env.context, &[withdraw], &[&winner]).await.unwrap();
+    let credited: CollateralVault = load_account(&mut env, to_vault).await;
+    assert_eq!(credited.total_balance, 1_040);
+    assert_eq!(credited.pending_cover, 200);
+    assert_eq!(token_balance(&mut env, reserve).await, 0);
+    assert_backed(&mut env, &vaults).await;
+
+    use_vault(&mut env, &other);
+    let withdraw = withdraw_ix(&env, 100);
+    send(&mut env.context, &[withdraw], &[&other]).await.unwrap();
+    assert_eq!(load_vault(&mut env).await.available_balance, 700);
+    assert_eq!(token_balance(&mut env, reserve).await, 200);
+    let index: YieldIndex = load_account(&mut env, yield_index).await;
+    assert_eq!(index.loss_reserve, 200);
+    assert_backed(&mut env, &vaults).await;
+
+    use_vault(&mut env, &winner);
+    let withdraw = withdraw_ix(&env, 100);
+    send(&mut env.context, &[withdraw], &[&winner]).await.unwrap();
+    let credited: CollateralVault = load_account(&mut env, to_vault).await;
+    assert_eq!(credited.total_balance, 1_140);
+    assert_eq!(credited.pending_cover, 0);
+    assert_eq!(token_balance(&mut env, to_token_account).await, 1_140);
+    assert_eq!(token_balance(&mut env, reserve).await, 0);
+    assert_backed(&mut env, &vaults).await;
+
+    let fund: InsuranceFund = load_account(&mut env, fund).await;
+    assert_eq!(fund.total_covered, 0);
+    assert_eq!(fund.total_socialized, 340);
+
+    // Socialized amounts only move between vaults
+    let counters: ProtocolCounters = load_account(&mut env, counters_address()).await;
+    assert_eq!(counters.total_value_locked, 2_640);
+}
+
+#[tokio::test]
+async fn insurance_requires_authorized_callers_and_admin() {
+    let mut env = setup(1_000).await;
+    funded_insurance(&mut env, false, 100).await;
+
+    let cover = cover_ix(&env, ROGUE_CALLER, env.vault, 50);
+    let result = send(&mut env.context, &[cover], &[]).await;
+    assert_vault_error(result, VaultError::UnauthorizedProgram);
+
+    let outsider = Keypair::new();
+    let socialize = socialize_ix(&env, outsider.pubkey(), true);
+    let result = send(&mut env.context, &[socialize], &[&outsider]).await;
+    assert_vault_error(result, VaultError::UnauthorizedAdmin);
+}
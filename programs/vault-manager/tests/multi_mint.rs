@@ -0,0 +1,120 @@
+mod common;
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, signature::Keypair, signature::Signer};
+use vault_manager::{AuthorizedProgram, CollateralVault, VaultAuthority, VaultError};
+
+fn deposit_ix(
+    env: &TestEnv,
+    vault: Pubkey,
+    user_token_account: Pubkey,
+    vault_token_account: Pubkey,
+    amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::Deposit {
+            user: env.user.pubkey(),
+            vault,
+            user_token_account,
+            vault_token_account,
+            authority: env.authority,
+            counters: counters_address(),
+            yield_index: Some(yield_index_address(&env.mint)),
+            owner: env.user.pubkey(),
+            mint: env.mint,
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::Deposit { amount }.data(),
+    }
+}
+
+#[tokio::test]
+async fn owner_has_one_vault_per_mint() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+    let second_mint = create_mint(&mut env.context).await;
+
+    let (vault, vault_token_account, _) =
+        create_funded_vault_for_mint(&mut env, &user, second_mint, 250).await;
+    assert_ne!(vault, env.vault);
+
+    let first = load_vault(&mut env).await;
+    assert_eq!(first.mint, env.mint);
+    assert_eq!(first.total_balance, 1_000);
+
+    let second: CollateralVault = load_account(&mut env, vault).await;
+    assert_eq!(second.owner, user.pubkey());
+    assert_eq!(second.mint, second_mint);
+    assert_eq!(second.token_account, vault_token_account);
+    assert_eq!(second.total_balance, 250);
+}
+
+#[tokio::test]
+async fn deposit_of_another_mint_is_rejected() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+    let second_mint = create_mint(&mut env.context).await;
+    let (_, other_vault_token_account, other_user_token_account) =
+        create_funded_vault_for_mint(&mut env, &user, second_mint, 0).await;
+
+    let deposit = deposit_ix(
+        &env,
+        env.vault,
+        other_user_token_account,
+        other_vault_token_account,
+        100,
+    );
+    let result = send(&mut env.context, &[deposit], &[&user]).await;
+    assert_vault_error(result, VaultError::MintMismatch);
+
+    // Right user token account, but the token account of the other vault
+    let deposit = deposit_ix(
+        &env,
+        env.vault,
+        env.user_token_account,
+        other_vault_token_account,
+        100,
+    );
+    let result = send(&mut env.context, &[deposit], &[&user]).await;
+    assert_vault_error(result, VaultError::InvalidVaultState);
+}
+
+#[tokio::test]
+async fn transfer_between_mints_is_rejected() {
+    let mut env = setup(1_000).await;
+    let counterparty = Keypair::new();
+    let second_mint = create_mint(&mut env.context).await;
+    let (to_vault, to_token_account, _) =
+        create_funded_vault_for_mint(&mut env, &counterparty, second_mint, 0).await;
+
+    let transfer = via_caller(
+        TRUSTED_CALLER,
+        TRUSTED_CALLER,
+        vault_manager::accounts::TransferCollateral {
+            from_vault: env.vault,
+            to_vault,
+            from_token_account: env.vault_token_account,
+            to_token_account,
+            authority: env.authority,
+            counters: counters_address(),
+            treasury_token_account: treasury_token_address(&env.mint),
+            yield_index: Some(yield_index_address(&env.mint)),
+            yield_reserve: Some(yield_reserve_address(&env.mint)),
+            caller_program: TRUSTED_CALLER,
+            caller_authority: VaultAuthority::caller_authority_address(&TRUSTED_CALLER),
+            authorized_program: AuthorizedProgram::find_address(&TRUSTED_CALLER).0,
+            mint: env.mint,
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None),
+        vault_manager::instruction::TransferCollateral { amount: 100 }.data(),
+    );
+    let result = send(&mut env.context, &[transfer], &[]).await;
+    assert_vault_error(result, VaultError::MintMismatch);
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.total_balance, 1_000);
+}
@@ -0,0 +1,599 @@
+mod common;
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use solana_sdk::{
+    instruction::Instruction,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+};
+use vault_manager::{
+    AdminAction, AdminMultisig, AdminProposal, AuthorizedProgram, InsuranceFund, ProgramLock,
+    VaultAuthority, VaultError, VaultStatus, YieldIndex,
+};
+
+const NEW_PROGRAM: Pubkey = Pubkey::new_from_array([11u8; 32]);
+
+fn multisig_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"multisig"], &vault_manager::ID).0
+}
+
+fn proposal_address(index: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"proposal", &index.to_le_bytes()], &vault_manager::ID).0
+}
+
+fn enable_ix(env: &TestEnv, signers: Vec<Pubkey>, threshold: u8) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::EnableMultisig {
+            admin: env.admin.pubkey(),
+            authority: env.authority,
+            multisig: multisig_address(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::EnableMultisig { signers, threshold }.data(),
+    }
+}
+
+fn create_ix(proposer: Pubkey, index: u64, action: AdminAction) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::CreateProposal {
+            proposer,
+            multisig: multisig_address(),
+            proposal: proposal_address(index),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::CreateProposal { action }.data(),
+    }
+}
+
+fn approve_ix(signer: Pubkey, index: u64) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::ApproveProposal {
+            signer,
+            multisig: multisig_address(),
+            proposal: proposal_address(index),
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::ApproveProposal {}.data(),
+    }
+}
+
+/// Accounts of `execute_proposal` without any of the optional ones
+fn execute_accounts(
+    env: &TestEnv,
+    executor: Pubkey,
+    proposer: Pubkey,
+    index: u64,
+) -> vault_manager::accounts::ExecuteProposal {
+    vault_manager::accounts::ExecuteProposal {
+        executor,
+        multisig: multisig_address(),
+        authority: env.authority,
+        proposal: proposal_address(index),
+        proposer,
+        vault: None,
+        authorized_program: None,
+        program_lock: None,
+        treasury: None,
+        treasury_token_account: None,
+        fee_destination: None,
+        yield_index: None,
+        insurance_fund: None,
+        reserve_token_account: None,
+        counters: None,
+        mint: None,
+        token_program: None,
+        associated_token_program: None,
+        system_program: system_program::ID,
+    }
+}
+
+fn execute_ix(
+    env: &TestEnv,
+    executor: Pubkey,
+    proposer: Pubkey,
+    index: u64,
+    vault: Option<Pubkey>,
+) -> Instruction {
+    execute_with(vault_manager::accounts::ExecuteProposal {
+        vault,
+        authorized_program: Some(AuthorizedProgram::find_address(&NEW_PROGRAM).0),
+        ..execute_accounts(env, executor, proposer, index)
+    })
+}
+
+fn execute_with(accounts: vault_manager::accounts::ExecuteProposal) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: accounts.to_account_metas(None),
+        data: vault_manager::instruction::ExecuteProposal {}.data(),
+    }
+}
+
+/// Execute an `AdminAction::DistributeYield` of the test mint
+fn execute_distribute_ix(
+    env: &TestEnv,
+    executor: Pubkey,
+    proposer: Pubkey,
+    index: u64,
+    yield_index: Option<Pubkey>,
+) -> Instruction {
+    execute_with(vault_manager::accounts::ExecuteProposal {
+        treasury: Some(VaultAuthority::find_treasury_address().0),
+        treasury_token_account: Some(treasury_token_address(&env.mint)),
+        fee_destination: Some(yield_reserve_address(&env.mint)),
+        yield_index,
+        counters: Some(counters_address()),
+        mint: Some(env.mint),
+        token_program: Some(spl_token::ID),
+        ..execute_accounts(env, executor, proposer, index)
+    })
+}
+
+/// Hand the admin role to a fresh 2-of-3 multisig and fund its signers
+async fn setup_multisig(env: &mut TestEnv) -> Vec<Keypair> {
+    let signers: Vec<Keypair> = (0..3).map(|_| Keypair::new()).collect();
+    let keys = signers.iter().map(|s| s.pubkey()).collect();
+
+    let mut ixs = vec![enable_ix(env, keys, 2)];
+    for signer in &signers {
+        ixs.push(solana_sdk::system_instruction::transfer(
+            &env.admin.pubkey(),
+            &signer.pubkey(),
+            1_000_000_000,
+        ));
+    }
+    send(&mut env.context, &ixs, &[]).await.unwrap();
+    signers
+}
+
+#[tokio::test]
+async fn enable_multisig_hands_over_admin_role() {
+    let mut env = setup(0).await;
+    let signers = setup_multisig(&mut env).await;
+
+    let authority = load_authority(&mut env).await;
+    assert_eq!(authority.admin, multisig_address());
+
+    let multisig: AdminMultisig = load_account(&mut env, multisig_address()).await;
+    assert_eq!(multisig.threshold, 2);
+    assert_eq!(multisig.signers.len(), 3);
+    assert!(multisig.is_signer(&signers[0].pubkey()));
+
+    // The former single admin can no longer act alone
+    let add = add_program_ix(env.admin.pubkey(), NEW_PROGRAM, "new", 0);
+    let result = send(&mut env.context, &[add], &[]).await;
+    assert_vault_error(result, VaultError::UnauthorizedAdmin);
+}
+
+#[tokio::test]
+async fn proposal_executes_only_after_threshold() {
+    let mut env = setup(0).await;
+    let signers = setup_multisig(&mut env).await;
+    let (alice, bob) = (&signers[0], &signers[1]);
+
+    let action = AdminAction::AddAuthorizedProgram {
+        program_id: NEW_PROGRAM,
+        label: "new".to_string(),
+        max_lock: 0,
+    };
+    let create = create_ix(alice.pubkey(), 0, action);
+    send(&mut env.context, &[create], &[alice]).await.unwrap();
+
+    // One approval (the proposer's) is below the 2-of-3 threshold
+    let execute = execute_ix(&env, alice.pubkey(), alice.pubkey(), 0, None);
+    let result = send(&mut env.context, &[execute], &[alice]).await;
+    assert_vault_error(result, VaultError::ProposalThresholdNotMet);
+
+    let approve = approve_ix(bob.pubkey(), 0);
+    send(&mut env.context, &[approve], &[bob]).await.unwrap();
+
+    let proposal: AdminProposal = load_account(&mut env, proposal_address(0)).await;
+    assert_eq!(proposal.approvals, vec![alice.pubkey(), bob.pubkey()]);
+
+    let execute = execute_ix(&env, bob.pubkey(), alice.pubkey(), 0, None);
+    send(&mut env.context, &[execute], &[bob]).await.unwrap();
+
+    let record: AuthorizedProgram =
+        load_account(&mut env, AuthorizedProgram::find_address(&NEW_PROGRAM).0).await;
+    assert!(record.enabled);
+
+    // Executed proposals are closed
+    let closed = env
+        .context
+        .banks_client
+        .get_account(proposal_address(0))
+        .await
+        .unwrap();
+    assert!(closed.is_none());
+}
+
+#[tokio::test]
+async fn outsiders_and_double_approvals_are_rejected() {
+    let mut env = setup(0).await;
+    let signers = setup_multisig(&mut env).await;
+    let alice = &signers[0];
+    let outsider = env.user.insecure_clone();
+
+    let create = create_ix(outsider.pubkey(), 0, AdminAction::Pause { reason: 1 });
+    let result = send(&mut env.context, &[create], &[&outsider]).await;
+    assert_vault_error(result, VaultError::NotMultisigSigner);
+
+    let create = create_ix(alice.pubkey(), 0, AdminAction::Pause { reason: 1 });
+    send(&mut env.context, &[create], &[alice]).await.unwrap();
+
+    let approve = approve_ix(alice.pubkey(), 0);
+    let result = send(&mut env.context, &[approve], &[alice]).await;
+    assert_vault_error(result, VaultError::ProposalAlreadyApproved);
+
+    let approve = approve_ix(outsider.pubkey(), 0);
+    let result = send(&mut env.context, &[approve], &[&outsider]).await;
+    assert_vault_error(result, VaultError::NotMultisigSigner);
+}
+
+#[tokio::test]
+async fn overlong_program_label_is_rejected_at_creation() {
+    let mut env = setup(0).await;
+    let signers = setup_multisig(&mut env).await;
+    let alice = &signers[0];
+
+    let action = AdminAction::AddAuthorizedProgram {
+        program_id: NEW_PROGRAM,
+        label: "x".repeat(AuthorizedProgram::MAX_LABEL_LEN + 1),
+        max_lock: 0,
+    };
+    let create = create_ix(alice.pubkey(), 0, action);
+    let result = send(&mut env.context, &[create], &[alice]).await;
+    assert_vault_error(result, VaultError::ProgramLabelTooLong);
+
+    let action = AdminAction::AddAuthorizedProgram {
+        program_id: NEW_PROGRAM,
+        label: "x".repeat(AuthorizedProgram::MAX_LABEL_LEN),
+        max_lock: 0,
+    };
+    let create = create_ix(alice.pubkey(), 0, action);
+    send(&mut env.context, &[create], &[alice]).await.unwrap();
+}
+
+#[tokio::test]
+async fn multisig_can_freeze_a_vault() {
+    let mut env = setup(1_000).await;
+    let signers = setup_multisig(&mut env).await;
+    let (alice, bob) = (&signers[0], &signers[1]);
+
+    let action = AdminAction::SetVaultFrozen {
+        vault: env.vault,
+        frozen: true,
+    };
+    let create = create_ix(alice.pubkey(), 0, action);
+    let approve = approve_ix(bob.pubkey(), 0);
+    send(&mut env.context, &[create, approve], &[alice, bob]).await.unwrap();
+
+    // The target vault must be passed in
+    let execute = execute_ix(&env, alice.pubkey(), alice.pubkey(), 0, None);
+    let result = send(&mut env.context, &[execute], &[alice]).await;
+    assert_vault_error(result, VaultError::InvalidVaultState);
+
+    let execute = execute_ix(&env, alice.pubkey(), alice.pubkey(), 0, Some(env.vault));
+    send(&mut env.context, &[execute], &[alice]).await.unwrap();
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.status, VaultStatus::Frozen);
+}
+
+#[tokio::test]
+async fn updating_signers_invalidates_open_proposals() {
+    let mut env = setup(0).await;
+    let signers = setup_multisig(&mut env).await;
+    let (alice, bob, carol) = (&signers[0], &signers[1], &signers[2]);
+
+    // Proposal 0 is left open while proposal 1 replaces the signer set
+    let create = create_ix(alice.pubkey(), 0, AdminAction::Pause { reason: 1 });
+    send(&mut env.context, &[create], &[alice]).await.unwrap();
+
+    let action = AdminAction::UpdateSigners {
+        signers: vec![alice.pubkey(), carol.pubkey()],
+        threshold: 2,
+    };
+    let create = create_ix(alice.pubkey(), 1, action);
+    let approve = approve_ix(carol.pubkey(), 1);
+    let execute = execute_ix(&env, alice.pubkey(), alice.pubkey(), 1, None);
+    send(&mut env.context, &[create, approve, execute], &[alice, carol])
+        .await
+        .unwrap();
+
+    let multisig: AdminMultisig = load_account(&mut env, multisig_address()).await;
+    assert!(!multisig.is_signer(&bob.pubkey()));
+
+    let approve = approve_ix(carol.pubkey(), 0);
+    let result = send(&mut env.context, &[approve], &[carol]).await;
+    assert_vault_error(result, VaultError::StaleProposal);
+
+    // Invalid signer sets are rejected up front
+    let action = AdminAction::UpdateSigners {
+        signers: vec![alice.pubkey(), alice.pubkey()],
+        threshold: 1,
+    };
+    let create = create_ix(alice.pubkey(), 2, action);
+    let result = send(&mut env.context, &[create], &[alice]).await;
+    assert_vault_error(result, VaultError::InvalidMultisigConfig);
+}
+
+#[tokio::test]
+async fn multisig_can_hand_admin_back_to_a_single_key() {
+    let mut env = setup(0).await;
+    let signers = setup_multisig(&mut env).await;
+    let (alice, bob) = (&signers[0], &signers[1]);
+    let new_admin = Keypair::new();
+
+    let action = AdminAction::ProposeAdmin {
+        new_admin: new_admin.pubkey(),
+    };
+    let create = create_ix(alice.pubkey(), 0, action);
+    let approve = approve_ix(bob.pubkey(), 0);
+    let execute = execute_ix(&env, bob.pubkey(), alice.pubkey(), 0, None);
+    send(&mut env.context, &[create, approve, execute], &[alice, bob])
+        .await
+        .unwrap();
+
+    let accept = Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::AcceptAdmin {
+            pending_admin: new_admin.pubkey(),
+            authority: env.authority,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::AcceptAdmin {}.data(),
+    };
+    send(&mut env.context, &[accept], &[&new_admin]).await.unwrap();
+
+    let authority = load_authority(&mut env).await;
+    assert_eq!(authority.admin, new_admin.pubkey());
+
+    // The multisig no longer holds the admin role
+    let create = create_ix(alice.pubkey(), 1, AdminAction::Unpause);
+    let approve = approve_ix(bob.pubkey(), 1);
+    send(&mut env.context, &[create, approve], &[alice, bob]).await.unwrap();
+    let execute = execute_ix(&env, alice.pubkey(), alice.pubkey(), 1, None);
+    let result = send(&mut env.context, &[execute], &[alice]).await;
+    assert_vault_error(result, VaultError::UnauthorizedAdmin);
+}
+
+#[tokio::test]
+async fn multisig_can_initialize_a_yield_index() {
+    let mut env = setup(0).await;
+    let signers = setup_multisig(&mut env).await;
+    let (alice, bob) = (&signers[0], &signers[1]);
+
+    // A mint the protocol has not seen yet
+    let mint = Keypair::new();
+    let rent = env.context.banks_client.get_rent().await.unwrap();
+    let ixs = [
+        solana_sdk::system_instruction::create_account(
+            &env.admin.pubkey(),
+            &mint.pubkey(),
+            rent.minimum_balance(spl_token::state::Mint::LEN),
+            spl_token::state::Mint::LEN as u64,
+            &spl_token::ID,
+        ),
+        spl_token::instruction::initialize_mint(
+            &spl_token::ID,
+            &mint.pubkey(),
+            &env.admin.pubkey(),
+            None,
+            6,
+        )
+        .unwrap(),
+    ];
+    send(&mut env.context, &ixs, &[&mint]).await.unwrap();
+    let mint = mint.pubkey();
+    let reserve = yield_reserve_address(&mint);
+
+    let action = AdminAction::InitializeYieldIndex {
+        mint,
+        include_locked: true,
+    };
+    let create = create_ix(alice.pubkey(), 0, action);
+    let approve = approve_ix(bob.pubkey(), 0);
+    let execute = execute_with(vault_manager::accounts::ExecuteProposal {
+        yield_index: Some(yield_index_address(&mint)),
+        reserve_token_account: Some(reserve),
+        mint: Some(mint),
+        token_program: Some(spl_token::ID),
+        associated_token_program: Some(spl_associated_token_account::ID),
+        ..execute_accounts(&env, bob.pubkey(), alice.pubkey(), 0)
+    });
+    send(&mut env.context, &[create, approve, execute], &[alice, bob])
+        .await
+        .unwrap();
+
+    let yield_index: YieldIndex = load_account(&mut env, yield_index_address(&mint)).await;
+    assert_eq!(yield_index.mint, mint);
+    assert_eq!(yield_index.index, YieldIndex::SCALE);
+    assert_eq!(yield_index.total_shares, 0);
+    assert!(yield_index.include_locked);
+    assert_eq!(yield_index.reserve, reserve);
+    assert_eq!(token_balance(&mut env, reserve).await, 0);
+}
+
+#[tokio::test]
+async fn multisig_can_distribute_treasury_tokens_as_yield() {
+    let mut env = setup(1_000).await;
+    let signers = setup_multisig(&mut env).await;
+    let (alice, bob) = (&signers[0], &signers[1]);
+    let (mint, treasury_token_account) = (env.mint, treasury_token_address(&env.mint));
+    let reserve = yield_reserve_address(&mint);
+
+    let fund = spl_token::instruction::mint_to(
+        &spl_token::ID,
+        &mint,
+        &treasury_token_account,
+        &env.admin.pubkey(),
+        &[],
+        100,
+    )
+    .unwrap();
+    send(&mut env.context, &[fund], &[]).await.unwrap();
+
+    let action = AdminAction::DistributeYield { mint, amount: 100 };
+    let create = create_ix(alice.pubkey(), 0, action);
+    let approve = approve_ix(bob.pubkey(), 0);
+    send(&mut env.context, &[create, approve], &[alice, bob])
+        .await
+        .unwrap();
+
+    // The yield index must be passed in
+    let execute = execute_distribute_ix(&env, bob.pubkey(), alice.pubkey(), 0, None);
+    let result = send(&mut env.context, &[execute], &[bob]).await;
+    assert_vault_error(result, VaultError::InvalidVaultState);
+
+    let yield_index = Some(yield_index_address(&mint));
+    let execute = execute_distribute_ix(&env, bob.pubkey(), alice.pubkey(), 0, yield_index);
+    send(&mut env.context, &[execute], &[bob]).await.unwrap();
+
+    let yield_index: YieldIndex = load_account(&mut env, yield_index_address(&mint)).await;
+    assert_eq!(yield_index.index, YieldIndex::SCALE / 10 * 11);
+    assert_eq!(yield_index.total_distributed, 100);
+    assert_eq!(token_balance(&mut env, treasury_token_account).await, 0);
+    assert_eq!(token_balance(&mut env, reserve).await, 100);
+}
+
+#[tokio::test]
+async fn multisig_can_run_the_insurance_fund() {
+    let mut env = setup(0).await;
+    let signers = setup_multisig(&mut env).await;
+    let (alice, bob) = (&signers[0], &signers[1]);
+    let (mint, treasury_token_account) = (env.mint, treasury_token_address(&env.mint));
+    let fund = InsuranceFund::find_address(&mint).0;
+    let fund_token_account = token_address(&fund, &mint, &spl_token::ID);
+
+    let action = AdminAction::InitializeInsuranceFund {
+        mint,
+        socialize_losses: false,
+    };
+    let create = create_ix(alice.pubkey(), 0, action);
+    let approve = approve_ix(bob.pubkey(), 0);
+    let execute = execute_with(vault_manager::accounts::ExecuteProposal {
+        insurance_fund: Some(fund),
+        reserve_token_account: Some(fund_token_account),
+        mint: Some(mint),
+        token_program: Some(spl_token::ID),
+        associated_token_program: Some(spl_associated_token_account::ID),
+        ..execute_accounts(&env, bob.pubkey(), alice.pubkey(), 0)
+    });
+    send(&mut env.context, &[create, approve, execute], &[alice, bob])
+        .await
+        .unwrap();
+
+    let record: InsuranceFund = load_account(&mut env, fund).await;
+    assert_eq!(record.mint, mint);
+    assert_eq!(record.token_account, fund_token_account);
+    assert!(!record.socialize_losses);
+
+    let action = AdminAction::SetSocializeLosses {
+        mint,
+        socialize_losses: true,
+    };
+    let create = create_ix(alice.pubkey(), 1, action);
+    let approve = approve_ix(bob.pubkey(), 1);
+    let execute = execute_with(vault_manager::accounts::ExecuteProposal {
+        insurance_fund: Some(fund),
+        ..execute_accounts(&env, bob.pubkey(), alice.pubkey(), 1)
+    });
+    send(&mut env.context, &[create, approve, execute], &[alice, bob])
+        .await
+        .unwrap();
+
+    let record: InsuranceFund = load_account(&mut env, fund).await;
+    assert!(record.socialize_losses);
+
+    let fees = spl_token::instruction::mint_to(
+        &spl_token::ID,
+        &mint,
+        &treasury_token_account,
+        &env.admin.pubkey(),
+        &[],
+        50,
+    )
+    .unwrap();
+    send(&mut env.context, &[fees], &[]).await.unwrap();
+
+    let action = AdminAction::CollectFeesToInsurance {
+        treasury_token_account,
+    };
+    let create = create_ix(alice.pubkey(), 2, action);
+    let approve = approve_ix(bob.pubkey(), 2);
+    let execute = execute_with(vault_manager::accounts::ExecuteProposal {
+        treasury: Some(VaultAuthority::find_treasury_address().0),
+        treasury_token_account: Some(treasury_token_account),
+        fee_destination: Some(fund_token_account),
+        insurance_fund: Some(fund),
+        mint: Some(mint),
+        token_program: Some(spl_token::ID),
+        ..execute_accounts(&env, bob.pubkey(), alice.pubkey(), 2)
+    });
+    send(&mut env.context, &[create, approve, execute], &[alice, bob])
+        .await
+        .unwrap();
+
+    let record: InsuranceFund = load_account(&mut env, fund).await;
+    assert_eq!(record.total_contributed, 50);
+    assert_eq!(token_balance(&mut env, fund_token_account).await, 50);
+    assert_eq!(token_balance(&mut env, treasury_token_account).await, 0);
+}
+
+#[tokio::test]
+async fn multisig_can_force_unlock_a_disabled_program() {
+    let mut env = setup(1_000).await;
+    let lock = lock_ix(&env, TRUSTED_CALLER, 400);
+    send(&mut env.context, &[lock], &[]).await.unwrap();
+    let signers = setup_multisig(&mut env).await;
+    let (alice, bob) = (&signers[0], &signers[1]);
+    let record = AuthorizedProgram::find_address(&TRUSTED_CALLER).0;
+    let program_lock = program_lock_address(&env.vault, &TRUSTED_CALLER);
+
+    let action = AdminAction::RemoveAuthorizedProgram {
+        program_id: TRUSTED_CALLER,
+    };
+    let create = create_ix(alice.pubkey(), 0, action);
+    let approve = approve_ix(bob.pubkey(), 0);
+    let execute = execute_with(vault_manager::accounts::ExecuteProposal {
+        authorized_program: Some(record),
+        ..execute_accounts(&env, bob.pubkey(), alice.pubkey(), 0)
+    });
+    send(&mut env.context, &[create, approve, execute], &[alice, bob])
+        .await
+        .unwrap();
+
+    let action = AdminAction::ForceUnlock {
+        vault: env.vault,
+        program: TRUSTED_CALLER,
+        amount: 400,
+    };
+    let create = create_ix(alice.pubkey(), 1, action);
+    let approve = approve_ix(bob.pubkey(), 1);
+    let execute = execute_with(vault_manager::accounts::ExecuteProposal {
+        vault: Some(env.vault),
+        authorized_program: Some(record),
+        program_lock: Some(program_lock),
+        yield_index: Some(yield_index_address(&env.mint)),
+        ..execute_accounts(&env, bob.pubkey(), alice.pubkey(), 1)
+    });
+    send(&mut env.context, &[create, approve, execute], &[alice, bob])
+        .await
+        .unwrap();
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.locked_balance, 0);
+    assert_eq!(vault.available_balance, 1_000);
+
+    let program_lock: ProgramLock = load_account(&mut env, program_lock).await;
+    assert_eq!(program_lock.locked_amount, 0);
+}
@@ -0,0 +1,155 @@
+mod common;
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use vault_manager::{AuthorizedProgram, VaultAuthority, VaultError, VaultStatus};
+
+fn unpause_ix(env: &TestEnv) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::ManageAuthority {
+            admin: env.admin.pubkey(),
+            authority: env.authority,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::Unpause {}.data(),
+    }
+}
+
+fn freeze_ix(env: &TestEnv, vault: Pubkey, frozen: bool) -> Instruction {
+    let accounts = vault_manager::accounts::FreezeVault {
+        admin: env.admin.pubkey(),
+        authority: env.authority,
+        vault,
+    }
+    .to_account_metas(None);
+    let data = if frozen {
+        vault_manager::instruction::FreezeVault {}.data()
+    } else {
+        vault_manager::instruction::UnfreezeVault {}.data()
+    };
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts,
+        data,
+    }
+}
+
+#[tokio::test]
+async fn pause_blocks_vault_operations_until_unpaused() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+
+    let lock = lock_ix(&env, TRUSTED_CALLER, 300);
+    send(&mut env.context, &[lock], &[]).await.unwrap();
+
+    let pause = pause_ix(&env, env.admin.pubkey(), 3);
+    send(&mut env.context, &[pause], &[]).await.unwrap();
+
+    let authority = load_authority(&mut env).await;
+    assert!(authority.paused);
+    assert_eq!(authority.pause_reason, 3);
+
+    let withdraw = withdraw_ix(&env, 100);
+    let result = send(&mut env.context, &[withdraw], &[&user]).await;
+    assert_vault_error(result, VaultError::ProtocolPaused);
+
+    let lock = lock_ix(&env, TRUSTED_CALLER, 100);
+    let result = send(&mut env.context, &[lock], &[]).await;
+    assert_vault_error(result, VaultError::ProtocolPaused);
+
+    // Releasing margin stays possible while paused
+    let unlock = unlock_ix(&env, TRUSTED_CALLER, 300);
+    send(&mut env.context, &[unlock], &[]).await.unwrap();
+
+    let unpause = unpause_ix(&env);
+    send(&mut env.context, &[unpause], &[]).await.unwrap();
+
+    let withdraw = withdraw_ix(&env, 100);
+    send(&mut env.context, &[withdraw], &[&user]).await.unwrap();
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.total_balance, 900);
+    assert_eq!(vault.locked_balance, 0);
+}
+
+#[tokio::test]
+async fn only_admin_can_pause() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+
+    let pause = pause_ix(&env, user.pubkey(), 1);
+    let result = send(&mut env.context, &[pause], &[&user]).await;
+    assert_vault_error(result, VaultError::UnauthorizedAdmin);
+
+    let authority = load_authority(&mut env).await;
+    assert!(!authority.paused);
+}
+
+#[tokio::test]
+async fn frozen_vault_rejects_debits_but_accepts_credits() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+    let other = Keypair::new();
+    let (other_vault, _, _) = create_funded_vault(&mut env, &other, 500).await;
+
+    // Leave the user tokens to deposit and a lock to release once frozen
+    let withdraw = withdraw_ix(&env, 100);
+    send(&mut env.context, &[withdraw], &[&user]).await.unwrap();
+    let lock = lock_ix(&env, TRUSTED_CALLER, 300);
+    send(&mut env.context, &[lock], &[]).await.unwrap();
+
+    let freeze = freeze_ix(&env, env.vault, true);
+    send(&mut env.context, &[freeze], &[]).await.unwrap();
+    assert_eq!(load_vault(&mut env).await.status, VaultStatus::Frozen);
+
+    // Collateral can still come in and locks can still be released
+    let deposit = deposit_ix(&env, 50);
+    send(&mut env.context, &[deposit], &[&user]).await.unwrap();
+    let unlock = unlock_ix(&env, TRUSTED_CALLER, 300);
+    send(&mut env.context, &[unlock], &[]).await.unwrap();
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.total_balance, 950);
+    assert_eq!(vault.locked_balance, 0);
+
+    let withdraw = withdraw_ix(&env, 100);
+    let result = send(&mut env.context, &[withdraw], &[&user]).await;
+    assert_vault_error(result, VaultError::VaultFrozen);
+
+    let lock = lock_ix(&env, TRUSTED_CALLER, 100);
+    let result = send(&mut env.context, &[lock], &[]).await;
+    assert_vault_error(result, VaultError::VaultFrozen);
+
+    // Another user's vault is unaffected
+    let lock_other = via_caller(
+        TRUSTED_CALLER,
+        TRUSTED_CALLER,
+        vault_manager::accounts::LockCollateral {
+            vault: other_vault,
+            authority: env.authority,
+            yield_index: Some(yield_index_address(&env.mint)),
+            caller_program: TRUSTED_CALLER,
+            caller_authority: VaultAuthority::caller_authority_address(&TRUSTED_CALLER),
+            authorized_program: AuthorizedProgram::find_address(&TRUSTED_CALLER).0,
+            program_lock: program_lock_address(&other_vault, &TRUSTED_CALLER),
+            payer: env.context.payer.pubkey(),
+            system_program: solana_sdk::system_program::ID,
+        }
+        .to_account_metas(None),
+        vault_manager::instruction::LockCollateral { amount: 100 }.data(),
+    );
+    send(&mut env.context, &[lock_other], &[]).await.unwrap();
+
+    let unfreeze = freeze_ix(&env, env.vault, false);
+    send(&mut env.context, &[unfreeze], &[]).await.unwrap();
+    assert_eq!(load_vault(&mut env).await.status, VaultStatus::Active);
+
+    let withdraw = withdraw_ix(&env, 100);
+    send(&mut env.context, &[withdraw], &[&user]).await.unwrap();
+}
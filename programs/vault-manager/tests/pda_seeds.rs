@@ -0,0 +1,122 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use common::*;
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::InstructionError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::Signer,
+    transaction::TransactionError,
+};
+use vault_manager::{
+    AuthorizedProgram, CollateralVault, ProgramLock, ProtocolCounters, VaultAuthority, YieldIndex,
+};
+
+fn pda(seeds: &[&[u8]]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, &vault_manager::ID)
+}
+
+/// Owner recorded in an SPL token account
+async fn token_owner(env: &mut TestEnv, token_account: Pubkey) -> Pubkey {
+    let account = env
+        .context
+        .banks_client
+        .get_account(token_account)
+        .await
+        .unwrap()
+        .unwrap();
+    spl_token::state::Account::unpack(&account.data).unwrap().owner
+}
+
+#[tokio::test]
+async fn accounts_live_at_their_seeds() {
+    let mut env = setup(1_000).await;
+    let lock = lock_ix(&env, TRUSTED_CALLER, 300);
+    send(&mut env.context, &[lock], &[]).await.unwrap();
+
+    let (authority, bump) = pda(&[b"authority"]);
+    assert_eq!(authority, env.authority);
+    assert_eq!(load_authority(&mut env).await.bump, bump);
+
+    let (counters, bump) = pda(&[b"protocol_counters"]);
+    let loaded: ProtocolCounters = load_account(&mut env, counters).await;
+    assert_eq!(loaded.bump, bump);
+
+    // Vaults are keyed by owner, mint and little-endian sub-account index
+    let owner = env.user.pubkey();
+    let (vault, bump) = pda(&[b"vault", owner.as_ref(), env.mint.as_ref(), &0u16.to_le_bytes()]);
+    assert_eq!(vault, env.vault);
+    let loaded: CollateralVault = load_vault(&mut env).await;
+    assert_eq!(loaded.bump, bump);
+    assert_eq!(loaded.owner, owner);
+    assert_eq!(loaded.mint, env.mint);
+    assert_eq!(loaded.sub_account, 0);
+
+    // Collateral sits in the vault PDA's associated token account
+    let vault_token_account = token_address(&vault, &env.mint, &env.token_program);
+    assert_eq!(loaded.token_account, vault_token_account);
+    assert_eq!(vault_token_account, env.vault_token_account);
+    assert_eq!(token_owner(&mut env, vault_token_account).await, vault);
+
+    let (yield_index, bump) = pda(&[b"yield_index", env.mint.as_ref()]);
+    let loaded: YieldIndex = load_account(&mut env, yield_index).await;
+    assert_eq!(loaded.bump, bump);
+    assert_eq!(loaded.reserve, token_address(&yield_index, &env.mint, &env.token_program));
+    assert_eq!(token_owner(&mut env, loaded.reserve).await, yield_index);
+
+    let (treasury, _) = pda(&[b"treasury"]);
+    let treasury_token_account = treasury_token_address(&env.mint);
+    assert_eq!(token_owner(&mut env, treasury_token_account).await, treasury);
+
+    let (authorization, bump) = pda(&[b"authorized_program", TRUSTED_CALLER.as_ref()]);
+    let loaded: AuthorizedProgram = load_account(&mut env, authorization).await;
+    assert_eq!(loaded.bump, bump);
+    assert_eq!(loaded.program_id, TRUSTED_CALLER);
+
+    let (program_lock, bump) = pda(&[b"program_lock", vault.as_ref(), TRUSTED_CALLER.as_ref()]);
+    let loaded: ProgramLock = load_account(&mut env, program_lock).await;
+    assert_eq!(loaded.bump, bump);
+    assert_eq!(loaded.vault, vault);
+    assert_eq!(loaded.program, TRUSTED_CALLER);
+    assert_eq!(loaded.locked_amount, 300);
+
+    // Callers sign with a PDA of their own program
+    let (caller_authority, _) = Pubkey::find_program_address(&[b"vault_caller"], &TRUSTED_CALLER);
+    assert_eq!(caller_authority, VaultAuthority::caller_authority_address(&TRUSTED_CALLER));
+}
+
+#[tokio::test]
+async fn vault_outside_its_seeds_is_rejected() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+
+    // A byte-for-byte copy of the vault at an address that is not its PDA
+    let account = env
+        .context
+        .banks_client
+        .get_account(env.vault)
+        .await
+        .unwrap()
+        .unwrap();
+    let copy = Pubkey::new_unique();
+    env.context.set_account(&copy, &AccountSharedData::from(account));
+
+    let mut deposit = deposit_ix(&env, 100);
+    for meta in deposit.accounts.iter_mut() {
+        if meta.pubkey == env.vault {
+            meta.pubkey = copy;
+        }
+    }
+    let result = send(&mut env.context, &[deposit], &[&user]).await;
+    match result.unwrap_err().unwrap() {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => {
+            assert_eq!(code, ErrorCode::ConstraintSeeds as u32);
+        }
+        other => panic!("unexpected error: {:?}", other),
+    }
+
+    let vault_token_account = env.vault_token_account;
+    assert_eq!(token_balance(&mut env, vault_token_account).await, 1_000);
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use common::*;
+use vault_manager::VaultError;
+
+#[tokio::test]
+async fn locks_are_tracked_per_program() {
+    let mut env = setup(1_000).await;
+
+    let lock_a = lock_ix(&env, TRUSTED_CALLER, 300);
+    let lock_b = lock_ix(&env, SECOND_CALLER, 200);
+    send(&mut env.context, &[lock_a, lock_b], &[]).await.unwrap();
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.locked_balance, 500);
+    assert_eq!(vault.available_balance, 500);
+
+    let lock_a = load_program_lock(&mut env, TRUSTED_CALLER).await;
+    assert_eq!(lock_a.vault, env.vault);
+    assert_eq!(lock_a.program, TRUSTED_CALLER);
+    assert_eq!(lock_a.locked_amount, 300);
+
+    let lock_b = load_program_lock(&mut env, SECOND_CALLER).await;
+    assert_eq!(lock_b.locked_amount, 200);
+}
+
+#[tokio::test]
+async fn program_cannot_unlock_collateral_locked_by_another_program() {
+    let mut env = setup(1_000).await;
+
+    let lock_a = lock_ix(&env, TRUSTED_CALLER, 300);
+    let lock_b = lock_ix(&env, SECOND_CALLER, 100);
+    send(&mut env.context, &[lock_a, lock_b], &[]).await.unwrap();
+
+    // Vault-wide locked balance is 400, but the second program only owns 100
+    let unlock = unlock_ix(&env, SECOND_CALLER, 300);
+    let result = send(&mut env.context, &[unlock], &[]).await;
+    assert_vault_error(result, VaultError::InvalidUnlockAmount);
+
+    let unlock = unlock_ix(&env, SECOND_CALLER, 100);
+    send(&mut env.context, &[unlock], &[]).await.unwrap();
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.locked_balance, 300);
+    assert_eq!(vault.available_balance, 700);
+
+    let lock_a = load_program_lock(&mut env, TRUSTED_CALLER).await;
+    assert_eq!(lock_a.locked_amount, 300);
+}
+
+#[tokio::test]
+async fn unlock_without_prior_lock_is_rejected() {
+    let mut env = setup(1_000).await;
+
+    let lock = lock_ix(&env, TRUSTED_CALLER, 300);
+    send(&mut env.context, &[lock], &[]).await.unwrap();
+
+    // The second program never locked, so its ledger account does not exist
+    let unlock = unlock_ix(&env, SECOND_CALLER, 100);
+    assert!(send(&mut env.context, &[unlock], &[]).await.is_err());
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.locked_balance, 300);
+}
@@ -0,0 +1,180 @@
+mod common;
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use vault_manager::{AuthorizedProgram, ProtocolCounters, VaultAuthority, VaultError};
+
+fn fee_ix(env: &TestEnv, admin: Pubkey, fee_bps: u16) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::ManageAuthority {
+            admin,
+            authority: env.authority,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::SetFee { fee_bps }.data(),
+    }
+}
+
+fn collect_ix(env: &TestEnv, destination: Pubkey) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::CollectFees {
+            admin: env.admin.pubkey(),
+            authority: env.authority,
+            treasury: VaultAuthority::find_treasury_address().0,
+            treasury_token_account: treasury_token_address(&env.mint),
+            destination,
+            mint: env.mint,
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::CollectFees {}.data(),
+    }
+}
+
+/// Create the admin's token account of the test mint to sweep fees into
+async fn admin_token_account(env: &mut TestEnv) -> Pubkey {
+    let admin = env.admin.pubkey();
+    let create = spl_associated_token_account::instruction::create_associated_token_account(
+        &admin,
+        &admin,
+        &env.mint,
+        &spl_token::ID,
+    );
+    send(&mut env.context, &[create], &[]).await.unwrap();
+    spl_associated_token_account::get_associated_token_address(&admin, &env.mint)
+}
+
+#[tokio::test]
+async fn withdrawal_pays_fee_to_treasury() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+    let fee = fee_ix(&env, env.admin.pubkey(), 100);
+    send(&mut env.context, &[fee], &[]).await.unwrap();
+
+    let withdraw = withdraw_ix(&env, 500);
+    send(&mut env.context, &[withdraw], &[&user]).await.unwrap();
+
+    // The vault is debited the full amount, 1% of which goes to the treasury
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.total_balance, 500);
+    assert_eq!(vault.total_withdrawn, 500);
+    let user_token_account = env.user_token_account;
+    assert_eq!(token_balance(&mut env, user_token_account).await, 495);
+    let treasury = treasury_token_address(&env.mint);
+    assert_eq!(token_balance(&mut env, treasury).await, 5);
+
+    let counters: ProtocolCounters = load_account(&mut env, counters_address()).await;
+    assert_eq!(counters.total_value_locked, 500);
+}
+
+#[tokio::test]
+async fn transfer_pays_fee_to_treasury() {
+    let mut env = setup(1_000).await;
+    let counterparty = Keypair::new();
+    let (to_vault, to_token_account, _) = create_funded_vault(&mut env, &counterparty, 0).await;
+    let fee = fee_ix(&env, env.admin.pubkey(), 250);
+    send(&mut env.context, &[fee], &[]).await.unwrap();
+
+    let transfer = via_caller(
+        TRUSTED_CALLER,
+        TRUSTED_CALLER,
+        vault_manager::accounts::TransferCollateral {
+            from_vault: env.vault,
+            to_vault,
+            from_token_account: env.vault_token_account,
+            to_token_account,
+            authority: env.authority,
+            counters: counters_address(),
+            treasury_token_account: treasury_token_address(&env.mint),
+            yield_index: Some(yield_index_address(&env.mint)),
+            yield_reserve: Some(yield_reserve_address(&env.mint)),
+            caller_program: TRUSTED_CALLER,
+            caller_authority: VaultAuthority::caller_authority_address(&TRUSTED_CALLER),
+            authorized_program: AuthorizedProgram::find_address(&TRUSTED_CALLER).0,
+            mint: env.mint,
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None),
+        vault_manager::instruction::TransferCollateral { amount: 400 }.data(),
+    );
+    send(&mut env.context, &[transfer], &[]).await.unwrap();
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.total_balance, 600);
+    let recipient: vault_manager::CollateralVault = load_account(&mut env, to_vault).await;
+    assert_eq!(recipient.total_balance, 390);
+    assert_eq!(token_balance(&mut env, to_token_account).await, 390);
+    let treasury = treasury_token_address(&env.mint);
+    assert_eq!(token_balance(&mut env, treasury).await, 10);
+
+    // Only the fee left the vaults
+    let counters: ProtocolCounters = load_account(&mut env, counters_address()).await;
+    assert_eq!(counters.total_value_locked, 990);
+}
+
+#[tokio::test]
+async fn admin_collects_fees() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+    let destination = admin_token_account(&mut env).await;
+
+    let fee = fee_ix(&env, env.admin.pubkey(), 1_000);
+    let withdraw = withdraw_ix(&env, 300);
+    send(&mut env.context, &[fee, withdraw], &[&user]).await.unwrap();
+
+    let collect = collect_ix(&env, destination);
+    send(&mut env.context, &[collect], &[]).await.unwrap();
+
+    assert_eq!(token_balance(&mut env, destination).await, 30);
+    let treasury = treasury_token_address(&env.mint);
+    assert_eq!(token_balance(&mut env, treasury).await, 0);
+
+    let collect = collect_ix(&env, destination);
+    let result = send(&mut env.context, &[collect], &[]).await;
+    assert_vault_error(result, VaultError::NoFeesToCollect);
+}
+
+#[tokio::test]
+async fn fee_must_go_to_a_treasury_account() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+
+    let mut withdraw = withdraw_ix(&env, 500);
+    let treasury = treasury_token_address(&env.mint);
+    for meta in withdraw.accounts.iter_mut() {
+        if meta.pubkey == treasury {
+            meta.pubkey = env.user_token_account;
+        }
+    }
+    let result = send(&mut env.context, &[withdraw], &[&user]).await;
+    assert_vault_error(result, VaultError::InvalidTreasuryAccount);
+}
+
+#[tokio::test]
+async fn fee_above_maximum_is_rejected() {
+    let mut env = setup(0).await;
+
+    let fee = fee_ix(&env, env.admin.pubkey(), VaultAuthority::MAX_FEE_BPS + 1);
+    let result = send(&mut env.context, &[fee], &[]).await;
+    assert_vault_error(result, VaultError::InvalidFee);
+
+    let authority = load_authority(&mut env).await;
+    assert_eq!(authority.fee_bps, 0);
+}
+
+#[tokio::test]
+async fn only_admin_can_set_fee() {
+    let mut env = setup(0).await;
+    let user = env.user.insecure_clone();
+
+    let fee = fee_ix(&env, user.pubkey(), 100);
+    let result = send(&mut env.context, &[fee], &[&user]).await;
+    assert_vault_error(result, VaultError::UnauthorizedAdmin);
+}
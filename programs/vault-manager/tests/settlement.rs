@@ -0,0 +1,190 @@
+mod common;
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use vault_manager::{
+    AuthorizedProgram, CollateralVault, SettlementReason, VaultAuthority, VaultError,
+};
+
+fn settle_ix(
+    env: &TestEnv,
+    caller: Pubkey,
+    to_vault: Pubkey,
+    to_token_account: Pubkey,
+    amount: u64,
+) -> Instruction {
+    via_caller(
+        caller,
+        caller,
+        vault_manager::accounts::SettleLocked {
+            from_vault: env.vault,
+            to_vault,
+            from_token_account: env.vault_token_account,
+            to_token_account,
+            authority: env.authority,
+            counters: counters_address(),
+            yield_index: Some(yield_index_address(&env.mint)),
+            yield_reserve: Some(yield_reserve_address(&env.mint)),
+            caller_program: caller,
+            caller_authority: VaultAuthority::caller_authority_address(&caller),
+            authorized_program: AuthorizedProgram::find_address(&caller).0,
+            program_lock: program_lock_address(&env.vault, &caller),
+            mint: env.mint,
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None),
+        vault_manager::instruction::SettleLocked {
+            amount,
+            reason: SettlementReason::Liquidation,
+        }
+        .data(),
+    )
+}
+
+fn assert_consistent(vault: &CollateralVault) {
+    assert_eq!(
+        vault.total_balance,
+        vault.locked_balance + vault.available_balance + vault.pending_balance
+    );
+}
+
+#[tokio::test]
+async fn settle_moves_locked_collateral_and_keeps_accounting_consistent() {
+    let mut env = setup(1_000).await;
+    let counterparty = Keypair::new();
+    let (to_vault, to_token_account, _) = create_funded_vault(&mut env, &counterparty, 0).await;
+
+    let lock = lock_ix(&env, TRUSTED_CALLER, 600);
+    send(&mut env.context, &[lock], &[]).await.unwrap();
+
+    let settle = settle_ix(&env, TRUSTED_CALLER, to_vault, to_token_account, 450);
+    send(&mut env.context, &[settle], &[]).await.unwrap();
+
+    let from = load_vault(&mut env).await;
+    assert_eq!(from.total_balance, 550);
+    assert_eq!(from.locked_balance, 150);
+    assert_eq!(from.available_balance, 400);
+    assert_consistent(&from);
+
+    let to: CollateralVault = load_account(&mut env, to_vault).await;
+    assert_eq!(to.total_balance, 450);
+    assert_eq!(to.available_balance, 450);
+    assert_consistent(&to);
+
+    let program_lock = load_program_lock(&mut env, TRUSTED_CALLER).await;
+    assert_eq!(program_lock.locked_amount, 150);
+
+    let vault_token_account = env.vault_token_account;
+    assert_eq!(token_balance(&mut env, vault_token_account).await, 550);
+    assert_eq!(token_balance(&mut env, to_token_account).await, 450);
+}
+
+#[tokio::test]
+async fn settle_cannot_exceed_own_lock() {
+    let mut env = setup(1_000).await;
+    let counterparty = Keypair::new();
+    let (to_vault, to_token_account, _) = create_funded_vault(&mut env, &counterparty, 0).await;
+
+    let lock_a = lock_ix(&env, TRUSTED_CALLER, 200);
+    let lock_b = lock_ix(&env, SECOND_CALLER, 500);
+    send(&mut env.context, &[lock_a, lock_b], &[]).await.unwrap();
+
+    // 700 is locked in total, but only 200 of it belongs to this program
+    let settle = settle_ix(&env, TRUSTED_CALLER, to_vault, to_token_account, 300);
+    let result = send(&mut env.context, &[settle], &[]).await;
+    assert_vault_error(result, VaultError::InsufficientLockedBalance);
+
+    let from = load_vault(&mut env).await;
+    assert_eq!(from.locked_balance, 700);
+    assert_eq!(from.total_balance, 1_000);
+}
+
+#[tokio::test]
+async fn settle_is_rejected_while_paused() {
+    let mut env = setup(1_000).await;
+    let counterparty = Keypair::new();
+    let (to_vault, to_token_account, _) = create_funded_vault(&mut env, &counterparty, 0).await;
+
+    let lock = lock_ix(&env, TRUSTED_CALLER, 500);
+    let pause = pause_ix(&env, env.admin.pubkey(), 1);
+    send(&mut env.context, &[lock, pause], &[]).await.unwrap();
+
+    let settle = settle_ix(&env, TRUSTED_CALLER, to_vault, to_token_account, 500);
+    let result = send(&mut env.context, &[settle], &[]).await;
+    assert_vault_error(result, VaultError::ProtocolPaused);
+
+    let from = load_vault(&mut env).await;
+    assert_eq!(from.locked_balance, 500);
+    assert_eq!(from.total_balance, 1_000);
+}
+
+#[tokio::test]
+async fn settle_rejects_foreign_token_account() {
+    let mut env = setup(1_000).await;
+    let counterparty = Keypair::new();
+    let (to_vault, _, counterparty_wallet_ata) =
+        create_funded_vault(&mut env, &counterparty, 0).await;
+
+    let lock = lock_ix(&env, TRUSTED_CALLER, 500);
+    send(&mut env.context, &[lock], &[]).await.unwrap();
+
+    let settle = settle_ix(&env, TRUSTED_CALLER, to_vault, counterparty_wallet_ata, 500);
+    let result = send(&mut env.context, &[settle], &[]).await;
+    assert_vault_error(result, VaultError::InvalidVaultState);
+}
+
+#[tokio::test]
+async fn transfer_debits_available_balance() {
+    let mut env = setup(1_000).await;
+    let counterparty = Keypair::new();
+    let (to_vault, to_token_account, _) = create_funded_vault(&mut env, &counterparty, 0).await;
+
+    let lock = lock_ix(&env, TRUSTED_CALLER, 700);
+    send(&mut env.context, &[lock], &[]).await.unwrap();
+
+    let transfer_accounts = vault_manager::accounts::TransferCollateral {
+        from_vault: env.vault,
+        to_vault,
+        from_token_account: env.vault_token_account,
+        to_token_account,
+        authority: env.authority,
+        counters: counters_address(),
+        treasury_token_account: treasury_token_address(&env.mint),
+        yield_index: Some(yield_index_address(&env.mint)),
+        yield_reserve: Some(yield_reserve_address(&env.mint)),
+        caller_program: TRUSTED_CALLER,
+        caller_authority: VaultAuthority::caller_authority_address(&TRUSTED_CALLER),
+        authorized_program: AuthorizedProgram::find_address(&TRUSTED_CALLER).0,
+        mint: env.mint,
+        token_program: spl_token::ID,
+    };
+
+    // Locked collateral cannot leave through a plain transfer
+    let transfer = via_caller(
+        TRUSTED_CALLER,
+        TRUSTED_CALLER,
+        transfer_accounts.to_account_metas(None),
+        vault_manager::instruction::TransferCollateral { amount: 400 }.data(),
+    );
+    let result = send(&mut env.context, &[transfer], &[]).await;
+    assert_vault_error(result, VaultError::InsufficientTransferBalance);
+
+    let transfer = via_caller(
+        TRUSTED_CALLER,
+        TRUSTED_CALLER,
+        transfer_accounts.to_account_metas(None),
+        vault_manager::instruction::TransferCollateral { amount: 300 }.data(),
+    );
+    send(&mut env.context, &[transfer], &[]).await.unwrap();
+
+    let from = load_vault(&mut env).await;
+    assert_eq!(from.total_balance, 700);
+    assert_eq!(from.locked_balance, 700);
+    assert_eq!(from.available_balance, 0);
+    assert_consistent(&from);
+}
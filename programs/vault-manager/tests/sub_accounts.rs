@@ -0,0 +1,122 @@
+mod common;
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use vault_manager::{CollateralVault, ProtocolCounters, VaultError};
+
+/// Move `amount` from the test vault (sub-account 0) into `to_vault`
+fn move_ix(env: &TestEnv, to_vault: Pubkey, to_token_account: Pubkey, amount: u64) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::TransferBetweenSubAccounts {
+            user: env.user.pubkey(),
+            from_vault: env.vault,
+            to_vault,
+            from_token_account: env.vault_token_account,
+            to_token_account,
+            authority: env.authority,
+            counters: counters_address(),
+            yield_index: Some(yield_index_address(&env.mint)),
+            yield_reserve: Some(yield_reserve_address(&env.mint)),
+            mint: env.mint,
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::TransferBetweenSubAccounts { amount }.data(),
+    }
+}
+
+#[tokio::test]
+async fn owner_opens_isolated_sub_accounts() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+    let mint = env.mint;
+
+    let (vault, vault_token_account, _) =
+        create_funded_sub_account(&mut env, &user, mint, 1, 300).await;
+    assert_ne!(vault, env.vault);
+    assert_eq!(vault, vault_address(&user.pubkey(), &mint, 1));
+
+    let main = load_vault(&mut env).await;
+    assert_eq!(main.sub_account, 0);
+    assert_eq!(main.total_balance, 1_000);
+
+    let sub: CollateralVault = load_account(&mut env, vault).await;
+    assert_eq!(sub.owner, user.pubkey());
+    assert_eq!(sub.sub_account, 1);
+    assert_eq!(sub.token_account, vault_token_account);
+    assert_eq!(sub.total_balance, 300);
+}
+
+#[tokio::test]
+async fn owner_moves_collateral_between_sub_accounts() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+    let mint = env.mint;
+    let (sub_vault, sub_token_account, _) =
+        create_funded_sub_account(&mut env, &user, mint, 1, 300).await;
+
+    let transfer = move_ix(&env, sub_vault, sub_token_account, 400);
+    send(&mut env.context, &[transfer], &[&user]).await.unwrap();
+
+    let main = load_vault(&mut env).await;
+    assert_eq!(main.total_balance, 600);
+    assert_eq!(main.total_withdrawn, 0);
+    let sub: CollateralVault = load_account(&mut env, sub_vault).await;
+    assert_eq!(sub.total_balance, 700);
+    assert_eq!(sub.available_balance, 700);
+    assert_eq!(token_balance(&mut env, sub_token_account).await, 700);
+
+    // The collateral never left the protocol
+    let counters: ProtocolCounters = load_account(&mut env, counters_address()).await;
+    assert_eq!(counters.total_value_locked, 1_300);
+}
+
+#[tokio::test]
+async fn locked_collateral_stays_in_its_sub_account() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+    let mint = env.mint;
+    let (sub_vault, sub_token_account, _) =
+        create_funded_sub_account(&mut env, &user, mint, 1, 0).await;
+
+    let lock = lock_ix(&env, TRUSTED_CALLER, 800);
+    send(&mut env.context, &[lock], &[]).await.unwrap();
+
+    let transfer = move_ix(&env, sub_vault, sub_token_account, 300);
+    let result = send(&mut env.context, &[transfer], &[&user]).await;
+    assert_vault_error(result, VaultError::InsufficientBalance);
+
+    let transfer = move_ix(&env, sub_vault, sub_token_account, 200);
+    send(&mut env.context, &[transfer], &[&user]).await.unwrap();
+}
+
+#[tokio::test]
+async fn move_within_one_sub_account_is_rejected() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+
+    let transfer = move_ix(&env, env.vault, env.vault_token_account, 100);
+    let result = send(&mut env.context, &[transfer], &[&user]).await;
+    assert_vault_error(result, VaultError::InvalidVaultState);
+}
+
+#[tokio::test]
+async fn cannot_move_into_another_owners_vault() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+    let other = Keypair::new();
+    let (other_vault, other_token_account, _) = create_funded_vault(&mut env, &other, 0).await;
+
+    let transfer = move_ix(&env, other_vault, other_token_account, 100);
+    let result = send(&mut env.context, &[transfer], &[&user]).await;
+    assert!(result.is_err());
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.total_balance, 1_000);
+}
@@ -0,0 +1,201 @@
+mod common;
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, signature::Signer, system_program};
+use vault_manager::{PendingWithdrawal, VaultAuthority, VaultError};
+
+const DELAY: i64 = 24 * 60 * 60;
+
+fn pending_withdrawal_address(vault: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"pending_withdrawal", vault.as_ref()],
+        &vault_manager::ID,
+    )
+    .0
+}
+
+fn delay_ix(env: &TestEnv, withdrawal_delay: i64) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::ManageAuthority {
+            admin: env.admin.pubkey(),
+            authority: env.authority,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::SetWithdrawalDelay { withdrawal_delay }.data(),
+    }
+}
+
+async fn set_delay(env: &mut TestEnv, withdrawal_delay: i64) {
+    let ix = delay_ix(env, withdrawal_delay);
+    send(&mut env.context, &[ix], &[]).await.unwrap();
+}
+
+fn request_ix(env: &TestEnv, amount: u64) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::RequestWithdrawal {
+            user: env.user.pubkey(),
+            vault: env.vault,
+            authority: env.authority,
+            yield_index: Some(yield_index_address(&env.mint)),
+            pending_withdrawal: pending_withdrawal_address(&env.vault),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::RequestWithdrawal { amount }.data(),
+    }
+}
+
+fn execute_ix(env: &TestEnv) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::ExecuteWithdrawal {
+            user: env.user.pubkey(),
+            vault: env.vault,
+            pending_withdrawal: pending_withdrawal_address(&env.vault),
+            user_token_account: env.user_token_account,
+            vault_token_account: env.vault_token_account,
+            authority: env.authority,
+            counters: counters_address(),
+            treasury_token_account: treasury_token_address(&env.mint),
+            yield_index: Some(yield_index_address(&env.mint)),
+            yield_reserve: Some(yield_reserve_address(&env.mint)),
+            mint: env.mint,
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::ExecuteWithdrawal {}.data(),
+    }
+}
+
+fn cancel_ix(env: &TestEnv) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::CancelWithdrawal {
+            user: env.user.pubkey(),
+            vault: env.vault,
+            pending_withdrawal: pending_withdrawal_address(&env.vault),
+            yield_index: Some(yield_index_address(&env.mint)),
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::CancelWithdrawal {}.data(),
+    }
+}
+
+#[tokio::test]
+async fn withdrawal_executes_only_after_delay() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+    set_delay(&mut env, DELAY).await;
+
+    let request = request_ix(&env, 400);
+    send(&mut env.context, &[request], &[&user]).await.unwrap();
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.total_balance, 1_000);
+    assert_eq!(vault.available_balance, 600);
+    assert_eq!(vault.pending_balance, 400);
+
+    let pending_address = pending_withdrawal_address(&env.vault);
+    let pending: PendingWithdrawal = load_account(&mut env, pending_address).await;
+    assert_eq!(pending.amount, 400);
+    assert_eq!(pending.executable_at, pending.requested_at + DELAY);
+
+    let execute = execute_ix(&env);
+    let result = send(&mut env.context, &[execute], &[&user]).await;
+    assert_vault_error(result, VaultError::WithdrawalTimelockActive);
+
+    advance_clock(&mut env, DELAY).await;
+    let execute = execute_ix(&env);
+    send(&mut env.context, &[execute], &[&user]).await.unwrap();
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.total_balance, 600);
+    assert_eq!(vault.available_balance, 600);
+    assert_eq!(vault.pending_balance, 0);
+    assert_eq!(vault.total_withdrawn, 400);
+
+    let user_token_account = env.user_token_account;
+    assert_eq!(token_balance(&mut env, user_token_account).await, 400);
+    assert!(env
+        .context
+        .banks_client
+        .get_account(pending_address)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn cancelled_withdrawal_returns_to_available() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+    set_delay(&mut env, DELAY).await;
+
+    let request = request_ix(&env, 400);
+    send(&mut env.context, &[request], &[&user]).await.unwrap();
+
+    // Pending collateral cannot be locked
+    let lock = lock_ix(&env, TRUSTED_CALLER, 700);
+    let result = send(&mut env.context, &[lock], &[]).await;
+    assert_vault_error(result, VaultError::InsufficientBalanceToLock);
+
+    let cancel = cancel_ix(&env);
+    send(&mut env.context, &[cancel], &[&user]).await.unwrap();
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.available_balance, 1_000);
+    assert_eq!(vault.pending_balance, 0);
+
+    // The request can be made again after cancelling
+    let request = request_ix(&env, 1_000);
+    send(&mut env.context, &[request], &[&user]).await.unwrap();
+}
+
+#[tokio::test]
+async fn instant_withdraw_is_rejected_while_delay_is_set() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+    set_delay(&mut env, DELAY).await;
+
+    let withdraw = withdraw_ix(&env, 100);
+    let result = send(&mut env.context, &[withdraw], &[&user]).await;
+    assert_vault_error(result, VaultError::WithdrawalTimelockRequired);
+
+    set_delay(&mut env, 0).await;
+    let withdraw = withdraw_ix(&env, 100);
+    send(&mut env.context, &[withdraw], &[&user]).await.unwrap();
+}
+
+#[tokio::test]
+async fn request_cannot_exceed_available_balance() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+    set_delay(&mut env, DELAY).await;
+
+    let lock = lock_ix(&env, TRUSTED_CALLER, 700);
+    send(&mut env.context, &[lock], &[]).await.unwrap();
+
+    let request = request_ix(&env, 400);
+    let result = send(&mut env.context, &[request], &[&user]).await;
+    assert_vault_error(result, VaultError::InsufficientBalance);
+}
+
+#[tokio::test]
+async fn delay_outside_bounds_is_rejected() {
+    let mut env = setup(1_000).await;
+
+    for withdrawal_delay in [-1, VaultAuthority::MAX_WITHDRAWAL_DELAY + 1] {
+        let ix = delay_ix(&env, withdrawal_delay);
+        let result = send(&mut env.context, &[ix], &[]).await;
+        assert_vault_error(result, VaultError::InvalidWithdrawalDelay);
+    }
+
+    set_delay(&mut env, VaultAuthority::MAX_WITHDRAWAL_DELAY).await;
+    assert_eq!(
+        load_authority(&mut env).await.withdrawal_delay,
+        VaultAuthority::MAX_WITHDRAWAL_DELAY
+    );
+}
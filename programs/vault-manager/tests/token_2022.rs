@@ -0,0 +1,158 @@
+mod common;
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use solana_sdk::signature::{Keypair, Signer};
+use spl_token_2022::extension::{interest_bearing_mint, transfer_fee, ExtensionType};
+use vault_manager::{
+    AuthorizedProgram, CollateralVault, ProtocolCounters, VaultAuthority, VaultError,
+};
+
+/// Start with a test vault of a Token-2022 mint charging a 1% transfer fee
+async fn setup_with_transfer_fee(deposit_amount: u64) -> TestEnv {
+    let mint = Keypair::new();
+    let init_fee = transfer_fee::instruction::initialize_transfer_fee_config(
+        &spl_token_2022::ID,
+        &mint.pubkey(),
+        None,
+        None,
+        100,
+        u64::MAX,
+    )
+    .unwrap();
+    setup_token_2022(
+        deposit_amount,
+        mint,
+        &[ExtensionType::TransferFeeConfig],
+        vec![init_fee],
+    )
+    .await
+}
+
+#[tokio::test]
+async fn deposit_credits_amount_received() {
+    let mut env = setup_with_transfer_fee(1_000).await;
+
+    // 1% of the deposit is withheld in the vault token account by the mint
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.total_balance, 990);
+    assert_eq!(vault.available_balance, 990);
+    assert_eq!(vault.total_deposited, 990);
+
+    let counters: ProtocolCounters = load_account(&mut env, counters_address()).await;
+    assert_eq!(counters.total_value_locked, 990);
+}
+
+#[tokio::test]
+async fn withdrawal_recipient_bears_transfer_fee() {
+    let mut env = setup_with_transfer_fee(1_000).await;
+    let user = env.user.insecure_clone();
+
+    let withdraw = withdraw_ix(&env, 990);
+    send(&mut env.context, &[withdraw], &[&user]).await.unwrap();
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.total_balance, 0);
+    assert_eq!(vault.total_withdrawn, 990);
+    let user_token_account = env.user_token_account;
+    assert_eq!(token_balance(&mut env, user_token_account).await, 980);
+
+    let counters: ProtocolCounters = load_account(&mut env, counters_address()).await;
+    assert_eq!(counters.total_value_locked, 0);
+}
+
+#[tokio::test]
+async fn transfer_credits_amount_received() {
+    let mut env = setup_with_transfer_fee(1_000).await;
+    let counterparty = Keypair::new();
+    let (to_vault, to_token_account, _) = create_funded_vault(&mut env, &counterparty, 0).await;
+
+    let transfer = via_caller(
+        TRUSTED_CALLER,
+        TRUSTED_CALLER,
+        vault_manager::accounts::TransferCollateral {
+            from_vault: env.vault,
+            to_vault,
+            from_token_account: env.vault_token_account,
+            to_token_account,
+            authority: env.authority,
+            counters: counters_address(),
+            treasury_token_account: token_address(
+                &VaultAuthority::find_treasury_address().0,
+                &env.mint,
+                &env.token_program,
+            ),
+            yield_index: Some(yield_index_address(&env.mint)),
+            yield_reserve: Some(token_address(
+                &yield_index_address(&env.mint),
+                &env.mint,
+                &env.token_program,
+            )),
+            caller_program: TRUSTED_CALLER,
+            caller_authority: VaultAuthority::caller_authority_address(&TRUSTED_CALLER),
+            authorized_program: AuthorizedProgram::find_address(&TRUSTED_CALLER).0,
+            mint: env.mint,
+            token_program: env.token_program,
+        }
+        .to_account_metas(None),
+        vault_manager::instruction::TransferCollateral { amount: 500 }.data(),
+    );
+    send(&mut env.context, &[transfer], &[]).await.unwrap();
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.total_balance, 490);
+    let recipient: CollateralVault = load_account(&mut env, to_vault).await;
+    assert_eq!(recipient.total_balance, 495);
+
+    // The transfer fee left the vaults
+    let counters: ProtocolCounters = load_account(&mut env, counters_address()).await;
+    assert_eq!(counters.total_value_locked, 985);
+}
+
+#[tokio::test]
+async fn unsupported_extension_is_rejected() {
+    let mut env = setup(0).await;
+    let user = env.user.insecure_clone();
+    let mint = Keypair::new();
+    let non_transferable = spl_token_2022::instruction::initialize_non_transferable_mint(
+        &spl_token_2022::ID,
+        &mint.pubkey(),
+    )
+    .unwrap();
+    create_token_2022_mint(
+        &mut env.context,
+        &mint,
+        &[ExtensionType::NonTransferable],
+        vec![non_transferable],
+    )
+    .await;
+
+    let init = initialize_vault_ix(&user.pubkey(), &mint.pubkey(), &spl_token_2022::ID, 0);
+    let result = send(&mut env.context, &[init], &[&user]).await;
+    assert_vault_error(result, VaultError::UnsupportedMintExtension);
+}
+
+#[tokio::test]
+async fn interest_bearing_mint_is_supported() {
+    let mint = Keypair::new();
+    let init_rate = interest_bearing_mint::instruction::initialize(
+        &spl_token_2022::ID,
+        &mint.pubkey(),
+        None,
+        500,
+    )
+    .unwrap();
+    let mut env = setup_token_2022(
+        1_000,
+        mint,
+        &[ExtensionType::InterestBearingConfig],
+        vec![init_rate],
+    )
+    .await;
+
+    // Interest is a display-only UI amount; raw balances are unaffected
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.total_balance, 1_000);
+    let vault_token_account = env.vault_token_account;
+    assert_eq!(token_balance(&mut env, vault_token_account).await, 1_000);
+}
@@ -0,0 +1,132 @@
+mod common;
+
+use anchor_lang::{error::ErrorCode, AccountSerialize};
+use common::*;
+use solana_program_test::BanksClientError;
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::InstructionError,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+use vault_manager::{CollateralVault, VaultError};
+
+/// Overwrite the test vault's account data with `vault`
+async fn write_vault(env: &mut TestEnv, vault: &CollateralVault) {
+    let mut account = env
+        .context
+        .banks_client
+        .get_account(env.vault)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut data = Vec::with_capacity(account.data.len());
+    vault.try_serialize(&mut data).unwrap();
+    account.data[..data.len()].copy_from_slice(&data);
+    env.context.set_account(&env.vault, &AccountSharedData::from(account));
+}
+
+fn assert_anchor_error(result: Result<(), BanksClientError>, expected: ErrorCode) {
+    match result.unwrap_err().unwrap() {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => {
+            assert_eq!(code, expected as u32);
+        }
+        other => panic!("unexpected error: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn zero_amounts_are_rejected() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+
+    let deposit = deposit_ix(&env, 0);
+    let result = send(&mut env.context, &[deposit], &[&user]).await;
+    assert_vault_error(result, VaultError::InvalidAmount);
+
+    let withdraw = withdraw_ix(&env, 0);
+    let result = send(&mut env.context, &[withdraw], &[&user]).await;
+    assert_vault_error(result, VaultError::InvalidAmount);
+
+    let lock = lock_ix(&env, TRUSTED_CALLER, 0);
+    let result = send(&mut env.context, &[lock], &[]).await;
+    assert_vault_error(result, VaultError::InvalidAmount);
+
+    let lock = lock_ix(&env, TRUSTED_CALLER, 100);
+    send(&mut env.context, &[lock], &[]).await.unwrap();
+    let unlock = unlock_ix(&env, TRUSTED_CALLER, 0);
+    let result = send(&mut env.context, &[unlock], &[]).await;
+    assert_vault_error(result, VaultError::InvalidAmount);
+}
+
+#[tokio::test]
+async fn only_the_owner_can_withdraw() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+    let intruder = Keypair::new();
+
+    // The vault seeds include the signer, so another signer never matches them
+    let mut withdraw = withdraw_ix(&env, 100);
+    for meta in withdraw.accounts.iter_mut() {
+        if meta.pubkey == user.pubkey() {
+            meta.pubkey = intruder.pubkey();
+        }
+    }
+    let result = send(&mut env.context, &[withdraw], &[&intruder]).await;
+    assert_anchor_error(result, ErrorCode::ConstraintSeeds);
+
+    // A recorded owner that disagrees with the seeds is rejected as well
+    let mut vault = load_vault(&mut env).await;
+    vault.owner = intruder.pubkey();
+    write_vault(&mut env, &vault).await;
+    let withdraw = withdraw_ix(&env, 100);
+    let result = send(&mut env.context, &[withdraw], &[&user]).await;
+    assert_vault_error(result, VaultError::UnauthorizedOwner);
+
+    let vault_token_account = env.vault_token_account;
+    assert_eq!(token_balance(&mut env, vault_token_account).await, 1_000);
+}
+
+#[tokio::test]
+async fn balance_overflow_is_rejected() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+
+    // Leave the user a token to deposit
+    let withdraw = withdraw_ix(&env, 1);
+    send(&mut env.context, &[withdraw], &[&user]).await.unwrap();
+
+    let mut vault = load_vault(&mut env).await;
+    vault.total_balance = u64::MAX;
+    vault.available_balance = u64::MAX;
+    write_vault(&mut env, &vault).await;
+
+    let deposit = deposit_ix(&env, 1);
+    let result = send(&mut env.context, &[deposit], &[&user]).await;
+    assert_vault_error(result, VaultError::NumericalOverflow);
+
+    // Nothing moved
+    let vault_token_account = env.vault_token_account;
+    assert_eq!(token_balance(&mut env, vault_token_account).await, 999);
+    assert_eq!(load_vault(&mut env).await.sequence, 2);
+}
+
+#[tokio::test]
+async fn balance_underflow_is_rejected() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+
+    // Available collateral the total no longer accounts for
+    let mut vault = load_vault(&mut env).await;
+    vault.total_balance = 50;
+    write_vault(&mut env, &vault).await;
+
+    let withdraw = withdraw_ix(&env, 100);
+    let result = send(&mut env.context, &[withdraw], &[&user]).await;
+    assert_vault_error(result, VaultError::UnderflowError);
+
+    // Nothing moved
+    let vault_token_account = env.vault_token_account;
+    assert_eq!(token_balance(&mut env, vault_token_account).await, 1_000);
+    assert_eq!(load_vault(&mut env).await.total_balance, 50);
+}
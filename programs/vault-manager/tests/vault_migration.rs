@@ -0,0 +1,154 @@
+mod common;
+
+use anchor_lang::{Discriminator, InstructionData, ToAccountMetas};
+use common::*;
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction, system_program,
+    transaction::TransactionError,
+};
+use vault_manager::{CollateralVault, VaultError};
+
+fn migrate_ix(payer: Pubkey, vault: Pubkey) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::MigrateVault {
+            payer,
+            vault,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::MigrateVault {}.data(),
+    }
+}
+
+/// Rewrite the test vault as a layout 0 account: the current data cut off
+/// before `version`, funded for that size only
+async fn downgrade_vault(env: &mut TestEnv) {
+    let mut account = env
+        .context
+        .banks_client
+        .get_account(env.vault)
+        .await
+        .unwrap()
+        .unwrap();
+    let rent = env.context.banks_client.get_rent().await.unwrap();
+    account.data.truncate(CollateralVault::LEGACY_LEN);
+    account.lamports = rent.minimum_balance(CollateralVault::LEGACY_LEN);
+    env.context.set_account(&env.vault, &AccountSharedData::from(account));
+}
+
+#[tokio::test]
+async fn legacy_vault_is_rejected_until_migrated() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+    downgrade_vault(&mut env).await;
+
+    let deposit = deposit_ix(&env, 100);
+    let result = send(&mut env.context, &[deposit], &[&user]).await;
+    match result.unwrap_err().unwrap() {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => {
+            assert_eq!(code, anchor_lang::error::ErrorCode::AccountDidNotDeserialize as u32);
+        }
+        other => panic!("unexpected error: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn anyone_can_migrate_a_legacy_vault() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+    downgrade_vault(&mut env).await;
+
+    let payer = Keypair::new();
+    let fund = system_instruction::transfer(&env.admin.pubkey(), &payer.pubkey(), 1_000_000_000);
+    let migrate = migrate_ix(payer.pubkey(), env.vault);
+    send(&mut env.context, &[fund, migrate], &[&payer]).await.unwrap();
+
+    let account = env
+        .context
+        .banks_client
+        .get_account(env.vault)
+        .await
+        .unwrap()
+        .unwrap();
+    let rent = env.context.banks_client.get_rent().await.unwrap();
+    assert_eq!(account.data.len(), CollateralVault::LEN);
+    assert!(rent.is_exempt(account.lamports, CollateralVault::LEN));
+
+    // Existing fields are kept and the new ones get their defaults
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.version, CollateralVault::VERSION);
+    assert_eq!(vault.borrowed, 0);
+    assert_eq!(vault.loss_checkpoint, 0);
+    assert_eq!(vault.unpaid_loss, 0);
+    assert_eq!(vault.unsettled_loss, 0);
+    assert_eq!(vault.previous_window_withdrawn, 0);
+    assert_eq!(vault.loss_weight, 0);
+    assert_eq!(vault.pending_cover, 0);
+    assert_eq!(vault.owner, user.pubkey());
+    assert_eq!(vault.total_balance, 1_000);
+
+    // The migration is the first mutation its sequence counts
+    assert_eq!(vault.sequence, 1);
+
+    let withdraw = withdraw_ix(&env, 100);
+    send(&mut env.context, &[withdraw], &[&user]).await.unwrap();
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.total_balance, 900);
+    assert_eq!(vault.sequence, 2);
+}
+
+#[tokio::test]
+async fn current_vault_cannot_be_migrated() {
+    let mut env = setup(1_000).await;
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.version, CollateralVault::VERSION);
+
+    let migrate = migrate_ix(env.admin.pubkey(), env.vault);
+    let result = send(&mut env.context, &[migrate], &[]).await;
+    assert_vault_error(result, VaultError::VaultAlreadyMigrated);
+}
+
+#[tokio::test]
+async fn baseline_vault_is_rejected() {
+    let mut env = setup(0).await;
+    let owner = Keypair::new().pubkey();
+    let (vault, _) = Pubkey::find_program_address(&[b"vault", owner.as_ref()], &vault_manager::ID);
+
+    // Owner, token account, seven balances and timestamps, bump
+    let mut data = CollateralVault::DISCRIMINATOR.to_vec();
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(Pubkey::new_unique().as_ref());
+    for value in [1_000u64, 0, 1_000, 1_000, 0, 1, 1] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.push(255);
+    assert_eq!(data.len(), CollateralVault::BASELINE_LEN);
+
+    let rent = env.context.banks_client.get_rent().await.unwrap();
+    let mut account = AccountSharedData::new(
+        rent.minimum_balance(data.len()),
+        data.len(),
+        &vault_manager::ID,
+    );
+    account.set_data_from_slice(&data);
+    env.context.set_account(&vault, &account);
+
+    let migrate = migrate_ix(env.admin.pubkey(), vault);
+    let result = send(&mut env.context, &[migrate], &[]).await;
+    assert_vault_error(result, VaultError::UnsupportedLegacyLayout);
+
+    let account = env
+        .context
+        .banks_client
+        .get_account(vault)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.data, data);
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use vault_manager::{CollateralVault, VaultError};
+
+fn freeze_ix(env: &TestEnv, vault: Pubkey) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::FreezeVault {
+            admin: env.admin.pubkey(),
+            authority: env.authority,
+            vault,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::FreezeVault {}.data(),
+    }
+}
+
+#[tokio::test]
+async fn sequence_counts_every_mutation() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+
+    // Initialization starts the count; the setup deposit is the first mutation
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.sequence, 1);
+
+    let lock = lock_ix(&env, TRUSTED_CALLER, 300);
+    send(&mut env.context, &[lock], &[]).await.unwrap();
+    assert_eq!(load_vault(&mut env).await.sequence, 2);
+
+    let unlock = unlock_ix(&env, TRUSTED_CALLER, 300);
+    send(&mut env.context, &[unlock], &[]).await.unwrap();
+    assert_eq!(load_vault(&mut env).await.sequence, 3);
+
+    let withdraw = withdraw_ix(&env, 100);
+    send(&mut env.context, &[withdraw], &[&user]).await.unwrap();
+    assert_eq!(load_vault(&mut env).await.sequence, 4);
+
+    let freeze = freeze_ix(&env, env.vault);
+    send(&mut env.context, &[freeze], &[]).await.unwrap();
+    assert_eq!(load_vault(&mut env).await.sequence, 5);
+}
+
+#[tokio::test]
+async fn failed_instruction_leaves_sequence_unchanged() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+
+    let withdraw = withdraw_ix(&env, 5_000);
+    let result = send(&mut env.context, &[withdraw], &[&user]).await;
+    assert_vault_error(result, VaultError::InsufficientBalance);
+
+    assert_eq!(load_vault(&mut env).await.sequence, 1);
+}
+
+#[tokio::test]
+async fn sequence_is_per_vault() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+    let other_user = Keypair::new();
+    let (other_vault, _, _) = create_funded_vault(&mut env, &other_user, 1_000).await;
+
+    let withdraw = withdraw_ix(&env, 100);
+    send(&mut env.context, &[withdraw], &[&user]).await.unwrap();
+    let deposit = deposit_ix(&env, 100);
+    send(&mut env.context, &[deposit], &[&user]).await.unwrap();
+
+    assert_eq!(load_vault(&mut env).await.sequence, 3);
+    let other: CollateralVault = load_account(&mut env, other_vault).await;
+    assert_eq!(other.sequence, 1);
+}
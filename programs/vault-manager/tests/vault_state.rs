@@ -0,0 +1,84 @@
+mod common;
+
+use anchor_lang::{AnchorDeserialize, InstructionData, ToAccountMetas};
+use common::*;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::Signer,
+    transaction::Transaction,
+};
+use vault_manager::{VaultState, VaultStatus};
+
+fn get_state_ix(env: &TestEnv, program: Pubkey) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::GetVaultState {
+            vault: env.vault,
+            yield_index: Some(yield_index_address(&env.mint)),
+            program_lock: program_lock_address(&env.vault, &program),
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::GetVaultState { program }.data(),
+    }
+}
+
+/// Simulate `get_vault_state` and decode its return data
+async fn read_state(env: &mut TestEnv, program: Pubkey) -> VaultState {
+    let payer = env.context.payer.insecure_clone();
+    let blockhash = env.context.get_new_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[get_state_ix(env, program)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        blockhash,
+    );
+    let simulation = env.context.banks_client.simulate_transaction(tx).await.unwrap();
+    simulation.result.unwrap().unwrap();
+
+    let return_data = simulation.simulation_details.unwrap().return_data.unwrap();
+    assert_eq!(return_data.program_id, vault_manager::ID);
+    VaultState::deserialize(&mut return_data.data.as_slice()).unwrap()
+}
+
+#[tokio::test]
+async fn reports_balances_and_program_lock() {
+    let mut env = setup(1_000).await;
+    let lock = lock_ix(&env, TRUSTED_CALLER, 300);
+    send(&mut env.context, &[lock], &[]).await.unwrap();
+
+    let state = read_state(&mut env, TRUSTED_CALLER).await;
+    assert_eq!(state.version, VaultState::VERSION);
+    assert_eq!(state.vault, env.vault);
+    assert_eq!(state.owner, env.user.pubkey());
+    assert_eq!(state.mint, env.mint);
+    assert_eq!(state.status, VaultStatus::Active);
+    assert_eq!(state.total_balance, 1_000);
+    assert_eq!(state.locked_balance, 300);
+    assert_eq!(state.available_balance, 700);
+    assert_eq!(state.program, TRUSTED_CALLER);
+    assert_eq!(state.program_locked, 300);
+    assert_eq!(state.sequence, 2);
+}
+
+#[tokio::test]
+async fn program_that_never_locked_reads_zero() {
+    let mut env = setup(1_000).await;
+    let lock = lock_ix(&env, TRUSTED_CALLER, 300);
+    send(&mut env.context, &[lock], &[]).await.unwrap();
+
+    let state = read_state(&mut env, SECOND_CALLER).await;
+    assert_eq!(state.locked_balance, 300);
+    assert_eq!(state.program, SECOND_CALLER);
+    assert_eq!(state.program_locked, 0);
+}
+
+#[tokio::test]
+async fn reading_does_not_mutate_the_vault() {
+    let mut env = setup(1_000).await;
+
+    let ix = get_state_ix(&env, TRUSTED_CALLER);
+    send(&mut env.context, &[ix], &[]).await.unwrap();
+
+    assert_eq!(load_vault(&mut env).await.sequence, 1);
+}
@@ -0,0 +1,203 @@
+mod common;
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use solana_sdk::{
+    clock::Clock,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use vault_manager::{VaultAuthority, VaultError};
+
+const WINDOW: i64 = 60 * 60;
+
+fn limits_ix(env: &TestEnv, window: i64, max_vault: u64, max_global: u64) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::ManageAuthority {
+            admin: env.admin.pubkey(),
+            authority: env.authority,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::SetWithdrawalLimits {
+            window,
+            max_vault_withdrawal: max_vault,
+            max_global_withdrawal: max_global,
+        }
+        .data(),
+    }
+}
+
+/// Withdraw from a vault other than the test environment's own
+fn withdraw_from(
+    env: &TestEnv,
+    owner: &Keypair,
+    (vault, vault_token_account, user_token_account): (Pubkey, Pubkey, Pubkey),
+    amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::Withdraw {
+            user: owner.pubkey(),
+            vault,
+            user_token_account,
+            vault_token_account,
+            authority: env.authority,
+            counters: counters_address(),
+            treasury_token_account: treasury_token_address(&env.mint),
+            yield_index: Some(yield_index_address(&env.mint)),
+            yield_reserve: Some(yield_reserve_address(&env.mint)),
+            owner: owner.pubkey(),
+            mint: env.mint,
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::Withdraw { amount }.data(),
+    }
+}
+
+/// Move the clock to the start of the next rate-limit bucket
+async fn align_clock(env: &mut TestEnv, window: i64) {
+    let origin = load_authority(env).await.withdrawal_window_start;
+    let clock: Clock = env.context.banks_client.get_sysvar().await.unwrap();
+    advance_clock(env, window - (clock.unix_timestamp - origin).rem_euclid(window)).await;
+}
+
+#[tokio::test]
+async fn vault_limit_rolls_over_window() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+    let limits = limits_ix(&env, WINDOW, 300, 0);
+    send(&mut env.context, &[limits], &[]).await.unwrap();
+    align_clock(&mut env, WINDOW).await;
+
+    let withdraw = withdraw_ix(&env, 200);
+    send(&mut env.context, &[withdraw], &[&user]).await.unwrap();
+
+    let withdraw = withdraw_ix(&env, 101);
+    let result = send(&mut env.context, &[withdraw], &[&user]).await;
+    assert_vault_error(result, VaultError::VaultWithdrawalRateLimited);
+
+    let withdraw = withdraw_ix(&env, 100);
+    send(&mut env.context, &[withdraw], &[&user]).await.unwrap();
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.withdrawal_window.withdrawn, 300);
+
+    // The previous bucket still fills the rolling window right after it ends
+    advance_clock(&mut env, WINDOW).await;
+    let withdraw = withdraw_ix(&env, 1);
+    let result = send(&mut env.context, &[withdraw], &[&user]).await;
+    assert_vault_error(result, VaultError::VaultWithdrawalRateLimited);
+
+    // Half of it has rolled out of the window halfway through the next bucket
+    advance_clock(&mut env, WINDOW / 2).await;
+    let withdraw = withdraw_ix(&env, 151);
+    let result = send(&mut env.context, &[withdraw], &[&user]).await;
+    assert_vault_error(result, VaultError::VaultWithdrawalRateLimited);
+
+    let withdraw = withdraw_ix(&env, 150);
+    send(&mut env.context, &[withdraw], &[&user]).await.unwrap();
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.previous_window_withdrawn, 300);
+    assert_eq!(vault.withdrawal_window.withdrawn, 150);
+
+    // Two idle buckets later nothing counts any more
+    advance_clock(&mut env, 2 * WINDOW).await;
+    let withdraw = withdraw_ix(&env, 300);
+    send(&mut env.context, &[withdraw], &[&user]).await.unwrap();
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.total_balance, 250);
+    assert_eq!(vault.previous_window_withdrawn, 0);
+    assert_eq!(vault.withdrawal_window.withdrawn, 300);
+}
+
+#[tokio::test]
+async fn changing_the_window_resets_its_buckets() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+    let limits = limits_ix(&env, WINDOW, 300, 0);
+    send(&mut env.context, &[limits], &[]).await.unwrap();
+    align_clock(&mut env, WINDOW).await;
+
+    let withdraw = withdraw_ix(&env, 300);
+    send(&mut env.context, &[withdraw], &[&user]).await.unwrap();
+
+    // Same length: the bucket keeps counting
+    advance_clock(&mut env, 10).await;
+    let limits = limits_ix(&env, WINDOW, 300, 0);
+    send(&mut env.context, &[limits], &[]).await.unwrap();
+    let withdraw = withdraw_ix(&env, 1);
+    let result = send(&mut env.context, &[withdraw], &[&user]).await;
+    assert_vault_error(result, VaultError::VaultWithdrawalRateLimited);
+
+    // A new length starts from empty buckets counted from the change
+    advance_clock(&mut env, 10).await;
+    let limits = limits_ix(&env, WINDOW / 4, 300, 0);
+    send(&mut env.context, &[limits], &[]).await.unwrap();
+    let authority = load_authority(&mut env).await;
+    let clock: Clock = env.context.banks_client.get_sysvar().await.unwrap();
+    assert_eq!(authority.withdrawal_window_start, clock.unix_timestamp);
+
+    let withdraw = withdraw_ix(&env, 200);
+    send(&mut env.context, &[withdraw], &[&user]).await.unwrap();
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.withdrawal_window.start, authority.withdrawal_window_start);
+    assert_eq!(vault.withdrawal_window.withdrawn, 200);
+    assert_eq!(vault.previous_window_withdrawn, 0);
+
+    let withdraw = withdraw_ix(&env, 101);
+    let result = send(&mut env.context, &[withdraw], &[&user]).await;
+    assert_vault_error(result, VaultError::VaultWithdrawalRateLimited);
+}
+
+#[tokio::test]
+async fn global_limit_spans_all_vaults() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+    let other = Keypair::new();
+    let other_accounts = create_funded_vault(&mut env, &other, 500).await;
+
+    let limits = limits_ix(&env, WINDOW, 0, 500);
+    send(&mut env.context, &[limits], &[]).await.unwrap();
+
+    let withdraw = withdraw_ix(&env, 400);
+    send(&mut env.context, &[withdraw], &[&user]).await.unwrap();
+
+    let withdraw = withdraw_from(&env, &other, other_accounts, 101);
+    let result = send(&mut env.context, &[withdraw], &[&other]).await;
+    assert_vault_error(result, VaultError::GlobalWithdrawalRateLimited);
+
+    let withdraw = withdraw_from(&env, &other, other_accounts, 100);
+    send(&mut env.context, &[withdraw], &[&other]).await.unwrap();
+}
+
+#[tokio::test]
+async fn disabled_window_does_not_limit() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+
+    // Limits without a window are stored but not enforced
+    let limits = limits_ix(&env, 0, 1, 1);
+    send(&mut env.context, &[limits], &[]).await.unwrap();
+
+    let withdraw = withdraw_ix(&env, 1_000);
+    send(&mut env.context, &[withdraw], &[&user]).await.unwrap();
+}
+
+#[tokio::test]
+async fn window_out_of_range_is_rejected() {
+    let mut env = setup(0).await;
+
+    let limits = limits_ix(&env, VaultAuthority::MAX_WITHDRAWAL_WINDOW + 1, 1, 1);
+    let result = send(&mut env.context, &[limits], &[]).await;
+    assert_vault_error(result, VaultError::InvalidWithdrawalWindow);
+
+    let limits = limits_ix(&env, -1, 1, 1);
+    let result = send(&mut env.context, &[limits], &[]).await;
+    assert_vault_error(result, VaultError::InvalidWithdrawalWindow);
+}
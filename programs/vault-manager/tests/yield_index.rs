@@ -0,0 +1,269 @@
+mod common;
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use vault_manager::{
+    AuthorizedProgram, CollateralVault, ProtocolCounters, VaultAuthority, VaultError, YieldIndex,
+};
+
+fn distribute_ix(env: &TestEnv, admin: Pubkey, source: Pubkey, amount: u64) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::DistributeYield {
+            admin,
+            authority: env.authority,
+            yield_index: yield_index_address(&env.mint),
+            yield_reserve: yield_reserve_address(&env.mint),
+            source_token_account: source,
+            counters: counters_address(),
+            mint: env.mint,
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::DistributeYield { amount }.data(),
+    }
+}
+
+/// Create the admin's token account of the test mint holding `amount` tokens
+/// to pay yield from
+async fn admin_token_account(env: &mut TestEnv, amount: u64) -> Pubkey {
+    let admin = env.admin.pubkey();
+    let token_account =
+        spl_associated_token_account::get_associated_token_address(&admin, &env.mint);
+    let ixs = [
+        spl_associated_token_account::instruction::create_associated_token_account(
+            &admin,
+            &admin,
+            &env.mint,
+            &spl_token::ID,
+        ),
+        spl_token::instruction::mint_to(
+            &spl_token::ID,
+            &env.mint,
+            &token_account,
+            &admin,
+            &[],
+            amount,
+        )
+        .unwrap(),
+    ];
+    send(&mut env.context, &ixs, &[]).await.unwrap();
+    token_account
+}
+
+/// Point the test environment at a fresh vault of a new mint whose yield
+/// index includes locked collateral
+async fn use_mint_including_locked(env: &mut TestEnv, deposit_amount: u64) {
+    let user = env.user.insecure_clone();
+    let mint = create_mint_with_yield(&mut env.context, true).await;
+    let (vault, vault_token_account, user_token_account) =
+        create_funded_vault_for_mint(env, &user, mint, deposit_amount).await;
+    env.mint = mint;
+    env.vault = vault;
+    env.vault_token_account = vault_token_account;
+    env.user_token_account = user_token_account;
+}
+
+#[tokio::test]
+async fn yield_accrues_pro_rata_to_shares() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+    let other = Keypair::new();
+    let (other_vault, _, _) = create_funded_vault(&mut env, &other, 3_000).await;
+
+    let source = admin_token_account(&mut env, 400).await;
+    let distribute = distribute_ix(&env, env.admin.pubkey(), source, 400);
+    send(&mut env.context, &[distribute], &[]).await.unwrap();
+
+    let yield_index_key = yield_index_address(&env.mint);
+    let yield_index: YieldIndex = load_account(&mut env, yield_index_key).await;
+    assert_eq!(yield_index.index, YieldIndex::SCALE / 10 * 11);
+    assert_eq!(yield_index.total_shares, 4_000);
+    assert_eq!(yield_index.total_distributed, 400);
+    let counters: ProtocolCounters = load_account(&mut env, counters_address()).await;
+    assert_eq!(counters.total_value_locked, 4_400);
+
+    // Yield is credited when the vault is next touched, and can be withdrawn
+    let withdraw = withdraw_ix(&env, 1_100);
+    send(&mut env.context, &[withdraw], &[&user]).await.unwrap();
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.total_balance, 0);
+    assert_eq!(vault.unsettled_yield, 0);
+    assert_eq!(vault.yield_shares, 0);
+    let user_token_account = env.user_token_account;
+    assert_eq!(token_balance(&mut env, user_token_account).await, 1_100);
+
+    // The other vault's share stays in the reserve until it is touched
+    let other: CollateralVault = load_account(&mut env, other_vault).await;
+    assert_eq!(other.total_balance, 3_000);
+    let reserve = yield_reserve_address(&env.mint);
+    assert_eq!(token_balance(&mut env, reserve).await, 300);
+}
+
+#[tokio::test]
+async fn transfer_settles_yield_into_vault() {
+    let mut env = setup(1_000).await;
+    let counterparty = Keypair::new();
+    let (to_vault, to_token_account, _) = create_funded_vault(&mut env, &counterparty, 0).await;
+
+    let source = admin_token_account(&mut env, 100).await;
+    let distribute = distribute_ix(&env, env.admin.pubkey(), source, 100);
+    send(&mut env.context, &[distribute], &[]).await.unwrap();
+
+    let transfer = via_caller(
+        TRUSTED_CALLER,
+        TRUSTED_CALLER,
+        vault_manager::accounts::TransferCollateral {
+            from_vault: env.vault,
+            to_vault,
+            from_token_account: env.vault_token_account,
+            to_token_account,
+            authority: env.authority,
+            counters: counters_address(),
+            treasury_token_account: treasury_token_address(&env.mint),
+            yield_index: Some(yield_index_address(&env.mint)),
+            yield_reserve: Some(yield_reserve_address(&env.mint)),
+            caller_program: TRUSTED_CALLER,
+            caller_authority: VaultAuthority::caller_authority_address(&TRUSTED_CALLER),
+            authorized_program: AuthorizedProgram::find_address(&TRUSTED_CALLER).0,
+            mint: env.mint,
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None),
+        vault_manager::instruction::TransferCollateral { amount: 1_100 }.data(),
+    );
+    send(&mut env.context, &[transfer], &[]).await.unwrap();
+
+    let recipient: CollateralVault = load_account(&mut env, to_vault).await;
+    assert_eq!(recipient.total_balance, 1_100);
+    assert_eq!(token_balance(&mut env, to_token_account).await, 1_100);
+    let vault_token_account = env.vault_token_account;
+    assert_eq!(token_balance(&mut env, vault_token_account).await, 0);
+}
+
+#[tokio::test]
+async fn locked_collateral_earns_nothing_by_default() {
+    let mut env = setup(1_000).await;
+    let lock = lock_ix(&env, TRUSTED_CALLER, 600);
+    send(&mut env.context, &[lock], &[]).await.unwrap();
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.yield_shares, 400);
+
+    let source = admin_token_account(&mut env, 100).await;
+    let distribute = distribute_ix(&env, env.admin.pubkey(), source, 100);
+    send(&mut env.context, &[distribute], &[]).await.unwrap();
+
+    // All of the yield goes to the 400 available tokens
+    let unlock = unlock_ix(&env, TRUSTED_CALLER, 600);
+    send(&mut env.context, &[unlock], &[]).await.unwrap();
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.total_balance, 1_100);
+    assert_eq!(vault.available_balance, 1_100);
+    assert_eq!(vault.unsettled_yield, 100);
+    // Re-issued at the raised index of 1.25
+    assert_eq!(vault.yield_shares, 880);
+}
+
+#[tokio::test]
+async fn locked_collateral_earns_when_included() {
+    let mut env = setup(0).await;
+    use_mint_including_locked(&mut env, 1_000).await;
+    let lock = lock_ix(&env, TRUSTED_CALLER, 600);
+    send(&mut env.context, &[lock], &[]).await.unwrap();
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.yield_shares, 1_000);
+
+    let source = admin_token_account(&mut env, 100).await;
+    let distribute = distribute_ix(&env, env.admin.pubkey(), source, 100);
+    send(&mut env.context, &[distribute], &[]).await.unwrap();
+
+    let unlock = unlock_ix(&env, TRUSTED_CALLER, 600);
+    send(&mut env.context, &[unlock], &[]).await.unwrap();
+
+    let vault = load_vault(&mut env).await;
+    assert_eq!(vault.total_balance, 1_100);
+    assert_eq!(vault.unsettled_yield, 100);
+}
+
+#[tokio::test]
+async fn distribution_without_shares_is_rejected() {
+    let mut env = setup(0).await;
+
+    let source = admin_token_account(&mut env, 100).await;
+    let distribute = distribute_ix(&env, env.admin.pubkey(), source, 100);
+    let result = send(&mut env.context, &[distribute], &[]).await;
+    assert_vault_error(result, VaultError::NoYieldShares);
+}
+
+#[tokio::test]
+async fn only_admin_can_distribute() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+
+    let distribute = distribute_ix(&env, user.pubkey(), env.user_token_account, 100);
+    let result = send(&mut env.context, &[distribute], &[&user]).await;
+    assert_vault_error(result, VaultError::UnauthorizedAdmin);
+}
+
+/// `ix` with the yield index and reserve left out, the way a client passes
+/// them for a mint that has none
+fn without_yield_index(env: &TestEnv, mut ix: Instruction) -> Instruction {
+    let left_out = [
+        yield_index_address(&env.mint),
+        yield_reserve_address(&env.mint),
+    ];
+    for meta in &mut ix.accounts {
+        if left_out.contains(&meta.pubkey) {
+            *meta = AccountMeta::new_readonly(vault_manager::ID, false);
+        }
+    }
+    ix
+}
+
+#[tokio::test]
+async fn mint_without_yield_index_works_without_it() {
+    let mut env = setup(0).await;
+    let user = env.user.insecure_clone();
+    // The initial deposit already goes through without a yield index
+    let mint = create_mint_without_yield(&mut env.context).await;
+    let (vault, vault_token_account, user_token_account) =
+        create_funded_vault_for_mint(&mut env, &user, mint, 1_000).await;
+    env.mint = mint;
+    env.vault = vault;
+    env.vault_token_account = vault_token_account;
+    env.user_token_account = user_token_account;
+
+    let withdraw = without_yield_index(&env, withdraw_ix(&env, 300));
+    send(&mut env.context, &[withdraw], &[&user]).await.unwrap();
+    let lock = without_yield_index(&env, lock_ix(&env, TRUSTED_CALLER, 200));
+    send(&mut env.context, &[lock], &[]).await.unwrap();
+
+    let vault: CollateralVault = load_vault(&mut env).await;
+    assert_eq!(vault.locked_balance, 200);
+    assert_eq!(vault.available_balance, 500);
+    assert_eq!(vault.yield_shares, 0);
+    let vault_token_account = env.vault_token_account;
+    assert_eq!(
+        vault.total_balance,
+        token_balance(&mut env, vault_token_account).await
+    );
+}
+
+#[tokio::test]
+async fn vault_earning_yield_must_pass_the_index() {
+    let mut env = setup(1_000).await;
+    let user = env.user.insecure_clone();
+
+    let withdraw = without_yield_index(&env, withdraw_ix(&env, 100));
+    let result = send(&mut env.context, &[withdraw], &[&user]).await;
+    assert_vault_error(result, VaultError::YieldIndexRequired);
+}
@@ -29,7 +29,19 @@ impl IntoResponse for VaultServiceError {
                 (StatusCode::BAD_REQUEST, self.to_string())
             }
             VaultServiceError::InvalidAmount(_) => (StatusCode::BAD_REQUEST, self.to_string()),
+            VaultServiceError::VaultNotEmpty(_) => (StatusCode::BAD_REQUEST, self.to_string()),
+            VaultServiceError::UnsupportedMint(_) => (StatusCode::BAD_REQUEST, self.to_string()),
+            VaultServiceError::WithdrawalTimelock(_) => (StatusCode::BAD_REQUEST, self.to_string()),
             VaultServiceError::Unauthorized => (StatusCode::UNAUTHORIZED, self.to_string()),
+            VaultServiceError::ProtocolPaused(_) => {
+                (StatusCode::SERVICE_UNAVAILABLE, self.to_string())
+            }
+            VaultServiceError::VaultFrozen(_) => (StatusCode::FORBIDDEN, self.to_string()),
+            VaultServiceError::OnChainMismatch(_) => (StatusCode::CONFLICT, self.to_string()),
+            VaultServiceError::InvalidMultisig(_) => (StatusCode::BAD_REQUEST, self.to_string()),
+            VaultServiceError::DepositCapExceeded(_) => {
+                (StatusCode::BAD_REQUEST, self.to_string())
+            }
             _ => (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
         };
 
@@ -51,8 +63,13 @@ pub async fn initialize_vault(
 ) -> Result<Json<TransactionResponse>, VaultServiceError> {
     let user_pubkey = Pubkey::from_str(&payload.user_pubkey)
         .map_err(|e| VaultServiceError::InvalidPublicKey(e))?;
+    let mint = Pubkey::from_str(&payload.mint)
+        .map_err(|e| VaultServiceError::InvalidPublicKey(e))?;
 
-    let vault_pubkey = state.vault_manager.initialize_vault(user_pubkey).await?;
+    let vault_pubkey = state
+        .vault_manager
+        .initialize_vault(user_pubkey, mint, payload.sub_account)
+        .await?;
 
     Ok(Json(TransactionResponse {
         signature: vault_pubkey,
@@ -60,6 +77,27 @@ pub async fn initialize_vault(
     }))
 }
 
+/// Close an empty vault (called after on-chain close_vault)
+pub async fn close_vault(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<CloseVaultRequest>,
+) -> Result<Json<TransactionResponse>, VaultServiceError> {
+    let user_pubkey = Pubkey::from_str(&payload.user_pubkey)
+        .map_err(|e| VaultServiceError::InvalidPublicKey(e))?;
+    let mint = Pubkey::from_str(&payload.mint)
+        .map_err(|e| VaultServiceError::InvalidPublicKey(e))?;
+
+    let vault_pubkey = state
+        .vault_manager
+        .close_vault(user_pubkey, mint, payload.sub_account)
+        .await?;
+
+    Ok(Json(TransactionResponse {
+        signature: vault_pubkey,
+        status: "closed".to_string(),
+    }))
+}
+
 /// Get vault balance by vault pubkey
 pub async fn get_vault_balance(
     State(state): State<Arc<AppState>>,
@@ -69,16 +107,34 @@ pub async fn get_vault_balance(
     Ok(Json(balance))
 }
 
-/// Get vault balance by owner pubkey
-pub async fn get_vault_by_owner(
+/// Get balances of all vaults of an owner (every sub-account of every collateral mint)
+pub async fn get_vaults_by_owner(
     State(state): State<Arc<AppState>>,
     Path(owner_pubkey): Path<String>,
-) -> Result<Json<VaultBalanceResponse>, VaultServiceError> {
-    let balance = state
+) -> Result<Json<Vec<VaultBalanceResponse>>, VaultServiceError> {
+    let balances = state
         .vault_manager
-        .get_vault_by_owner(&owner_pubkey)
+        .get_vaults_by_owner(&owner_pubkey)
         .await?;
-    Ok(Json(balance))
+    Ok(Json(balances))
+}
+
+/// List the collateral mints vaults can be opened for
+pub async fn get_collateral_mints(
+    State(state): State<Arc<AppState>>,
+) -> Json<Vec<CollateralMintResponse>> {
+    let mints = state
+        .vault_manager
+        .collateral_mints()
+        .iter()
+        .map(|mint| CollateralMintResponse {
+            symbol: mint.symbol.clone(),
+            mint: mint.address.to_string(),
+            token_program: mint.token_program.to_string(),
+        })
+        .collect();
+
+    Json(mints)
 }
 
 /// Record a deposit (called after on-chain transaction)
@@ -89,15 +145,31 @@ pub async fn record_deposit(
     // In production, verify the transaction signature on-chain
     let user_pubkey = Pubkey::from_str(&payload.user_pubkey)
         .map_err(|e| VaultServiceError::InvalidPublicKey(e))?;
+    let mint = Pubkey::from_str(&payload.mint)
+        .map_err(|e| VaultServiceError::InvalidPublicKey(e))?;
 
-    let (vault_pda, _) = state.vault_manager.derive_vault_pda(&user_pubkey);
+    let payer = payload
+        .payer
+        .as_deref()
+        .map(Pubkey::from_str)
+        .transpose()?
+        .filter(|payer| *payer != user_pubkey);
+
+    let vault_pda = state
+        .vault_manager
+        .resolve_vault(&user_pubkey, &mint, payload.sub_account)?;
 
     // Simulated signature - in production, get from actual transaction
     let signature = format!("sim_{}", uuid::Uuid::new_v4());
 
     state
         .vault_manager
-        .record_deposit(&vault_pda.to_string(), payload.amount, &signature)
+        .record_deposit(
+            &vault_pda.to_string(),
+            payload.amount,
+            payer.as_ref(),
+            &signature,
+        )
         .await?;
 
     // Trigger balance update notification
@@ -119,8 +191,12 @@ pub async fn record_withdrawal(
 ) -> Result<Json<TransactionResponse>, VaultServiceError> {
     let user_pubkey = Pubkey::from_str(&payload.user_pubkey)
         .map_err(|e| VaultServiceError::InvalidPublicKey(e))?;
+    let mint = Pubkey::from_str(&payload.mint)
+        .map_err(|e| VaultServiceError::InvalidPublicKey(e))?;
 
-    let (vault_pda, _) = state.vault_manager.derive_vault_pda(&user_pubkey);
+    let vault_pda = state
+        .vault_manager
+        .resolve_vault(&user_pubkey, &mint, payload.sub_account)?;
 
     // Simulated signature
     let signature = format!("sim_{}", uuid::Uuid::new_v4());
@@ -142,14 +218,160 @@ pub async fn record_withdrawal(
     }))
 }
 
+/// Record a move between two of an owner's sub-accounts (called after
+/// on-chain transfer_between_sub_accounts)
+pub async fn transfer_between_sub_accounts(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<SubAccountTransferRequest>,
+) -> Result<Json<TransactionResponse>, VaultServiceError> {
+    let user_pubkey = Pubkey::from_str(&payload.user_pubkey)
+        .map_err(|e| VaultServiceError::InvalidPublicKey(e))?;
+    let mint = Pubkey::from_str(&payload.mint)
+        .map_err(|e| VaultServiceError::InvalidPublicKey(e))?;
+
+    let from_vault = state
+        .vault_manager
+        .resolve_vault(&user_pubkey, &mint, payload.from_sub_account)?
+        .to_string();
+    let to_vault = state
+        .vault_manager
+        .resolve_vault(&user_pubkey, &mint, payload.to_sub_account)?
+        .to_string();
+
+    // Simulated signature
+    let signature = format!("sim_{}", uuid::Uuid::new_v4());
+
+    state
+        .vault_manager
+        .record_sub_account_transfer(&from_vault, &to_vault, payload.amount, &signature)
+        .await?;
+
+    // Trigger balance update notifications
+    state.balance_tracker.monitor_vault(&from_vault).await?;
+    state.balance_tracker.monitor_vault(&to_vault).await?;
+
+    Ok(Json(TransactionResponse {
+        signature,
+        status: "confirmed".to_string(),
+    }))
+}
+
+/// Record a timelocked withdrawal request (called after on-chain request_withdrawal)
+pub async fn request_withdrawal(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<RequestWithdrawalRequest>,
+) -> Result<Json<PendingWithdrawalResponse>, VaultServiceError> {
+    let user_pubkey = Pubkey::from_str(&payload.user_pubkey)
+        .map_err(|e| VaultServiceError::InvalidPublicKey(e))?;
+    let mint = Pubkey::from_str(&payload.mint)
+        .map_err(|e| VaultServiceError::InvalidPublicKey(e))?;
+
+    let vault_pda = state
+        .vault_manager
+        .resolve_vault(&user_pubkey, &mint, payload.sub_account)?;
+
+    let pending = state
+        .vault_manager
+        .request_withdrawal(&vault_pda.to_string(), payload.amount)
+        .await?;
+
+    // Trigger balance update notification
+    state
+        .balance_tracker
+        .monitor_vault(&vault_pda.to_string())
+        .await?;
+
+    Ok(Json(pending))
+}
+
+/// Record execution of a matured withdrawal (called after on-chain execute_withdrawal)
+pub async fn execute_withdrawal(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<ExecuteWithdrawalRequest>,
+) -> Result<Json<TransactionResponse>, VaultServiceError> {
+    let user_pubkey = Pubkey::from_str(&payload.user_pubkey)
+        .map_err(|e| VaultServiceError::InvalidPublicKey(e))?;
+    let mint = Pubkey::from_str(&payload.mint)
+        .map_err(|e| VaultServiceError::InvalidPublicKey(e))?;
+
+    let vault_pda = state
+        .vault_manager
+        .resolve_vault(&user_pubkey, &mint, payload.sub_account)?;
+
+    // Simulated signature
+    let signature = format!("sim_{}", uuid::Uuid::new_v4());
+
+    state
+        .vault_manager
+        .execute_withdrawal(&vault_pda.to_string(), &signature)
+        .await?;
+
+    // Trigger balance update notification
+    state
+        .balance_tracker
+        .monitor_vault(&vault_pda.to_string())
+        .await?;
+
+    Ok(Json(TransactionResponse {
+        signature,
+        status: "confirmed".to_string(),
+    }))
+}
+
+/// Record cancellation of a pending withdrawal (called after on-chain cancel_withdrawal)
+pub async fn cancel_withdrawal(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<CancelWithdrawalRequest>,
+) -> Result<Json<TransactionResponse>, VaultServiceError> {
+    let user_pubkey = Pubkey::from_str(&payload.user_pubkey)
+        .map_err(|e| VaultServiceError::InvalidPublicKey(e))?;
+    let mint = Pubkey::from_str(&payload.mint)
+        .map_err(|e| VaultServiceError::InvalidPublicKey(e))?;
+
+    let vault_pda = state
+        .vault_manager
+        .resolve_vault(&user_pubkey, &mint, payload.sub_account)?;
+
+    state
+        .vault_manager
+        .cancel_withdrawal(&vault_pda.to_string())
+        .await?;
+
+    // Trigger balance update notification
+    state
+        .balance_tracker
+        .monitor_vault(&vault_pda.to_string())
+        .await?;
+
+    Ok(Json(TransactionResponse {
+        signature: vault_pda.to_string(),
+        status: "cancelled".to_string(),
+    }))
+}
+
+/// Get pending withdrawals of a vault and when each one matures
+pub async fn get_pending_withdrawals(
+    State(state): State<Arc<AppState>>,
+    Path(vault_pubkey): Path<String>,
+) -> Result<Json<Vec<PendingWithdrawalResponse>>, VaultServiceError> {
+    let pending = state
+        .vault_manager
+        .get_pending_withdrawals(&vault_pubkey)
+        .await?;
+    Ok(Json(pending))
+}
+
 /// Lock collateral (internal API for position manager)
 pub async fn lock_collateral(
     State(state): State<Arc<AppState>>,
     Json(payload): Json<LockCollateralRequest>,
 ) -> Result<Json<TransactionResponse>, VaultServiceError> {
+    let program_id = Pubkey::from_str(&payload.program_id)
+        .map_err(|e| VaultServiceError::InvalidPublicKey(e))?;
+
     state
         .vault_manager
-        .lock_collateral(&payload.vault_pubkey, payload.amount)
+        .lock_collateral(&payload.vault_pubkey, &program_id, payload.amount)
         .await?;
 
     // Trigger balance update notification
@@ -169,9 +391,12 @@ pub async fn unlock_collateral(
     State(state): State<Arc<AppState>>,
     Json(payload): Json<UnlockCollateralRequest>,
 ) -> Result<Json<TransactionResponse>, VaultServiceError> {
+    let program_id = Pubkey::from_str(&payload.program_id)
+        .map_err(|e| VaultServiceError::InvalidPublicKey(e))?;
+
     state
         .vault_manager
-        .unlock_collateral(&payload.vault_pubkey, payload.amount)
+        .unlock_collateral(&payload.vault_pubkey, &program_id, payload.amount)
         .await?;
 
     // Trigger balance update notification
@@ -186,6 +411,41 @@ pub async fn unlock_collateral(
     }))
 }
 
+/// Settle locked collateral into another vault (internal API for liquidation engine)
+pub async fn settle_locked(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<SettleLockedRequest>,
+) -> Result<Json<TransactionResponse>, VaultServiceError> {
+    let program_id = Pubkey::from_str(&payload.program_id)
+        .map_err(|e| VaultServiceError::InvalidPublicKey(e))?;
+
+    state
+        .vault_manager
+        .record_settlement(
+            &payload.from_vault,
+            &payload.to_vault,
+            &program_id,
+            payload.amount,
+            payload.reason,
+        )
+        .await?;
+
+    // Trigger balance update notifications
+    state
+        .balance_tracker
+        .monitor_vault(&payload.from_vault)
+        .await?;
+    state
+        .balance_tracker
+        .monitor_vault(&payload.to_vault)
+        .await?;
+
+    Ok(Json(TransactionResponse {
+        signature: "settle_success".to_string(),
+        status: "confirmed".to_string(),
+    }))
+}
+
 #[derive(Deserialize)]
 pub struct TransactionHistoryQuery {
     #[serde(default = "default_limit")]
@@ -215,16 +475,304 @@ pub async fn get_tvl(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<TvlResponse>, VaultServiceError> {
     let stats = state.db.calculate_tvl().await?;
+    let status = state.vault_manager.get_protocol_status().await?;
 
     Ok(Json(TvlResponse {
         total_tvl: stats.total_tvl,
         total_locked: stats.total_locked,
         total_available: stats.total_available,
         vault_count: stats.vault_count,
+        max_tvl: status.max_tvl,
+        tvl_headroom: (status.max_tvl > 0)
+            .then(|| status.max_tvl.saturating_sub(stats.total_tvl)),
+        max_vault_balance: status.max_vault_balance,
         timestamp: stats.timestamp.to_rfc3339(),
     }))
 }
 
+/// Get protocol fee statistics
+pub async fn get_fee_stats(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<FeeStatsResponse>, VaultServiceError> {
+    let stats = state.vault_manager.get_fee_stats().await?;
+    Ok(Json(stats))
+}
+
+/// Get the mirrored protocol pause state
+pub async fn get_protocol_status(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ProtocolStatusResponse>, VaultServiceError> {
+    let status = state.vault_manager.get_protocol_status().await?;
+    Ok(Json(status))
+}
+
+/// Mirror an on-chain pause (admin API)
+pub async fn pause(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<PauseRequest>,
+) -> Result<Json<ProtocolStatusResponse>, VaultServiceError> {
+    state.vault_manager.set_paused(true, payload.reason).await?;
+    let status = state.vault_manager.get_protocol_status().await?;
+    Ok(Json(status))
+}
+
+/// Mirror an on-chain unpause (admin API)
+pub async fn unpause(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ProtocolStatusResponse>, VaultServiceError> {
+    state.vault_manager.set_paused(false, 0).await?;
+    let status = state.vault_manager.get_protocol_status().await?;
+    Ok(Json(status))
+}
+
+/// Mirror an on-chain admin proposal (admin API)
+pub async fn propose_admin(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<AdminHandoverRequest>,
+) -> Result<Json<ProtocolStatusResponse>, VaultServiceError> {
+    state.vault_manager.propose_admin(&payload.new_admin).await?;
+    let status = state.vault_manager.get_protocol_status().await?;
+    Ok(Json(status))
+}
+
+/// Mirror an on-chain admin acceptance (admin API)
+pub async fn accept_admin(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<AdminHandoverRequest>,
+) -> Result<Json<ProtocolStatusResponse>, VaultServiceError> {
+    state.vault_manager.accept_admin(&payload.new_admin).await?;
+    let status = state.vault_manager.get_protocol_status().await?;
+    Ok(Json(status))
+}
+
+/// Mirror an on-chain `enable_multisig` or executed signer update (admin API)
+pub async fn set_multisig(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<MultisigRequest>,
+) -> Result<Json<ProtocolStatusResponse>, VaultServiceError> {
+    state
+        .vault_manager
+        .set_multisig(&payload.signers, payload.threshold)
+        .await?;
+    let status = state.vault_manager.get_protocol_status().await?;
+    Ok(Json(status))
+}
+
+/// Mirror on-chain deposit caps (admin API)
+pub async fn set_deposit_caps(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<DepositCapsRequest>,
+) -> Result<Json<ProtocolStatusResponse>, VaultServiceError> {
+    state
+        .vault_manager
+        .set_deposit_caps(payload.max_vault_balance, payload.max_tvl)
+        .await?;
+    let status = state.vault_manager.get_protocol_status().await?;
+    Ok(Json(status))
+}
+
+/// Mirror the on-chain protocol fee (admin API)
+pub async fn set_fee(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<FeeRequest>,
+) -> Result<Json<ProtocolStatusResponse>, VaultServiceError> {
+    state.vault_manager.set_fee(payload.fee_bps).await?;
+    let status = state.vault_manager.get_protocol_status().await?;
+    Ok(Json(status))
+}
+
+/// Mirror an on-chain `initialize_yield_index` (admin API)
+pub async fn initialize_yield_index(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<YieldIndexRequest>,
+) -> Result<Json<YieldIndexResponse>, VaultServiceError> {
+    let index = state
+        .vault_manager
+        .initialize_yield_index(&payload.mint, payload.include_locked)
+        .await?;
+    Ok(Json(index))
+}
+
+/// Mirror an on-chain `distribute_yield` (admin API)
+pub async fn distribute_yield(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<DistributeYieldRequest>,
+) -> Result<Json<YieldIndexResponse>, VaultServiceError> {
+    let index = state
+        .vault_manager
+        .distribute_yield(&payload.mint, payload.amount)
+        .await?;
+    Ok(Json(index))
+}
+
+/// Get the yield index of a collateral mint
+pub async fn get_yield_index(
+    State(state): State<Arc<AppState>>,
+    Path(mint): Path<String>,
+) -> Result<Json<YieldIndexResponse>, VaultServiceError> {
+    let index = state.vault_manager.get_yield_index(&mint).await?;
+    Ok(Json(index))
+}
+
+/// Mirror an on-chain `initialize_insurance_fund` (admin API)
+pub async fn initialize_insurance_fund(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<InsuranceFundRequest>,
+) -> Result<Json<InsuranceFundResponse>, VaultServiceError> {
+    state
+        .vault_manager
+        .initialize_insurance_fund(&payload.mint, payload.socialize_losses)
+        .await?;
+    let fund = state
+        .vault_manager
+        .get_insurance_fund(&payload.mint, default_limit())
+        .await?;
+    Ok(Json(fund))
+}
+
+/// Mirror an on-chain `set_socialize_losses` (admin API)
+pub async fn set_socialize_losses(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<InsuranceFundRequest>,
+) -> Result<Json<InsuranceFundResponse>, VaultServiceError> {
+    state
+        .vault_manager
+        .set_socialize_losses(&payload.mint, payload.socialize_losses)
+        .await?;
+    let fund = state
+        .vault_manager
+        .get_insurance_fund(&payload.mint, default_limit())
+        .await?;
+    Ok(Json(fund))
+}
+
+/// Mirror an on-chain `collect_fees_to_insurance` (admin API)
+pub async fn collect_fees_to_insurance(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<InsuranceFeesRequest>,
+) -> Result<Json<InsuranceFundResponse>, VaultServiceError> {
+    state
+        .vault_manager
+        .record_insurance_fees(&payload.mint, payload.amount, payload.signature)
+        .await?;
+    let fund = state
+        .vault_manager
+        .get_insurance_fund(&payload.mint, default_limit())
+        .await?;
+    Ok(Json(fund))
+}
+
+/// Record a liquidation penalty paid into the insurance fund (internal API)
+pub async fn settle_to_insurance(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<InsurancePenaltyRequest>,
+) -> Result<Json<TransactionResponse>, VaultServiceError> {
+    let program_id = Pubkey::from_str(&payload.program_id)
+        .map_err(|e| VaultServiceError::InvalidPublicKey(e))?;
+
+    state
+        .vault_manager
+        .record_insurance_penalty(
+            &payload.vault_pubkey,
+            &program_id,
+            payload.amount,
+            payload.signature.clone(),
+        )
+        .await?;
+
+    state
+        .balance_tracker
+        .monitor_vault(&payload.vault_pubkey)
+        .await?;
+
+    Ok(Json(TransactionResponse {
+        signature: payload.signature.unwrap_or_default(),
+        status: "settled".to_string(),
+    }))
+}
+
+/// Record a shortfall covered by the insurance fund (internal API)
+pub async fn cover_shortfall(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<CoverShortfallRequest>,
+) -> Result<Json<TransactionResponse>, VaultServiceError> {
+    let program_id = Pubkey::from_str(&payload.program_id)
+        .map_err(|e| VaultServiceError::InvalidPublicKey(e))?;
+
+    let uncovered = state
+        .vault_manager
+        .record_cover_shortfall(
+            &payload.to_vault,
+            &program_id,
+            payload.amount,
+            payload.signature.clone(),
+        )
+        .await?;
+
+    state
+        .balance_tracker
+        .monitor_vault(&payload.to_vault)
+        .await?;
+
+    Ok(Json(TransactionResponse {
+        signature: payload.signature.unwrap_or_default(),
+        status: if uncovered == 0 { "covered" } else { "partially_covered" }.to_string(),
+    }))
+}
+
+/// Get the insurance fund of a collateral mint and its history
+pub async fn get_insurance_fund(
+    State(state): State<Arc<AppState>>,
+    Path(mint): Path<String>,
+    Query(query): Query<TransactionHistoryQuery>,
+) -> Result<Json<InsuranceFundResponse>, VaultServiceError> {
+    let fund = state
+        .vault_manager
+        .get_insurance_fund(&mint, query.limit)
+        .await?;
+    Ok(Json(fund))
+}
+
+/// Mirror an on-chain vault freeze (admin API)
+pub async fn freeze_vault(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<FreezeVaultRequest>,
+) -> Result<Json<TransactionResponse>, VaultServiceError> {
+    state
+        .vault_manager
+        .set_vault_frozen(&payload.vault_pubkey, true)
+        .await?;
+
+    Ok(Json(TransactionResponse {
+        signature: payload.vault_pubkey,
+        status: "frozen".to_string(),
+    }))
+}
+
+/// Mirror an on-chain vault unfreeze (admin API)
+pub async fn unfreeze_vault(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<FreezeVaultRequest>,
+) -> Result<Json<TransactionResponse>, VaultServiceError> {
+    state
+        .vault_manager
+        .set_vault_frozen(&payload.vault_pubkey, false)
+        .await?;
+
+    Ok(Json(TransactionResponse {
+        signature: payload.vault_pubkey,
+        status: "active".to_string(),
+    }))
+}
+
+/// List open vaults that still need an on-chain `migrate_vault` (admin API)
+pub async fn get_unmigrated_vaults(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<UnmigratedVaultResponse>>, VaultServiceError> {
+    let vaults = state.vault_manager.get_unmigrated_vaults().await?;
+    Ok(Json(vaults))
+}
+
 /// Health check endpoint
 pub async fn health_check() -> impl IntoResponse {
     (StatusCode::OK, "OK")
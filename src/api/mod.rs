@@ -20,10 +20,20 @@ pub fn create_router(state: Arc<AppState>) -> Router {
         .route("/health", get(handlers::health_check))
         // Vault operations
         .route("/vault/initialize", post(handlers::initialize_vault))
+        .route("/vault/close", post(handlers::close_vault))
         .route("/vault/balance/:vault", get(handlers::get_vault_balance))
-        .route("/vault/owner/:owner", get(handlers::get_vault_by_owner))
+        .route("/vault/owner/:owner", get(handlers::get_vaults_by_owner))
+        .route("/vault/mints", get(handlers::get_collateral_mints))
         .route("/vault/deposit", post(handlers::record_deposit))
         .route("/vault/withdraw", post(handlers::record_withdrawal))
+        .route(
+            "/vault/sub-account/transfer",
+            post(handlers::transfer_between_sub_accounts),
+        )
+        .route("/vault/withdraw/request", post(handlers::request_withdrawal))
+        .route("/vault/withdraw/execute", post(handlers::execute_withdrawal))
+        .route("/vault/withdraw/cancel", post(handlers::cancel_withdrawal))
+        .route("/vault/pending/:vault", get(handlers::get_pending_withdrawals))
         .route(
             "/vault/transactions/:vault",
             get(handlers::get_transaction_history),
@@ -31,8 +41,34 @@ pub fn create_router(state: Arc<AppState>) -> Router {
         // Internal operations (for position manager)
         .route("/internal/lock", post(handlers::lock_collateral))
         .route("/internal/unlock", post(handlers::unlock_collateral))
+        .route("/internal/settle", post(handlers::settle_locked))
+        .route(
+            "/internal/insurance/settle",
+            post(handlers::settle_to_insurance),
+        )
+        .route("/internal/insurance/cover", post(handlers::cover_shortfall))
+        // Admin operations (mirror on-chain pause/freeze/handover/multisig)
+        .route("/admin/pause", post(handlers::pause))
+        .route("/admin/unpause", post(handlers::unpause))
+        .route("/admin/propose", post(handlers::propose_admin))
+        .route("/admin/accept", post(handlers::accept_admin))
+        .route("/admin/multisig", post(handlers::set_multisig))
+        .route("/admin/caps", post(handlers::set_deposit_caps))
+        .route("/admin/fee", post(handlers::set_fee))
+        .route("/admin/yield/index", post(handlers::initialize_yield_index))
+        .route("/admin/yield/distribute", post(handlers::distribute_yield))
+        .route("/admin/insurance/fund", post(handlers::initialize_insurance_fund))
+        .route("/admin/insurance/socialize", post(handlers::set_socialize_losses))
+        .route("/admin/insurance/fees", post(handlers::collect_fees_to_insurance))
+        .route("/admin/vault/freeze", post(handlers::freeze_vault))
+        .route("/admin/vault/unfreeze", post(handlers::unfreeze_vault))
+        .route("/admin/vault/unmigrated", get(handlers::get_unmigrated_vaults))
+        .route("/protocol/status", get(handlers::get_protocol_status))
         // Analytics
         .route("/analytics/tvl", get(handlers::get_tvl))
+        .route("/analytics/fees", get(handlers::get_fee_stats))
+        .route("/analytics/yield/:mint", get(handlers::get_yield_index))
+        .route("/analytics/insurance/:mint", get(handlers::get_insurance_fund))
         .layer(cors)
         .layer(TraceLayer::new_for_http())
         .with_state(state)
@@ -1,13 +1,19 @@
+use crate::config::Config;
 use crate::database::DatabaseManager;
 use crate::errors::Result;
-use crate::models::{BalanceSnapshot, SnapshotType, VaultDocument, WsMessage};
+use crate::models::{BalanceSnapshot, SnapshotType, TransactionType, VaultDocument, WsMessage};
 use chrono::Utc;
 use solana_client::rpc_client::RpcClient;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::broadcast;
 use tokio::time::{interval, Duration};
 
+/// Share of a withdrawal limit (in percent) at which a warning is raised
+const WITHDRAWAL_LIMIT_WARNING_PCT: u64 = 80;
+
 pub struct BalanceTracker {
+    config: Arc<Config>,
     db: Arc<DatabaseManager>,
     rpc_client: Arc<RpcClient>,
     ws_sender: broadcast::Sender<WsMessage>,
@@ -15,11 +21,13 @@ pub struct BalanceTracker {
 
 impl BalanceTracker {
     pub fn new(
+        config: Arc<Config>,
         db: Arc<DatabaseManager>,
         rpc_client: Arc<RpcClient>,
         ws_sender: broadcast::Sender<WsMessage>,
     ) -> Self {
         Self {
+            config,
             db,
             rpc_client,
             ws_sender,
@@ -51,6 +59,9 @@ impl BalanceTracker {
                         if let Err(e) = tracker.reconcile_balances().await {
                             log::error!("Failed to reconcile balances: {}", e);
                         }
+                        if let Err(e) = tracker.check_withdrawal_limits().await {
+                            log::error!("Failed to check withdrawal limits: {}", e);
+                        }
                     }
                 }
             }
@@ -137,16 +148,98 @@ impl BalanceTracker {
         Ok(())
     }
 
+    /// Warn when a vault, or the protocol as a whole, has used most of its
+    /// on-chain withdrawal rate limit. Mirrors the program's rolling window:
+    /// withdrawals fall into buckets counted from `withdrawal_window_start`,
+    /// and the previous bucket counts for the part that still overlaps the
+    /// last `withdrawal_window_secs` seconds, rounded up. Withdrawals from
+    /// before the window length changed are not counted.
+    async fn check_withdrawal_limits(&self) -> Result<()> {
+        let program = &self.config.vault_program;
+        let window = program.withdrawal_window_secs;
+        if window == 0 {
+            return Ok(());
+        }
+
+        let now = Utc::now().timestamp();
+        let origin = program.withdrawal_window_start;
+        let bucket_start = now - (now - origin).rem_euclid(window);
+        let overlap = window - (now - bucket_start);
+        let since = (bucket_start - window).max(origin);
+        let Some(since) = chrono::DateTime::from_timestamp(since, 0) else {
+            return Ok(());
+        };
+        let withdrawals = self
+            .db
+            .get_confirmed_transactions(TransactionType::Withdrawal, Some(since))
+            .await?;
+
+        // (current bucket, previous bucket) per vault
+        let mut per_vault: HashMap<String, (u64, u64)> = HashMap::new();
+        for withdrawal in &withdrawals {
+            let buckets = per_vault.entry(withdrawal.vault.clone()).or_default();
+            if withdrawal.timestamp.timestamp() >= bucket_start {
+                buckets.0 += withdrawal.amount;
+            } else {
+                buckets.1 += withdrawal.amount;
+            }
+        }
+        let rolling = |(current, previous): (u64, u64)| {
+            let carried = (previous as u128 * overlap as u128).div_ceil(window as u128);
+            current.saturating_add(carried as u64)
+        };
+
+        let total = per_vault.values().fold((0u64, 0u64), |total, buckets| {
+            (total.0 + buckets.0, total.1 + buckets.1)
+        });
+
+        if program.max_vault_withdrawal > 0 {
+            for (vault, buckets) in per_vault {
+                let withdrawn = rolling(buckets);
+                self.warn_if_near_limit(Some(vault), withdrawn, program.max_vault_withdrawal);
+            }
+        }
+        if program.max_global_withdrawal > 0 {
+            self.warn_if_near_limit(None, rolling(total), program.max_global_withdrawal);
+        }
+
+        Ok(())
+    }
+
+    fn warn_if_near_limit(&self, vault: Option<String>, withdrawn: u64, limit: u64) {
+        if withdrawn.saturating_mul(100) < limit.saturating_mul(WITHDRAWAL_LIMIT_WARNING_PCT) {
+            return;
+        }
+
+        log::warn!(
+            "Withdrawal rate limit nearly reached for {}: {} of {} in the rolling {}s window",
+            vault.as_deref().unwrap_or("protocol"),
+            withdrawn,
+            limit,
+            self.config.vault_program.withdrawal_window_secs
+        );
+
+        let _ = self.ws_sender.send(WsMessage::WithdrawalLimitWarning {
+            vault,
+            withdrawn,
+            limit,
+            window_secs: self.config.vault_program.withdrawal_window_secs,
+        });
+    }
+
     /// Validate vault balance consistency
     fn validate_vault_balance(&self, vault: &VaultDocument) -> bool {
-        // Check that total = locked + available
-        if vault.total_balance != vault.locked_balance + vault.available_balance {
+        // Check that total = locked + available + pending
+        if vault.total_balance
+            != vault.locked_balance + vault.available_balance + vault.pending_balance
+        {
             log::error!(
-                "Vault {} balance mismatch: total={}, locked={}, available={}",
+                "Vault {} balance mismatch: total={}, locked={}, available={}, pending={}",
                 vault.id,
                 vault.total_balance,
                 vault.locked_balance,
-                vault.available_balance
+                vault.available_balance,
+                vault.pending_balance
             );
             return false;
         }
@@ -165,6 +258,7 @@ impl BalanceTracker {
         // Broadcast current balance
         let _ = self.ws_sender.send(WsMessage::BalanceUpdate {
             vault: vault.id.clone(),
+            mint: vault.mint.clone(),
             total_balance: vault.total_balance,
             locked_balance: vault.locked_balance,
             available_balance: vault.available_balance,
@@ -234,6 +328,7 @@ impl BalanceTracker {
 impl Clone for BalanceTracker {
     fn clone(&self) -> Self {
         Self {
+            config: Arc::clone(&self.config),
             db: Arc::clone(&self.db),
             rpc_client: Arc::clone(&self.rpc_client),
             ws_sender: self.ws_sender.clone(),
@@ -31,7 +31,57 @@ pub struct ServerConfig {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VaultProgramConfig {
     pub program_id: String,
-    pub usdt_mint: String,
+    /// Allow-list of collateral mints vaults can be opened for
+    pub collateral_mints: Vec<MintConfig>,
+    /// Mirror of the on-chain withdrawal timelock in seconds (0 = instant withdrawals)
+    pub withdrawal_delay_secs: i64,
+    /// Mirror of the on-chain withdrawal rate-limit window in seconds (0 = no rate limit)
+    pub withdrawal_window_secs: i64,
+    /// Mirror of the on-chain `withdrawal_window_start`, the unix time the
+    /// window length was last changed
+    pub withdrawal_window_start: i64,
+    /// Mirror of the on-chain per-vault withdrawal limit per window (0 = no limit)
+    pub max_vault_withdrawal: u64,
+    /// Mirror of the on-chain global withdrawal limit per window (0 = no limit)
+    pub max_global_withdrawal: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MintConfig {
+    pub symbol: String,
+    pub address: String,
+    /// Token program that owns the mint (SPL Token or Token-2022)
+    pub token_program: String,
+}
+
+impl MintConfig {
+    /// Parse `SYMBOL:address[:token_program],...` (e.g. `USDT:Es9v...,PYUSD:2b1k...:TokenzQd...`).
+    /// The token program defaults to SPL Token.
+    fn parse_list(value: &str) -> Result<Vec<Self>, Box<dyn std::error::Error>> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let mut parts = entry.split(':').map(str::trim);
+                let (Some(symbol), Some(address)) = (parts.next(), parts.next()) else {
+                    return Err(format!("Invalid collateral mint entry: {}", entry).into());
+                };
+                let token_program = parts
+                    .next()
+                    .map(str::to_string)
+                    .unwrap_or_else(|| spl_token::ID.to_string());
+                if parts.next().is_some() {
+                    return Err(format!("Invalid collateral mint entry: {}", entry).into());
+                }
+                Ok(MintConfig {
+                    symbol: symbol.to_string(),
+                    address: address.to_string(),
+                    token_program,
+                })
+            })
+            .collect()
+    }
 }
 
 impl Config {
@@ -63,9 +113,37 @@ impl Config {
             },
             vault_program: VaultProgramConfig {
                 program_id: env::var("VAULT_PROGRAM_ID")
-                    .unwrap_or_else(|_| "VAULTmngr11111111111111111111111111111111".to_string()),
-                usdt_mint: env::var("USDT_MINT")
-                    .expect("USDT_MINT must be set"),
+                    .unwrap_or_else(|_| "Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS".to_string()),
+                collateral_mints: match env::var("COLLATERAL_MINTS") {
+                    Ok(mints) => MintConfig::parse_list(&mints)?,
+                    // Single-mint deployments only configure USDT
+                    Err(_) => vec![MintConfig {
+                        symbol: "USDT".to_string(),
+                        address: env::var("USDT_MINT")
+                            .expect("COLLATERAL_MINTS or USDT_MINT must be set"),
+                        token_program: spl_token::ID.to_string(),
+                    }],
+                },
+                withdrawal_delay_secs: env::var("WITHDRAWAL_DELAY_SECS")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
+                withdrawal_window_secs: env::var("WITHDRAWAL_WINDOW_SECS")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
+                withdrawal_window_start: env::var("WITHDRAWAL_WINDOW_START")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
+                max_vault_withdrawal: env::var("MAX_VAULT_WITHDRAWAL")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
+                max_global_withdrawal: env::var("MAX_GLOBAL_WITHDRAWAL")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
             },
         })
     }
@@ -36,7 +36,7 @@ impl DatabaseManager {
         vaults
             .create_index(
                 IndexModel::builder()
-                    .keys(doc! { "owner": 1 })
+                    .keys(doc! { "owner": 1, "mint": 1 })
                     .options(IndexOptions::builder().unique(false).build())
                     .build(),
                 None,
@@ -64,6 +64,42 @@ impl DatabaseManager {
             )
             .await?;
 
+        // Program locks indexes
+        let program_locks: Collection<ProgramLockDocument> = self.db.collection("program_locks");
+        program_locks
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "vault": 1, "program": 1 })
+                    .options(IndexOptions::builder().unique(true).build())
+                    .build(),
+                None,
+            )
+            .await?;
+
+        // Pending withdrawals indexes
+        let pending_withdrawals: Collection<PendingWithdrawalDocument> =
+            self.db.collection("pending_withdrawals");
+        pending_withdrawals
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "vault": 1, "status": 1 })
+                    .build(),
+                None,
+            )
+            .await?;
+
+        // Insurance fund history indexes
+        let insurance_events: Collection<InsuranceEventDocument> =
+            self.db.collection("insurance_events");
+        insurance_events
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "mint": 1, "timestamp": -1 })
+                    .build(),
+                None,
+            )
+            .await?;
+
         // Balance snapshots indexes
         let snapshots: Collection<BalanceSnapshot> = self.db.collection("balance_snapshots");
         snapshots
@@ -105,12 +141,15 @@ impl DatabaseManager {
         Ok(vault)
     }
 
-    pub async fn get_vault_by_owner(&self, owner_pubkey: &str) -> Result<Option<VaultDocument>> {
+    pub async fn get_vaults_by_owner(&self, owner_pubkey: &str) -> Result<Vec<VaultDocument>> {
+        use futures::stream::TryStreamExt;
+
         let collection: Collection<VaultDocument> = self.db.collection("vaults");
-        let vault = collection
-            .find_one(doc! { "owner": owner_pubkey }, None)
+        let cursor = collection
+            .find(doc! { "owner": owner_pubkey }, None)
             .await?;
-        Ok(vault)
+        let vaults: Vec<VaultDocument> = cursor.try_collect().await?;
+        Ok(vaults)
     }
 
     pub async fn update_vault_balance(
@@ -173,6 +212,84 @@ impl DatabaseManager {
         Ok(())
     }
 
+    pub async fn update_vault_pending_balance(
+        &self,
+        vault_pubkey: &str,
+        total_balance: u64,
+        available_balance: u64,
+        pending_balance: u64,
+        total_withdrawn: Option<u64>,
+    ) -> Result<()> {
+        let collection: Collection<VaultDocument> = self.db.collection("vaults");
+
+        let mut update_doc = doc! {
+            "total_balance": total_balance as i64,
+            "available_balance": available_balance as i64,
+            "pending_balance": pending_balance as i64,
+            "last_updated": Utc::now(),
+        };
+
+        if let Some(withdrawn) = total_withdrawn {
+            update_doc.insert("total_withdrawn", withdrawn as i64);
+        }
+
+        collection
+            .update_one(
+                doc! { "_id": vault_pubkey },
+                doc! { "$set": update_doc },
+                None,
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn update_vault_status(&self, vault_pubkey: &str, status: VaultStatus) -> Result<()> {
+        let collection: Collection<VaultDocument> = self.db.collection("vaults");
+        collection
+            .update_one(
+                doc! { "_id": vault_pubkey },
+                doc! {
+                    "$set": {
+                        "status": bson::to_bson(&status)?,
+                        "last_updated": Utc::now(),
+                    }
+                },
+                None,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Record the on-chain sequence of the last mutation mirrored for a vault
+    pub async fn update_vault_sequence(&self, vault_pubkey: &str, sequence: u64) -> Result<()> {
+        let collection: Collection<VaultDocument> = self.db.collection("vaults");
+        collection
+            .update_one(
+                doc! { "_id": vault_pubkey },
+                doc! { "$set": { "last_sequence": sequence as i64 } },
+                None,
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn replace_vault(&self, vault: VaultDocument) -> Result<()> {
+        let collection: Collection<VaultDocument> = self.db.collection("vaults");
+        collection
+            .replace_one(doc! { "_id": &vault.id }, vault, None)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_vaults_by_mint(&self, mint: &str) -> Result<Vec<VaultDocument>> {
+        use futures::stream::TryStreamExt;
+
+        let collection: Collection<VaultDocument> = self.db.collection("vaults");
+        let cursor = collection.find(doc! { "mint": mint }, None).await?;
+        let vaults: Vec<VaultDocument> = cursor.try_collect().await?;
+        Ok(vaults)
+    }
+
     pub async fn get_all_vaults(&self) -> Result<Vec<VaultDocument>> {
         use futures::stream::TryStreamExt;
 
@@ -182,6 +299,203 @@ impl DatabaseManager {
         Ok(vaults)
     }
 
+    // ============ Program Lock Operations ============
+
+    pub async fn get_program_lock(
+        &self,
+        vault_pubkey: &str,
+        program_id: &str,
+    ) -> Result<Option<ProgramLockDocument>> {
+        let collection: Collection<ProgramLockDocument> = self.db.collection("program_locks");
+        let lock = collection
+            .find_one(doc! { "vault": vault_pubkey, "program": program_id }, None)
+            .await?;
+        Ok(lock)
+    }
+
+    pub async fn get_vault_program_locks(
+        &self,
+        vault_pubkey: &str,
+    ) -> Result<Vec<ProgramLockDocument>> {
+        use futures::stream::TryStreamExt;
+
+        let collection: Collection<ProgramLockDocument> = self.db.collection("program_locks");
+        let cursor = collection
+            .find(doc! { "vault": vault_pubkey }, None)
+            .await?;
+        let locks: Vec<ProgramLockDocument> = cursor.try_collect().await?;
+        Ok(locks)
+    }
+
+    pub async fn upsert_program_lock(&self, lock: ProgramLockDocument) -> Result<()> {
+        use mongodb::options::ReplaceOptions;
+
+        let collection: Collection<ProgramLockDocument> = self.db.collection("program_locks");
+        collection
+            .replace_one(
+                doc! { "_id": &lock.id },
+                lock,
+                ReplaceOptions::builder().upsert(true).build(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    // ============ Protocol State Operations ============
+
+    pub async fn get_protocol_state(&self) -> Result<Option<ProtocolStateDocument>> {
+        let collection: Collection<ProtocolStateDocument> = self.db.collection("protocol_state");
+        let state = collection.find_one(doc! { "_id": "global" }, None).await?;
+        Ok(state)
+    }
+
+    pub async fn save_protocol_state(&self, state: ProtocolStateDocument) -> Result<()> {
+        use mongodb::options::ReplaceOptions;
+
+        let collection: Collection<ProtocolStateDocument> = self.db.collection("protocol_state");
+        collection
+            .replace_one(
+                doc! { "_id": &state.id },
+                state,
+                ReplaceOptions::builder().upsert(true).build(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    // ============ Yield Index Operations ============
+
+    pub async fn get_yield_index(&self, mint: &str) -> Result<Option<YieldIndexDocument>> {
+        let collection: Collection<YieldIndexDocument> = self.db.collection("yield_indexes");
+        let index = collection.find_one(doc! { "_id": mint }, None).await?;
+        Ok(index)
+    }
+
+    pub async fn save_yield_index(&self, index: YieldIndexDocument) -> Result<()> {
+        use mongodb::options::ReplaceOptions;
+
+        let collection: Collection<YieldIndexDocument> = self.db.collection("yield_indexes");
+        collection
+            .replace_one(
+                doc! { "_id": &index.id },
+                index,
+                ReplaceOptions::builder().upsert(true).build(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    // ============ Insurance Fund Operations ============
+
+    pub async fn get_insurance_fund(&self, mint: &str) -> Result<Option<InsuranceFundDocument>> {
+        let collection: Collection<InsuranceFundDocument> = self.db.collection("insurance_funds");
+        let fund = collection.find_one(doc! { "_id": mint }, None).await?;
+        Ok(fund)
+    }
+
+    pub async fn save_insurance_fund(&self, fund: InsuranceFundDocument) -> Result<()> {
+        use mongodb::options::ReplaceOptions;
+
+        let collection: Collection<InsuranceFundDocument> = self.db.collection("insurance_funds");
+        collection
+            .replace_one(
+                doc! { "_id": &fund.id },
+                fund,
+                ReplaceOptions::builder().upsert(true).build(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn insert_insurance_event(&self, event: InsuranceEventDocument) -> Result<()> {
+        let collection: Collection<InsuranceEventDocument> =
+            self.db.collection("insurance_events");
+        collection.insert_one(event, None).await?;
+        Ok(())
+    }
+
+    pub async fn get_insurance_events(
+        &self,
+        mint: &str,
+        limit: i64,
+    ) -> Result<Vec<InsuranceEventDocument>> {
+        use futures::stream::TryStreamExt;
+        use mongodb::options::FindOptions;
+
+        let collection: Collection<InsuranceEventDocument> =
+            self.db.collection("insurance_events");
+        let options = FindOptions::builder()
+            .sort(doc! { "timestamp": -1 })
+            .limit(limit)
+            .build();
+
+        let cursor = collection.find(doc! { "mint": mint }, options).await?;
+        let events: Vec<InsuranceEventDocument> = cursor.try_collect().await?;
+        Ok(events)
+    }
+
+    // ============ Pending Withdrawal Operations ============
+
+    pub async fn insert_pending_withdrawal(
+        &self,
+        withdrawal: PendingWithdrawalDocument,
+    ) -> Result<()> {
+        let collection: Collection<PendingWithdrawalDocument> =
+            self.db.collection("pending_withdrawals");
+        collection.insert_one(withdrawal, None).await?;
+        Ok(())
+    }
+
+    /// Get the outstanding withdrawal of a vault (at most one exists on-chain)
+    pub async fn get_open_pending_withdrawal(
+        &self,
+        vault_pubkey: &str,
+    ) -> Result<Option<PendingWithdrawalDocument>> {
+        let collection: Collection<PendingWithdrawalDocument> =
+            self.db.collection("pending_withdrawals");
+        let withdrawal = collection
+            .find_one(doc! { "vault": vault_pubkey, "status": "pending" }, None)
+            .await?;
+        Ok(withdrawal)
+    }
+
+    pub async fn get_pending_withdrawals(
+        &self,
+        vault_pubkey: &str,
+    ) -> Result<Vec<PendingWithdrawalDocument>> {
+        use futures::stream::TryStreamExt;
+        use mongodb::options::FindOptions;
+
+        let collection: Collection<PendingWithdrawalDocument> =
+            self.db.collection("pending_withdrawals");
+        let options = FindOptions::builder()
+            .sort(doc! { "executable_at": 1 })
+            .build();
+
+        let cursor = collection
+            .find(doc! { "vault": vault_pubkey, "status": "pending" }, options)
+            .await?;
+        let withdrawals: Vec<PendingWithdrawalDocument> = cursor.try_collect().await?;
+        Ok(withdrawals)
+    }
+
+    pub async fn update_pending_withdrawal_status(
+        &self,
+        id: &str,
+        status: PendingWithdrawalStatus,
+    ) -> Result<()> {
+        let collection: Collection<PendingWithdrawalDocument> =
+            self.db.collection("pending_withdrawals");
+        collection
+            .update_one(
+                doc! { "_id": id },
+                doc! { "$set": { "status": bson::to_bson(&status)? } },
+                None,
+            )
+            .await?;
+        Ok(())
+    }
+
     // ============ Transaction Operations ============
 
     pub async fn insert_transaction(&self, transaction: TransactionDocument) -> Result<()> {
@@ -241,6 +555,28 @@ impl DatabaseManager {
         Ok(transactions)
     }
 
+    /// Confirmed transactions of one type across all vaults, optionally only
+    /// those since a given time
+    pub async fn get_confirmed_transactions(
+        &self,
+        transaction_type: TransactionType,
+        since: Option<chrono::DateTime<Utc>>,
+    ) -> Result<Vec<TransactionDocument>> {
+        use futures::stream::TryStreamExt;
+
+        let collection: Collection<TransactionDocument> = self.db.collection("transactions");
+        let mut filter = doc! {
+            "transaction_type": bson::to_bson(&transaction_type)?,
+            "status": bson::to_bson(&TransactionStatus::Confirmed)?,
+        };
+        if let Some(since) = since {
+            filter.insert("timestamp", doc! { "$gte": bson::to_bson(&since)? });
+        }
+        let cursor = collection.find(filter, None).await?;
+        let transactions: Vec<TransactionDocument> = cursor.try_collect().await?;
+        Ok(transactions)
+    }
+
     // ============ Balance Snapshot Operations ============
 
     pub async fn insert_snapshot(&self, snapshot: BalanceSnapshot) -> Result<()> {
@@ -299,7 +635,11 @@ impl DatabaseManager {
         use futures::stream::TryStreamExt;
 
         let collection: Collection<VaultDocument> = self.db.collection("vaults");
-        let vaults: Vec<VaultDocument> = collection.find(None, None).await?.try_collect().await?;
+        let vaults: Vec<VaultDocument> = collection
+            .find(doc! { "status": { "$ne": "closed" } }, None)
+            .await?
+            .try_collect()
+            .await?;
 
         let mut total_tvl = 0u64;
         let mut total_locked = 0u64;
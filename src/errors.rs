@@ -26,6 +26,30 @@ pub enum VaultServiceError {
     #[error("Vault not found: {0}")]
     VaultNotFound(String),
 
+    #[error("Vault is not empty: {0}")]
+    VaultNotEmpty(String),
+
+    #[error("Unsupported collateral mint: {0}")]
+    UnsupportedMint(String),
+
+    #[error("Withdrawal timelock: {0}")]
+    WithdrawalTimelock(String),
+
+    #[error("Protocol is paused (reason code {0})")]
+    ProtocolPaused(u8),
+
+    #[error("Vault is frozen: {0}")]
+    VaultFrozen(String),
+
+    #[error("Not yet applied on-chain: {0}")]
+    OnChainMismatch(String),
+
+    #[error("Invalid multisig: {0}")]
+    InvalidMultisig(String),
+
+    #[error("Deposit cap exceeded: {0}")]
+    DepositCapExceeded(String),
+
     #[error("Insufficient balance: available={0}, required={1}")]
     InsufficientBalance(u64, u64),
 
@@ -55,6 +55,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Initialize balance tracker
     let balance_tracker = Arc::new(BalanceTracker::new(
+        Arc::clone(&config),
         Arc::clone(&db),
         Arc::clone(&rpc_client),
         ws_sender.clone(),
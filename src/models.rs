@@ -8,26 +8,140 @@ pub struct VaultDocument {
     #[serde(rename = "_id")]
     pub id: String, // vault pubkey as string
     pub owner: String, // owner pubkey as string
+    #[serde(default)]
+    pub mint: String, // collateral mint pubkey as string
+    #[serde(default)]
+    pub sub_account: u16, // index of the owner's vault for this mint
     pub token_account: String,
     pub total_balance: u64,
     pub locked_balance: u64,
     pub available_balance: u64,
+    #[serde(default)]
+    pub pending_balance: u64, // reserved by a timelocked withdrawal
     pub total_deposited: u64,
     pub total_withdrawn: u64,
     pub created_at: DateTime<Utc>,
     pub last_updated: DateTime<Utc>,
     pub bump: u8,
     pub status: VaultStatus,
+    #[serde(default)]
+    pub last_sequence: Option<u64>, // on-chain sequence last mirrored; None until first seen
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum VaultStatus {
     Active,
-    Suspended,
+    Suspended, // frozen on-chain by the admin
     Closed,
 }
 
+/// Mirror of the on-chain pause flag and admin keys on `VaultAuthority`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtocolStateDocument {
+    #[serde(rename = "_id")]
+    pub id: String, // singleton, always "global"
+    pub paused: bool,
+    pub pause_reason: u8,
+    #[serde(default)]
+    pub admin: Option<String>,
+    #[serde(default)]
+    pub pending_admin: Option<String>, // proposed, not yet accepted
+    #[serde(default)]
+    pub multisig_signers: Vec<String>, // empty unless the admin is the multisig PDA
+    #[serde(default)]
+    pub multisig_threshold: u8,
+    #[serde(default)]
+    pub max_vault_balance: u64, // 0 = no cap
+    #[serde(default)]
+    pub max_tvl: u64, // 0 = no cap
+    #[serde(default)]
+    pub fee_bps: u16, // protocol fee on withdrawals and transfers
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Mirror of the on-chain `YieldIndex` of a collateral mint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct YieldIndexDocument {
+    #[serde(rename = "_id")]
+    pub id: String, // mint pubkey as string
+    pub index: u64, // token value of one share, scaled by 1e9
+    pub include_locked: bool, // whether locked collateral earns yield
+    pub total_distributed: u64,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Mirror of the on-chain `InsuranceFund` of a collateral mint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InsuranceFundDocument {
+    #[serde(rename = "_id")]
+    pub id: String, // mint pubkey as string
+    pub address: String, // insurance fund PDA as string
+    pub socialize_losses: bool, // haircut vaults when the fund runs short
+    pub balance: u64, // tokens held by the fund's token account
+    pub total_contributed: u64,
+    pub total_covered: u64, // paid out of the fund
+    pub total_socialized: u64, // haircut from vaults' available balances
+    pub updated_at: DateTime<Utc>,
+}
+
+/// One movement into or out of an insurance fund, for its history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InsuranceEventDocument {
+    #[serde(rename = "_id")]
+    pub id: String, // UUID
+    pub mint: String,
+    pub event_type: InsuranceEventType,
+    pub amount: u64,
+    pub vault: Option<String>, // penalized vault, or vault a shortfall was owed to
+    pub program: Option<String>, // authorized program behind a penalty or cover
+    pub socialized: u64, // part of a cover haircut from vaults
+    pub uncovered: u64, // part of a cover left unpaid
+    pub fund_balance: u64, // fund balance afterwards
+    pub signature: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum InsuranceEventType {
+    Fees, // treasury sweep by `collect_fees_to_insurance`
+    Penalty, // locked collateral seized by `settle_to_insurance`
+    Cover, // shortfall paid by `cover_shortfall`
+}
+
+/// Collateral locked in a vault by a single authorized program
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgramLockDocument {
+    #[serde(rename = "_id")]
+    pub id: String, // program lock PDA as string
+    pub vault: String,
+    pub program: String,
+    pub locked_amount: u64,
+    pub last_updated: DateTime<Utc>,
+}
+
+/// Timelocked withdrawal requested on-chain with `request_withdrawal`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingWithdrawalDocument {
+    #[serde(rename = "_id")]
+    pub id: String, // UUID; the PDA is reused by later requests
+    pub address: String, // pending withdrawal PDA as string
+    pub vault: String,
+    pub amount: u64,
+    pub requested_at: DateTime<Utc>,
+    pub executable_at: DateTime<Utc>,
+    pub status: PendingWithdrawalStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PendingWithdrawalStatus {
+    Pending,
+    Executed,
+    Cancelled,
+}
+
 /// Transaction record for MongoDB storage
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransactionDocument {
@@ -42,6 +156,12 @@ pub struct TransactionDocument {
     pub to_vault: Option<String>,
     pub status: TransactionStatus,
     pub error_message: Option<String>,
+    /// Depositor of a `deposit_for` into someone else's vault
+    #[serde(default)]
+    pub payer: Option<String>,    /// On-chain `CollateralVault::sequence` after this transaction; `None`
+    /// for legacy-layout vaults and records without their own vault mutation
+    #[serde(default)]
+    pub sequence: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -52,6 +172,21 @@ pub enum TransactionType {
     Lock,
     Unlock,
     Transfer,
+    Settlement,
+    Fee, // protocol fee paid to the treasury out of a withdrawal or transfer
+    Yield, // share of a `distribute_yield` credited to the vault
+    Insurance, // liquidation penalty paid into the insurance fund
+    Coverage, // bad debt credited by `cover_shortfall`
+    Haircut, // socialized loss debited from available collateral
+}
+
+/// Reason code carried by on-chain `SettlementEvent`s
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SettlementReason {
+    Settlement,
+    Liquidation,
+    LiquidationPenalty,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -114,29 +249,76 @@ pub struct TvlStats {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct InitializeVaultRequest {
     pub user_pubkey: String,
+    pub mint: String,
+    #[serde(default)]
+    pub sub_account: u16,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CloseVaultRequest {
+    pub user_pubkey: String,
+    pub mint: String,
+    #[serde(default)]
+    pub sub_account: u16,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DepositRequest {
     pub user_pubkey: String,
+    pub mint: String,
+    #[serde(default)]
+    pub sub_account: u16,
     pub amount: u64,
+    /// Wallet that paid a `deposit_for` into `user_pubkey`'s vault
+    #[serde(default)]
+    pub payer: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct WithdrawRequest {
     pub user_pubkey: String,
+    pub mint: String,
+    #[serde(default)]
+    pub sub_account: u16,
     pub amount: u64,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RequestWithdrawalRequest {
+    pub user_pubkey: String,
+    pub mint: String,
+    #[serde(default)]
+    pub sub_account: u16,
+    pub amount: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExecuteWithdrawalRequest {
+    pub user_pubkey: String,
+    pub mint: String,
+    #[serde(default)]
+    pub sub_account: u16,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CancelWithdrawalRequest {
+    pub user_pubkey: String,
+    pub mint: String,
+    #[serde(default)]
+    pub sub_account: u16,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LockCollateralRequest {
     pub vault_pubkey: String,
+    pub program_id: String,
     pub amount: u64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UnlockCollateralRequest {
     pub vault_pubkey: String,
+    pub program_id: String,
     pub amount: u64,
 }
 
@@ -147,15 +329,169 @@ pub struct TransferCollateralRequest {
     pub amount: u64,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubAccountTransferRequest {
+    pub user_pubkey: String,
+    pub mint: String,
+    pub from_sub_account: u16,
+    pub to_sub_account: u16,
+    pub amount: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SettleLockedRequest {
+    pub from_vault: String,
+    pub to_vault: String,
+    pub program_id: String,
+    pub amount: u64,
+    pub reason: SettlementReason,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PauseRequest {
+    #[serde(default)]
+    pub reason: u8,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AdminHandoverRequest {
+    pub new_admin: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MultisigRequest {
+    pub signers: Vec<String>,
+    pub threshold: u8,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DepositCapsRequest {
+    pub max_vault_balance: u64,
+    pub max_tvl: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FeeRequest {
+    pub fee_bps: u16,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct YieldIndexRequest {
+    pub mint: String,
+    #[serde(default)]
+    pub include_locked: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DistributeYieldRequest {
+    pub mint: String,
+    pub amount: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InsuranceFundRequest {
+    pub mint: String,
+    #[serde(default)]
+    pub socialize_losses: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InsuranceFeesRequest {
+    pub mint: String,
+    pub amount: u64, // tokens the fund received from the treasury sweep
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InsurancePenaltyRequest {
+    pub vault_pubkey: String,
+    pub program_id: String,
+    pub amount: u64,
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CoverShortfallRequest {
+    pub to_vault: String,
+    pub program_id: String,
+    pub amount: u64,
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FreezeVaultRequest {
+    pub vault_pubkey: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProtocolStatusResponse {
+    pub paused: bool,
+    pub pause_reason: u8,
+    pub admin: Option<String>,
+    pub pending_admin: Option<String>,
+    pub multisig_signers: Vec<String>,
+    pub multisig_threshold: u8,
+    pub max_vault_balance: u64,
+    pub max_tvl: u64,
+    pub fee_bps: u16,
+    pub updated_at: String,
+}
+
+/// An open vault whose on-chain account has not been migrated to the current layout
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UnmigratedVaultResponse {
+    pub vault: String,
+    pub owner: String,
+    pub mint: String,
+    pub sub_account: u16,
+    /// Layout version of the on-chain account (0 = no version field)
+    pub version: u8,
+    pub current_version: u8,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct VaultBalanceResponse {
     pub vault: String,
     pub owner: String,
+    pub mint: String,
+    pub sub_account: u16,
+    pub status: VaultStatus,
     pub total_balance: u64,
     pub locked_balance: u64,
     pub available_balance: u64,
+    pub pending_balance: u64,
     pub total_deposited: u64,
     pub total_withdrawn: u64,
+    pub yield_shares: u64,
+    pub yield_share_value: u64, // token amount the shares are worth at `yield_index`
+    pub yield_index: u64, // scaled by 1e9
+    pub program_locks: Vec<ProgramLockBalance>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProgramLockBalance {
+    pub program: String,
+    pub locked_amount: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PendingWithdrawalResponse {
+    pub pending_withdrawal: String,
+    pub vault: String,
+    pub amount: u64,
+    pub requested_at: String,
+    pub executable_at: String,
+    pub matured: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CollateralMintResponse {
+    pub symbol: String,
+    pub mint: String,
+    pub token_program: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -164,12 +500,47 @@ pub struct TransactionResponse {
     pub status: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FeeStatsResponse {
+    pub fee_bps: u16,
+    pub total_fees: u64,
+    pub fee_count: u64,
+    pub fees_24h: u64,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct YieldIndexResponse {
+    pub mint: String,
+    pub index: u64, // scaled by 1e9
+    pub include_locked: bool,
+    pub total_shares: u64,
+    pub total_distributed: u64,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InsuranceFundResponse {
+    pub mint: String,
+    pub address: String,
+    pub socialize_losses: bool,
+    pub balance: u64,
+    pub total_contributed: u64,
+    pub total_covered: u64,
+    pub total_socialized: u64,
+    pub updated_at: String,
+    pub history: Vec<InsuranceEventDocument>, // most recent first
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TvlResponse {
     pub total_tvl: u64,
     pub total_locked: u64,
     pub total_available: u64,
     pub vault_count: u64,
+    pub max_tvl: u64, // 0 = no cap
+    pub tvl_headroom: Option<u64>, // None when the TVL is uncapped
+    pub max_vault_balance: u64, // 0 = no cap
     pub timestamp: String,
 }
 
@@ -187,6 +558,7 @@ pub enum WsMessage {
     #[serde(rename = "balance_update")]
     BalanceUpdate {
         vault: String,
+        mint: String,
         total_balance: u64,
         locked_balance: u64,
         available_balance: u64,
@@ -194,23 +566,27 @@ pub enum WsMessage {
     #[serde(rename = "deposit")]
     Deposit {
         vault: String,
+        mint: String,
         amount: u64,
         signature: String,
     },
     #[serde(rename = "withdrawal")]
     Withdrawal {
         vault: String,
+        mint: String,
         amount: u64,
         signature: String,
     },
     #[serde(rename = "lock")]
     Lock {
         vault: String,
+        mint: String,
         amount: u64,
     },
     #[serde(rename = "unlock")]
     Unlock {
         vault: String,
+        mint: String,
         amount: u64,
     },
     #[serde(rename = "tvl_update")]
@@ -218,6 +594,13 @@ pub enum WsMessage {
         total_tvl: u64,
         vault_count: u64,
     },
+    #[serde(rename = "withdrawal_limit_warning")]
+    WithdrawalLimitWarning {
+        vault: Option<String>, // None for the global limit
+        withdrawn: u64,
+        limit: u64,
+        window_secs: i64,
+    },
     #[serde(rename = "error")]
     Error {
         message: String,
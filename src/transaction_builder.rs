@@ -5,12 +5,35 @@ use solana_sdk::{
     commitment_config::CommitmentConfig,
     compute_budget::ComputeBudgetInstruction,
     instruction::Instruction,
+    message::Message,
+    packet::PACKET_DATA_SIZE,
     pubkey::Pubkey,
     signature::{Keypair, Signature, Signer},
     transaction::Transaction,
 };
 use std::sync::Arc;
 
+/// Compute unit limit requested for single-instruction transactions
+const DEFAULT_COMPUTE_UNITS: u32 = 300_000;
+
+/// Most compute units a transaction can request
+const MAX_COMPUTE_UNITS: u32 = 1_400_000;
+
+/// Compute units of a `batch_lock`/`batch_unlock` outside its entries
+/// (account validation, CPI from the caller program)
+const BATCH_BASE_COMPUTE_UNITS: u32 = 30_000;
+
+/// Compute units per batch entry, including a first lock creating the
+/// program lock ledger
+const BATCH_ENTRY_COMPUTE_UNITS: u32 = 25_000;
+
+/// One vault of a `batch_lock` / `batch_unlock`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchEntry {
+    pub vault: Pubkey,
+    pub amount: u64,
+}
+
 pub struct TransactionBuilder {
     rpc_client: Arc<RpcClient>,
     config: Arc<Config>,
@@ -26,12 +49,86 @@ impl TransactionBuilder {
         &self,
         instructions: Vec<Instruction>,
         signers: &[&Keypair],
+    ) -> Result<Signature> {
+        self.send_with_compute_limit(instructions, signers, DEFAULT_COMPUTE_UNITS)
+            .await
+    }
+
+    /// Split `entries` into as few batch instructions as fit, one per
+    /// transaction, and send them in order. `build` turns a slice of entries
+    /// into the caller program's instruction that invokes `batch_lock` or
+    /// `batch_unlock`. Each transaction is all-or-nothing on-chain; an error
+    /// stops at the failing batch and earlier batches stay applied.
+    pub async fn send_batches<F>(
+        &self,
+        entries: &[BatchEntry],
+        build: F,
+        signers: &[&Keypair],
+    ) -> Result<Vec<Signature>>
+    where
+        F: Fn(&[BatchEntry]) -> Instruction,
+    {
+        let payer = signers
+            .first()
+            .ok_or_else(|| VaultServiceError::InternalError("No payer".to_string()))?
+            .pubkey();
+
+        let mut signatures = Vec::new();
+        for batch in Self::pack_batches(&payer, entries, &build)? {
+            let compute_units = batch_compute_units(batch.len());
+            let signature = self
+                .send_with_compute_limit(vec![build(batch)], signers, compute_units)
+                .await?;
+            signatures.push(signature);
+        }
+
+        log::info!(
+            "Sent {} entries in {} batch transactions",
+            entries.len(),
+            signatures.len()
+        );
+        Ok(signatures)
+    }
+
+    /// Greedily split `entries` into consecutive batches, each as large as
+    /// the compute limit and the transaction size limit allow
+    pub fn pack_batches<'a, F>(
+        payer: &Pubkey,
+        entries: &'a [BatchEntry],
+        build: &F,
+    ) -> Result<Vec<&'a [BatchEntry]>>
+    where
+        F: Fn(&[BatchEntry]) -> Instruction,
+    {
+        let mut batches = Vec::new();
+        let mut start = 0;
+        while start < entries.len() {
+            let mut end = start + 1;
+            if !batch_fits(payer, &build(&entries[start..end]), 1) {
+                return Err(VaultServiceError::TransactionFailed(
+                    "A single batch entry does not fit in a transaction".to_string(),
+                ));
+            }
+            while end < entries.len()
+                && batch_fits(payer, &build(&entries[start..=end]), end + 1 - start)
+            {
+                end += 1;
+            }
+            batches.push(&entries[start..end]);
+            start = end;
+        }
+        Ok(batches)
+    }
+
+    /// Send `instructions` in one transaction, requesting `compute_units`
+    async fn send_with_compute_limit(
+        &self,
+        instructions: Vec<Instruction>,
+        signers: &[&Keypair],
+        compute_units: u32,
     ) -> Result<Signature> {
         // Add compute budget instructions
-        let mut all_instructions = vec![
-            ComputeBudgetInstruction::set_compute_unit_limit(300_000),
-            ComputeBudgetInstruction::set_compute_unit_price(1),
-        ];
+        let mut all_instructions = compute_budget_instructions(compute_units).to_vec();
         all_instructions.extend(instructions);
 
         // Get recent blockhash
@@ -125,3 +222,33 @@ impl TransactionBuilder {
         ))
     }
 }
+
+fn compute_budget_instructions(compute_units: u32) -> [Instruction; 2] {
+    [
+        ComputeBudgetInstruction::set_compute_unit_limit(compute_units),
+        ComputeBudgetInstruction::set_compute_unit_price(1),
+    ]
+}
+
+fn batch_compute_units(entries: usize) -> u32 {
+    BATCH_ENTRY_COMPUTE_UNITS
+        .saturating_mul(entries as u32)
+        .saturating_add(BATCH_BASE_COMPUTE_UNITS)
+}
+
+/// Whether a transaction carrying `instruction` (a batch of `entries`) and the
+/// compute budget instructions stays within the compute and packet size limits
+fn batch_fits(payer: &Pubkey, instruction: &Instruction, entries: usize) -> bool {
+    let compute_units = batch_compute_units(entries);
+    if compute_units > MAX_COMPUTE_UNITS {
+        return false;
+    }
+
+    let mut instructions = compute_budget_instructions(compute_units).to_vec();
+    instructions.push(instruction.clone());
+    let message = Message::new(&instructions, Some(payer));
+
+    // Signatures are prefixed by a one-byte compact-u16 count
+    let signatures = 1 + 64 * message.header.num_required_signatures as usize;
+    signatures + message.serialize().len() <= PACKET_DATA_SIZE
+}
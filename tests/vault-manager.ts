@@ -11,6 +11,7 @@ import {
   TOKEN_PROGRAM_ID, 
   ASSOCIATED_TOKEN_PROGRAM_ID,
   createMint,
+  getAssociatedTokenAddressSync,
   getOrCreateAssociatedTokenAccount,
   mintTo,
 } from "@solana/spl-token";
@@ -30,6 +31,12 @@ describe("vault-manager", () => {
   let vaultTokenAccount: PublicKey;
   let authorityPda: PublicKey;
   let authorityBump: number;
+  let countersPda: PublicKey;
+  let treasuryTokenAccount: PublicKey;
+  let yieldIndexPda: PublicKey;
+  let yieldReserve: PublicKey;
+  let programLockPda: PublicKey;
+  let authorizedProgramPda: PublicKey;
 
   before(async () => {
     // Create test user
@@ -74,9 +81,14 @@ describe("vault-manager", () => {
 
     console.log("User Token Account:", userTokenAccount.toBase58());
 
-    // Derive PDAs
+    // Derive PDAs (the user's first sub-account, index 0)
     [vaultPda, vaultBump] = PublicKey.findProgramAddressSync(
-      [Buffer.from("vault"), user.publicKey.toBuffer()],
+      [
+        Buffer.from("vault"),
+        user.publicKey.toBuffer(),
+        usdtMint.toBuffer(),
+        new anchor.BN(0).toArrayLike(Buffer, "le", 2),
+      ],
       program.programId
     );
 
@@ -85,6 +97,42 @@ describe("vault-manager", () => {
       program.programId
     );
 
+    [countersPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("protocol_counters")],
+      program.programId
+    );
+
+    // Treasury token account that collects the protocol fee in USDT
+    const [treasuryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("treasury")],
+      program.programId
+    );
+    const treasuryTokenAccountInfo = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      user,
+      usdtMint,
+      treasuryPda,
+      true // allow an off-curve (PDA) owner
+    );
+    treasuryTokenAccount = treasuryTokenAccountInfo.address;
+
+    // Yield index of USDT and the reserve holding its undistributed yield
+    [yieldIndexPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("yield_index"), usdtMint.toBuffer()],
+      program.programId
+    );
+    yieldReserve = getAssociatedTokenAddressSync(usdtMint, yieldIndexPda, true);
+
+    [programLockPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("program_lock"), vaultPda.toBuffer(), program.programId.toBuffer()],
+      program.programId
+    );
+
+    [authorizedProgramPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("authorized_program"), program.programId.toBuffer()],
+      program.programId
+    );
+
     console.log("Vault PDA:", vaultPda.toBase58());
     console.log("Authority PDA:", authorityPda.toBase58());
   });
@@ -95,6 +143,7 @@ describe("vault-manager", () => {
       .accounts({
         admin: provider.wallet.publicKey,
         authority: authorityPda,
+        counters: countersPda,
         systemProgram: SystemProgram.programId,
       })
       .rpc();
@@ -107,9 +156,29 @@ describe("vault-manager", () => {
     );
   });
 
+  it("Initializes the USDT yield index", async () => {
+    await program.methods
+      .initializeYieldIndex(false)
+      .accounts({
+        admin: provider.wallet.publicKey,
+        authority: authorityPda,
+        yieldIndex: yieldIndexPda,
+        yieldReserve: yieldReserve,
+        mint: usdtMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    const yieldIndex = await program.account.yieldIndex.fetch(yieldIndexPda);
+    expect(yieldIndex.index.toNumber()).to.equal(1e9);
+    expect(yieldIndex.includeLocked).to.equal(false);
+  });
+
   it("Initializes user vault", async () => {
     const tx = await program.methods
-      .initializeVault()
+      .initializeVault(0)
       .accounts({
         user: user.publicKey,
         vault: vaultPda,
@@ -126,6 +195,7 @@ describe("vault-manager", () => {
 
     const vaultAccount = await program.account.collateralVault.fetch(vaultPda);
     expect(vaultAccount.owner.toBase58()).to.equal(user.publicKey.toBase58());
+    expect(vaultAccount.subAccount).to.equal(0);
     expect(vaultAccount.totalBalance.toNumber()).to.equal(0);
   });
 
@@ -139,7 +209,11 @@ describe("vault-manager", () => {
         vault: vaultPda,
         userTokenAccount: userTokenAccount,
         vaultTokenAccount: vaultTokenAccount,
+        authority: authorityPda,
+        counters: countersPda,
+        yieldIndex: yieldIndexPda,
         owner: user.publicKey,
+        mint: usdtMint,
         tokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([user])
@@ -152,40 +226,59 @@ describe("vault-manager", () => {
     expect(vaultAccount.availableBalance.toNumber()).to.equal(depositAmount.toNumber());
   });
 
-  it("Locks collateral", async () => {
+  it("Rejects lock from a caller that is not an authorized program", async () => {
     const lockAmount = new anchor.BN(500 * 1e6); // 500 USDT
 
-    const tx = await program.methods
-      .lockCollateral(lockAmount)
-      .accounts({
-        vault: vaultPda,
-        authority: authorityPda,
-      })
-      .rpc();
+    // Lock/unlock must be signed by an authorized program's caller PDA via CPI;
+    // a plain wallet signer is not accepted.
+    try {
+      await program.methods
+        .lockCollateral(lockAmount)
+        .accounts({
+          vault: vaultPda,
+          authority: authorityPda,
+          yieldIndex: yieldIndexPda,
+          callerProgram: program.programId,
+          callerAuthority: user.publicKey,
+          authorizedProgram: authorizedProgramPda,
+          programLock: programLockPda,
+          payer: user.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc();
 
-    console.log("Lock collateral tx:", tx);
+      expect.fail("Should have thrown an error");
+    } catch (error) {
+      expect(error.toString()).to.include("UnauthorizedProgram");
+    }
 
     const vaultAccount = await program.account.collateralVault.fetch(vaultPda);
-    expect(vaultAccount.lockedBalance.toNumber()).to.equal(lockAmount.toNumber());
-    expect(vaultAccount.availableBalance.toNumber()).to.equal(500 * 1e6);
+    expect(vaultAccount.lockedBalance.toNumber()).to.equal(0);
   });
 
-  it("Unlocks collateral", async () => {
+  it("Rejects unlock from a caller that is not an authorized program", async () => {
     const unlockAmount = new anchor.BN(200 * 1e6); // 200 USDT
 
-    const tx = await program.methods
-      .unlockCollateral(unlockAmount)
-      .accounts({
-        vault: vaultPda,
-        authority: authorityPda,
-      })
-      .rpc();
-
-    console.log("Unlock collateral tx:", tx);
+    try {
+      await program.methods
+        .unlockCollateral(unlockAmount)
+        .accounts({
+          vault: vaultPda,
+          authority: authorityPda,
+          yieldIndex: yieldIndexPda,
+          callerProgram: program.programId,
+          callerAuthority: user.publicKey,
+          authorizedProgram: authorizedProgramPda,
+          programLock: programLockPda,
+        })
+        .signers([user])
+        .rpc();
 
-    const vaultAccount = await program.account.collateralVault.fetch(vaultPda);
-    expect(vaultAccount.lockedBalance.toNumber()).to.equal(300 * 1e6);
-    expect(vaultAccount.availableBalance.toNumber()).to.equal(700 * 1e6);
+      expect.fail("Should have thrown an error");
+    } catch (error) {
+      expect(error.toString()).to.include("UnauthorizedProgram");
+    }
   });
 
   it("Withdraws collateral", async () => {
@@ -198,7 +291,13 @@ describe("vault-manager", () => {
         vault: vaultPda,
         userTokenAccount: userTokenAccount,
         vaultTokenAccount: vaultTokenAccount,
+        authority: authorityPda,
+        counters: countersPda,
+        treasuryTokenAccount: treasuryTokenAccount,
+        yieldIndex: yieldIndexPda,
+        yieldReserve: yieldReserve,
         owner: user.publicKey,
+        mint: usdtMint,
         tokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([user])
@@ -222,7 +321,13 @@ describe("vault-manager", () => {
           vault: vaultPda,
           userTokenAccount: userTokenAccount,
           vaultTokenAccount: vaultTokenAccount,
+          authority: authorityPda,
+          counters: countersPda,
+          treasuryTokenAccount: treasuryTokenAccount,
+          yieldIndex: yieldIndexPda,
+          yieldReserve: yieldReserve,
           owner: user.publicKey,
+          mint: usdtMint,
           tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([user])